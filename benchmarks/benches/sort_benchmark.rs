@@ -1,9 +1,6 @@
-#![cfg_attr(feature = "nightly", feature(portable_simd))]
-
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
 use paste::paste;
 use rand::{distributions::Standard, prelude::Distribution, rngs::StdRng, Rng, SeedableRng};
-use simd_sort;
 
 pub fn create_uniform_data<T>(size: usize) -> Vec<T>
 where
@@ -107,6 +104,32 @@ macro_rules! avx2_sort {
     };
 }
 
+macro_rules! sse_sort {
+    ($c: ident, $ty: ident, $name: literal, $sort_fn: ident) => {
+        paste! {
+            #[cfg(target_arch = "x86_64")]
+            {
+                if std::is_x86_feature_detected!("sse4.2") {
+                    let data = create_uniform_data::<$ty>(1024 * 1024);
+                    use simd_sort::platform::x86::sse::$sort_fn;
+                    let data_t = data.clone();
+                    $c.bench_function($name, move |b| {
+                        // This will avoid timing the to_vec call.
+                        b.iter_batched(
+                            || data_t.clone(),
+                            |mut data| {
+                                $sort_fn(data.as_mut_slice());
+                                black_box(data);
+                            },
+                            BatchSize::LargeInput,
+                        )
+                    });
+                }
+            }
+        }
+    };
+}
+
 macro_rules! cpp_avx512_qsort {
     ($c: ident, $ty: ident, $name: literal, $sort_fn: ident) => {
         paste! {
@@ -168,6 +191,27 @@ macro_rules! avx512_sort {
     };
 }
 
+macro_rules! small_sort {
+    ($c: ident, $feature: tt, $name: literal, $sort_fn: path) => {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if std::is_x86_feature_detected!($feature) {
+                let data_t = create_uniform_data::<i64>(256);
+                $c.bench_function($name, move |b| {
+                    b.iter_batched(
+                        || data_t.clone(),
+                        |mut data| {
+                            $sort_fn(data.as_mut_slice());
+                            black_box(data);
+                        },
+                        BatchSize::SmallInput,
+                    )
+                });
+            }
+        }
+    };
+}
+
 pub fn criterion_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("sort benchmarks");
     group.throughput(Throughput::Bytes(1024 * 1024 * 8));
@@ -183,6 +227,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     avx2_sort!(group, i64, "avx2_i64", avx2_sort_i64);
     avx2_sort!(group, f64, "avx2_f64", avx2_sort_f64);
 
+    sse_sort!(group, i64, "sse_i64", sse_sort_i64);
+    sse_sort!(group, u64, "sse_u64", sse_sort_u64);
+    sse_sort!(group, f64, "sse_f64", sse_sort_f64);
+
     cpp_avx512_qsort!(group, i64, "cpp_avx512_qsort_i64", slice_sort_unstable);
     cpp_avx512_qsort!(group, u64, "cpp_avx512_qsort_u64", slice_sort_unstable);
     cpp_avx512_qsort!(group, f64, "cpp_avx512_qsort_f64", slice_sort_unstable_by);
@@ -241,7 +289,99 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             )
         });
     }
+
+    #[cfg(feature = "nightly")]
+    {
+        use simd_sort::platform::nightly::portable_simd_sort_u64;
+        let data_t = create_uniform_data::<u64>(1024 * 1024);
+        group.bench_function("portable_simd_sort_u64", move |b| {
+            // This will avoid timing the to_vec call.
+            b.iter_batched(
+                || data_t.clone(),
+                |mut data| {
+                    portable_simd_sort_u64(data.as_mut_slice());
+                    black_box(data);
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    #[cfg(feature = "nightly")]
+    {
+        use simd_sort::platform::nightly::portable_simd_sort_f64;
+        let data_t = create_uniform_data::<f64>(1024 * 1024);
+        group.bench_function("portable_simd_sort_f64", move |b| {
+            // This will avoid timing the to_vec call.
+            b.iter_batched(
+                || data_t.clone(),
+                |mut data| {
+                    portable_simd_sort_f64(data.as_mut_slice());
+                    black_box(data);
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
 }
 
-criterion_group!(benches, criterion_benchmark);
+// 256 values are sorted by the bitonic networks alone, so these track the
+// cost of the in-register blends and shuffles
+pub fn small_sort_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small sort benchmarks");
+    group.throughput(Throughput::Bytes(256 * 8));
+
+    small_sort!(
+        group,
+        "sse4.2",
+        "sse_256_i64",
+        simd_sort::platform::x86::sse::sse_sort_i64
+    );
+    small_sort!(
+        group,
+        "avx2",
+        "avx2_256_i64",
+        simd_sort::platform::x86::avx2::avx2_sort_i64
+    );
+    small_sort!(
+        group,
+        "avx512f",
+        "avx512_256_i64",
+        simd_sort::platform::x86::avx512::avx512_sort_i64
+    );
+}
+
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    small_sort_benchmark,
+    byte_sort_benchmark
+);
 criterion_main!(benches);
+
+// bytes are sorted by counting them, so these track the histogram and the
+// filling of the runs
+pub fn byte_sort_benchmark(c: &mut Criterion) {
+    use simd_sort::platform::{sort_u8_with, Backend, BACKENDS_8BIT};
+
+    let mut group = c.benchmark_group("byte sort benchmarks");
+    group.throughput(Throughput::Bytes(1024 * 1024));
+
+    let data = create_uniform_data::<u8>(1024 * 1024);
+    for &backend in BACKENDS_8BIT.iter().chain([&Backend::Std]) {
+        if !backend.is_available() {
+            continue;
+        }
+        let data_t = data.clone();
+        group.bench_function(format!("{}_u8", backend.name()), move |b| {
+            b.iter_batched(
+                || data_t.clone(),
+                |mut data| {
+                    sort_u8_with(backend, data.as_mut_slice());
+                    black_box(data);
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
@@ -0,0 +1,421 @@
+use std::{array, cmp};
+
+use crate::{bit_64::Bit64Simd, SimdCompare};
+
+/// Registers of 8 keys of 128 bits, split into the high and the low halves.
+///
+/// Keys compare by `hi` first and then by `lo`. Both halves are stored such
+/// that the signed compare of the `i64` backends orders them, so unsigned
+/// halves have their sign bit flipped.
+#[derive(Debug, Clone, Copy)]
+struct Pair<U> {
+    hi: U,
+    lo: U,
+}
+
+impl<U> Pair<U>
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    #[inline(always)]
+    fn set(key: (i64, i64)) -> Self {
+        Pair {
+            hi: U::set(key.0),
+            lo: U::set(key.1),
+        }
+    }
+
+    #[inline(always)]
+    fn loadu(hi: &[i64], lo: &[i64]) -> Self {
+        Pair {
+            hi: U::loadu(hi),
+            lo: U::loadu(lo),
+        }
+    }
+
+    #[inline(always)]
+    fn storeu(self, hi: &mut [i64], lo: &mut [i64]) {
+        U::storeu(self.hi, hi);
+        U::storeu(self.lo, lo);
+    }
+
+    /// Lanes whose key in `a` is below the one in `b`
+    #[inline(always)]
+    fn lt(a: Self, b: Self) -> U::OPMask {
+        let hi_lt = U::not_mask(U::ge(a.hi, b.hi));
+        let lo_lt = U::not_mask(U::ge(a.lo, b.lo));
+        U::or_mask(hi_lt, U::and_mask(U::eq(a.hi, b.hi), lo_lt))
+    }
+
+    /// Lexicographic compare and exchange, returns the smaller and the
+    /// larger key of every lane
+    #[inline(always)]
+    fn min_max(a: Self, b: Self) -> (Self, Self) {
+        let b_lt_a = Self::lt(b, a);
+        let min = Pair {
+            hi: U::blend(b_lt_a, a.hi, b.hi),
+            lo: U::blend(b_lt_a, a.lo, b.lo),
+        };
+        let max = Pair {
+            hi: U::blend(b_lt_a, b.hi, a.hi),
+            lo: U::blend(b_lt_a, b.lo, a.lo),
+        };
+        (min, max)
+    }
+
+    #[inline(always)]
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Pair {
+            hi: U::mask_mov::<MASK>(a.hi, b.hi),
+            lo: U::mask_mov::<MASK>(a.lo, b.lo),
+        }
+    }
+
+    /// Applies the same lane permutation to both halves
+    #[inline(always)]
+    fn permute(self, f: fn(U) -> U) -> Self {
+        Pair {
+            hi: f(self.hi),
+            lo: f(self.lo),
+        }
+    }
+}
+
+#[inline(always)]
+fn cmp_merge<U, const MASK: u64>(in1: Pair<U>, in2: Pair<U>) -> Pair<U>
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    let (min, max) = Pair::min_max(in2, in1);
+    Pair::mask_mov::<MASK>(min, max) // 0 -> min, 1 -> max
+}
+
+/// Same network as `sort_zmm_64bit` with the lexicographic compare
+#[inline(always)]
+fn sort_vec<U>(mut zmm: Pair<U>) -> Pair<U>
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    zmm = cmp_merge::<U, 0xAA>(zmm, zmm.permute(U::shuffle1_1_1_1));
+    zmm = cmp_merge::<U, 0xCC>(zmm, zmm.permute(U::network64bit1));
+    zmm = cmp_merge::<U, 0xAA>(zmm, zmm.permute(U::shuffle1_1_1_1));
+    zmm = cmp_merge::<U, 0xF0>(zmm, zmm.permute(U::network64bit2));
+    zmm = cmp_merge::<U, 0xCC>(zmm, zmm.permute(U::network64bit3));
+    zmm = cmp_merge::<U, 0xAA>(zmm, zmm.permute(U::shuffle1_1_1_1));
+    zmm
+}
+
+// Assumes zmm is bitonic and performs a recursive half cleaner
+#[inline(always)]
+fn merge_vec<U>(mut zmm: Pair<U>) -> Pair<U>
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    zmm = cmp_merge::<U, 0xF0>(zmm, zmm.permute(U::network64bit4));
+    zmm = cmp_merge::<U, 0xCC>(zmm, zmm.permute(U::network64bit3));
+    zmm = cmp_merge::<U, 0xAA>(zmm, zmm.permute(U::shuffle1_1_1_1));
+    zmm
+}
+
+/*
+ * Assumes both halves of regs are sorted and merges them, see
+ * `bitonic_merge_registers` of the 64-bit networks.
+ */
+#[inline(always)]
+fn bitonic_merge_registers<U>(regs: &mut [Pair<U>])
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    let n = regs.len();
+    for i in 0..n / 2 {
+        let reversed = regs[n - 1 - i].permute(U::network64bit2);
+        let (min, max) = Pair::min_max(regs[i], reversed);
+        regs[i] = min;
+        regs[n - 1 - i] = max.permute(U::network64bit2);
+    }
+    let mut distance = n / 4;
+    while distance > 0 {
+        for i in 0..n {
+            if i & distance == 0 {
+                let (min, max) = Pair::min_max(regs[i], regs[i + distance]);
+                regs[i] = min;
+                regs[i + distance] = max;
+            }
+        }
+        distance /= 2;
+    }
+    for reg in regs.iter_mut() {
+        *reg = merge_vec(*reg);
+    }
+}
+
+/// Sorts up to `R * 8` keys using `R` register pairs
+#[inline(always)]
+fn sort_registers<U, const R: usize>(hi: &mut [i64], lo: &mut [i64])
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    let len = hi.len();
+    debug_assert!(len <= R * 8);
+    let chunk = |i: usize| cmp::min(i * 8, len)..cmp::min((i + 1) * 8, len);
+
+    let mut regs: [Pair<U>; R] = array::from_fn(|i| {
+        let range = chunk(i);
+        if range.len() == 8 {
+            Pair::loadu(&hi[range.clone()], &lo[range])
+        } else {
+            // padding keys are the largest key and end up at the back
+            Pair {
+                hi: U::mask_loadu(&hi[range.clone()]),
+                lo: U::mask_loadu(&lo[range]),
+            }
+        }
+    });
+    for reg in regs.iter_mut() {
+        *reg = sort_vec(*reg);
+    }
+    let mut width = 2;
+    while width <= R {
+        for run in regs.chunks_mut(width) {
+            bitonic_merge_registers(run);
+        }
+        width *= 2;
+    }
+    for (i, reg) in regs.into_iter().enumerate() {
+        let range = chunk(i);
+        if range.len() == 8 {
+            reg.storeu(&mut hi[range.clone()], &mut lo[range]);
+        } else {
+            U::mask_storeu(reg.hi, &mut hi[range.clone()]);
+            U::mask_storeu(reg.lo, &mut lo[range]);
+        }
+    }
+}
+
+/// Sorts up to 128 keys with bitonic networks
+#[inline(always)]
+fn bitonic_sort<U>(hi: &mut [i64], lo: &mut [i64])
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    let len = hi.len();
+    if len <= 8 {
+        sort_registers::<U, 1>(hi, lo);
+    } else if len <= 16 {
+        sort_registers::<U, 2>(hi, lo);
+    } else if len <= 32 {
+        sort_registers::<U, 4>(hi, lo);
+    } else if len <= 64 {
+        sort_registers::<U, 8>(hi, lo);
+    } else {
+        sort_registers::<U, 16>(hi, lo);
+    }
+}
+
+/// Sorts the keys of `hi` and `lo` without simd
+pub(crate) fn sort_128bit_std(hi: &mut [i64], lo: &mut [i64]) {
+    let mut keys: Vec<(i64, i64)> = hi.iter().copied().zip(lo.iter().copied()).collect();
+    keys.sort_unstable();
+    for (i, (key_hi, key_lo)) in keys.into_iter().enumerate() {
+        hi[i] = key_hi;
+        lo[i] = key_lo;
+    }
+}
+
+#[inline(always)]
+fn get_pivot<U>(hi: &[i64], lo: &[i64]) -> (i64, i64)
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    // median of 8
+    let size = (hi.len() - 1) / 8;
+    let rand_index = array::from_fn(|i| (i + 1) * size);
+    let sorted = sort_vec(Pair::<U> {
+        hi: U::gather_from_idx(rand_index, hi),
+        lo: U::gather_from_idx(rand_index, lo),
+    });
+    (
+        U::get_value_at_idx(sorted.hi, 4),
+        U::get_value_at_idx(sorted.lo, 4),
+    )
+}
+
+/*
+ * Partitions one register pair and stores the keys below the pivot at `left`
+ * and the others before `right`, returns how many keys are not below.
+ */
+#[inline(always)]
+fn partition_vec<U>(
+    hi: &mut [i64],
+    lo: &mut [i64],
+    left: usize,
+    right: usize,
+    curr_vec: Pair<U>,
+    pivot_vec: Pair<U>,
+) -> usize
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    let lt_mask = Pair::lt(curr_vec, pivot_vec);
+    let ge_mask = U::not_mask(lt_mask);
+    let amount_ge_pivot = U::ones_count(ge_mask);
+    U::mask_compressstoreu(&mut hi[left..], lt_mask, curr_vec.hi);
+    U::mask_compressstoreu(&mut lo[left..], lt_mask, curr_vec.lo);
+    U::mask_compressstoreu(&mut hi[right - amount_ge_pivot..], ge_mask, curr_vec.hi);
+    U::mask_compressstoreu(&mut lo[right - amount_ge_pivot..], ge_mask, curr_vec.lo);
+    amount_ge_pivot
+}
+
+/// Moves the keys below `pivot` to the front and returns how many there are,
+/// works like `partition_avx512` on register pairs
+#[inline(always)]
+fn partition<U>(hi: &mut [i64], lo: &mut [i64], pivot: (i64, i64)) -> usize
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    /* make array length divisible by 8, shortening the array */
+    let mut left = 0;
+    let mut right = hi.len();
+    for _ in 0..right % 8 {
+        if (hi[left], lo[left]) < pivot {
+            left += 1;
+        } else {
+            right -= 1;
+            hi.swap(left, right);
+            lo.swap(left, right);
+        }
+    }
+    if left == right {
+        return left;
+    }
+
+    let pivot_vec = Pair::<U>::set(pivot);
+    if right - left == 8 {
+        let vec = Pair::loadu(&hi[left..], &lo[left..]);
+        let amount_ge_pivot = partition_vec(hi, lo, left, left + 8, vec, pivot_vec);
+        return left + (8 - amount_ge_pivot);
+    }
+
+    // first and last 8 keys are partitioned at the end
+    let vec_left = Pair::loadu(&hi[left..], &lo[left..]);
+    let vec_right = Pair::loadu(&hi[right - 8..], &lo[right - 8..]);
+    let mut r_end = right;
+    let mut l_store = left;
+    left += 8;
+    right -= 8;
+    while right != left {
+        let curr_vec;
+        if r_end - right < left - l_store {
+            right -= 8;
+            curr_vec = Pair::loadu(&hi[right..], &lo[right..]);
+        } else {
+            curr_vec = Pair::loadu(&hi[left..], &lo[left..]);
+            left += 8;
+        }
+        let amount_ge_pivot = partition_vec(hi, lo, l_store, r_end, curr_vec, pivot_vec);
+        r_end -= amount_ge_pivot;
+        l_store += 8 - amount_ge_pivot;
+    }
+
+    let amount_ge_pivot = partition_vec(hi, lo, l_store, r_end, vec_left, pivot_vec);
+    l_store += 8 - amount_ge_pivot;
+    let amount_ge_pivot = partition_vec(hi, lo, l_store, l_store + 8, vec_right, pivot_vec);
+    l_store + 8 - amount_ge_pivot
+}
+
+/// Returns the smallest key above `key`
+fn successor(key: (i64, i64)) -> Option<(i64, i64)> {
+    match key {
+        (hi, i64::MAX) => hi.checked_add(1).map(|hi| (hi, i64::MIN)),
+        (hi, lo) => Some((hi, lo + 1)),
+    }
+}
+
+/// Sorts the keys whose halves are `hi[i]` and `lo[i]`.
+///
+/// Like `qsort_64bit_` the parts still to sort wait on a stack, so the sort
+/// inlines into a caller compiled with the target features of `U`.
+#[inline(always)]
+pub(crate) fn qsort_128bit_<U>(hi: &mut [i64], lo: &mut [i64], max_iters: i64)
+where
+    U: SimdCompare<i64, 8> + Bit64Simd<i64>,
+{
+    debug_assert_eq!(hi.len(), lo.len());
+    let mut pending = Vec::new();
+    let mut next = Some((0, hi.len(), max_iters));
+    while let Some((start, end, max_iters)) = next.take().or_else(|| pending.pop()) {
+        let (hi, lo) = (&mut hi[start..end], &mut lo[start..end]);
+        /*
+         * Resort to std::sort if quicksort isnt making any progress
+         */
+        if max_iters <= 0 {
+            sort_128bit_std(hi, lo);
+            continue;
+        }
+        if hi.len() <= 16 * 8 {
+            bitonic_sort::<U>(hi, lo);
+            continue;
+        }
+
+        let pivot = get_pivot::<U>(hi, lo);
+        let pivot_index = partition::<U>(hi, lo, pivot);
+        if pivot_index == 0 {
+            // the pivot is the smallest key, split off the keys equal to it
+            // instead since they are already in place
+            if let Some(key) = successor(pivot) {
+                let pivot_index = start + partition::<U>(hi, lo, key);
+                next = Some((pivot_index, end, max_iters - 1));
+            }
+            continue;
+        }
+        pending.push((start + pivot_index, end, max_iters - 1));
+        next = Some((start, start + pivot_index, max_iters - 1));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{partition, qsort_128bit_, successor};
+    use crate::bit_64::test::random_u64;
+
+    fn random_keys(len: usize, modulo: u64) -> (Vec<i64>, Vec<i64>) {
+        random_u64(2 * len)
+            .chunks(2)
+            .map(|pair| ((pair[0] % modulo) as i64, (pair[1] % modulo) as i64))
+            .unzip()
+    }
+
+    #[test]
+    fn test_successor() {
+        assert_eq!(successor((3, 4)), Some((3, 5)));
+        assert_eq!(successor((3, i64::MAX)), Some((4, i64::MIN)));
+        assert_eq!(successor((i64::MAX, i64::MAX)), None);
+    }
+
+    #[test]
+    fn test_partition_128bit() {
+        for len in [0, 1, 7, 8, 9, 16, 17, 100, 1000] {
+            let (mut hi, mut lo) = random_keys(len, 4);
+            let pivot = (1, 2);
+            let below = partition::<[i64; 8]>(&mut hi, &mut lo, pivot);
+            for i in 0..len {
+                assert_eq!((hi[i], lo[i]) < pivot, i < below, "len {}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_qsort_128bit() {
+        for len in [0, 1, 7, 8, 9, 100, 128, 129, 1000, 10_000] {
+            for modulo in [2, 1000, u64::MAX] {
+                let (mut hi, mut lo) = random_keys(len, modulo);
+                let mut expected: Vec<(i64, i64)> =
+                    hi.iter().copied().zip(lo.iter().copied()).collect();
+                expected.sort_unstable();
+                qsort_128bit_::<[i64; 8]>(&mut hi, &mut lo, f64::log2(len as f64) as i64);
+                let sorted: Vec<(i64, i64)> = hi.into_iter().zip(lo).collect();
+                assert_eq!(sorted, expected, "len {}, modulo {}", len, modulo);
+            }
+        }
+    }
+}
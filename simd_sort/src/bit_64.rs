@@ -1,7 +1,10 @@
-use std::cmp;
+use std::{array, cmp};
 
-use crate::{cmp_merge, coex, partition_avx512_unrolled, SimdCompare, SimdSortable};
+use crate::{
+    cmp_merge, coex, partition_avx512_unrolled, platform::SetOperation, SimdCompare, SimdSortable,
+};
 
+/// Element types of 64 bits the quicksort can sort
 pub trait Bit64Element: SimdSortable {}
 
 impl Bit64Element for u64 {}
@@ -9,19 +12,16 @@ impl Bit64Element for u64 {}
 impl Bit64Element for i64 {}
 impl Bit64Element for f64 {}
 
-pub(crate) trait Bit64Simd<T: Bit64Element> {
-    //ZMM 76543210
-    //    10101010 , 0 -> first, 1 -> second
-    fn swizzle2_0xaa(a: Self, b: Self) -> Self;
-
-    //ZMM 76543210
-    //    11001100, 0 -> first, 1 -> second
-    fn swizzle2_0xcc(a: Self, b: Self) -> Self;
-
-    //ZMM 76543210
-    //    11110000, 0 -> first, 1 -> second
-    fn swizzle2_0xf0(a: Self, b: Self) -> Self;
+// pointer sized integers only have 64 bits on 64-bit targets
+#[cfg(target_pointer_width = "64")]
+impl Bit64Element for usize {}
+#[cfg(target_pointer_width = "64")]
+impl Bit64Element for isize {}
 
+/// Lane permutations of 8 lane registers used by the bitonic networks, the
+/// lanes are numbered from the right like in the comments of the intel
+/// sources.
+pub trait Bit64Simd<T: Bit64Element> {
     /// [1, 0, 3, 2, 5, 4, 7, 6]
     fn shuffle1_1_1_1(a: Self) -> Self;
 
@@ -43,1052 +43,647 @@ pub(crate) trait Bit64Simd<T: Bit64Element> {
  * Assumes zmm is random and performs a full sorting network defined in
  * https://en.wikipedia.org/wiki/Bitonic_sorter#/media/File:BitonicSort.svg
  */
-#[inline]
+#[inline(always)]
 pub(crate) fn sort_zmm_64bit<U, T>(mut zmm: T) -> T
 where
     U: Bit64Element,
     T: SimdCompare<U, 8> + Bit64Simd<U>,
 {
-    zmm = cmp_merge(zmm, T::shuffle1_1_1_1(zmm), T::swizzle2_0xaa);
-    zmm = cmp_merge(zmm, T::network64bit1(zmm), T::swizzle2_0xcc);
-    zmm = cmp_merge(zmm, T::shuffle1_1_1_1(zmm), T::swizzle2_0xaa);
-    zmm = cmp_merge(zmm, T::network64bit2(zmm), T::swizzle2_0xf0);
-    zmm = cmp_merge(zmm, T::network64bit3(zmm), T::swizzle2_0xcc);
-    zmm = cmp_merge(zmm, T::shuffle1_1_1_1(zmm), T::swizzle2_0xaa);
+    zmm = cmp_merge::<U, 8, T, 0xAA>(zmm, T::shuffle1_1_1_1(zmm));
+    zmm = cmp_merge::<U, 8, T, 0xCC>(zmm, T::network64bit1(zmm));
+    zmm = cmp_merge::<U, 8, T, 0xAA>(zmm, T::shuffle1_1_1_1(zmm));
+    zmm = cmp_merge::<U, 8, T, 0xF0>(zmm, T::network64bit2(zmm));
+    zmm = cmp_merge::<U, 8, T, 0xCC>(zmm, T::network64bit3(zmm));
+    zmm = cmp_merge::<U, 8, T, 0xAA>(zmm, T::shuffle1_1_1_1(zmm));
     zmm
 }
 
 // Assumes zmm is bitonic and performs a recursive half cleaner
-#[inline]
+#[inline(always)]
 fn bitonic_merge_zmm_64bit<U: Bit64Element, T: SimdCompare<U, 8> + Bit64Simd<U>>(mut zmm: T) -> T {
     // 1) half_cleaner[8]: compare 0-4, 1-5, 2-6, 3-7
-    zmm = cmp_merge(zmm, T::network64bit4(zmm), T::swizzle2_0xf0);
+    zmm = cmp_merge::<U, 8, T, 0xF0>(zmm, T::network64bit4(zmm));
     // 2) half_cleaner[4]
-    zmm = cmp_merge(zmm, T::network64bit3(zmm), T::swizzle2_0xcc);
+    zmm = cmp_merge::<U, 8, T, 0xCC>(zmm, T::network64bit3(zmm));
     // 3) half_cleaner[1]
-    zmm = cmp_merge(zmm, T::shuffle1_1_1_1(zmm), T::swizzle2_0xaa);
+    zmm = cmp_merge::<U, 8, T, 0xAA>(zmm, T::shuffle1_1_1_1(zmm));
     zmm
 }
 
-// Assumes zmm1 and zmm2 are sorted and performs a recursive half cleaner
-#[inline]
-fn bitonic_merge_two_zmm_64bit<U: Bit64Element, T: SimdCompare<U, 8> + Bit64Simd<U>>(
-    zmm1: &mut T,
-    zmm2: &mut T,
-) {
-    // 1) First step of a merging network: coex of zmm1 and zmm2 reversed
-    *zmm2 = T::network64bit2(*zmm2);
-    let zmm3 = T::min(*zmm1, *zmm2);
-    let zmm4 = T::max(*zmm1, *zmm2);
-    // 2) Recursive half cleaner for each
-    *zmm1 = bitonic_merge_zmm_64bit(zmm3);
-    *zmm2 = bitonic_merge_zmm_64bit(zmm4);
-}
+/// In-register bitonic primitives of an `N` lane vector. The multi-register
+/// networks and the quicksort driver are built on top of these, so a backend
+/// only needs the shuffles of its native register width. Register types of 8
+/// lanes get them from [`Bit64Simd`].
+pub trait BitonicNetwork<T: Bit64Element, const N: usize>: SimdCompare<T, N> {
+    /// Reverses the order of the lanes
+    fn reverse(a: Self) -> Self;
 
-// Assumes [zmm0, zmm1] and [zmm2, zmm3] are sorted and performs a recursive
-// half cleaner
-#[inline]
-fn bitonic_merge_four_zmm_64bit<U: Bit64Element, T: SimdCompare<U, 8> + Bit64Simd<U>>(
-    zmm: &mut [T],
-) {
-    // 1) First step of a merging network
-    let zmm2r = T::network64bit2(zmm[2]);
-    let zmm3r = T::network64bit2(zmm[3]);
-    let zmm_t1 = T::min(zmm[0], zmm3r);
-    let zmm_t2 = T::min(zmm[1], zmm2r);
-    // 2) Recursive half clearer: 16
-    let zmm_t3 = T::network64bit2(T::max(zmm[1], zmm2r));
-    let zmm_t4 = T::network64bit2(T::max(zmm[0], zmm3r));
-    let zmm0 = T::min(zmm_t1, zmm_t2);
-    let zmm1 = T::max(zmm_t1, zmm_t2);
-    let zmm2 = T::min(zmm_t3, zmm_t4);
-    let zmm3 = T::max(zmm_t3, zmm_t4);
-    zmm[0] = bitonic_merge_zmm_64bit(zmm0);
-    zmm[1] = bitonic_merge_zmm_64bit(zmm1);
-    zmm[2] = bitonic_merge_zmm_64bit(zmm2);
-    zmm[3] = bitonic_merge_zmm_64bit(zmm3);
-}
+    /// Sorts a vector holding random values, the smallest value goes to
+    /// lane 0
+    fn sort_vec(a: Self) -> Self;
 
-#[inline]
-fn bitonic_merge_eight_zmm_64bit<U: Bit64Element, T: SimdCompare<U, 8> + Bit64Simd<U>>(
-    zmm: &mut [T],
-) {
-    let zmm4r = T::network64bit2(zmm[4]);
-    let zmm5r = T::network64bit2(zmm[5]);
-    let zmm6r = T::network64bit2(zmm[6]);
-    let zmm7r = T::network64bit2(zmm[7]);
-    let mut zmm_t1 = T::min(zmm[0], zmm7r);
-    let mut zmm_t2 = T::min(zmm[1], zmm6r);
-    let mut zmm_t3 = T::min(zmm[2], zmm5r);
-    let mut zmm_t4 = T::min(zmm[3], zmm4r);
-    let mut zmm_t5 = T::network64bit2(T::max(zmm[3], zmm4r));
-    let mut zmm_t6 = T::network64bit2(T::max(zmm[2], zmm5r));
-    let mut zmm_t7 = T::network64bit2(T::max(zmm[1], zmm6r));
-    let mut zmm_t8 = T::network64bit2(T::max(zmm[0], zmm7r));
-    coex(&mut zmm_t1, &mut zmm_t3);
-    coex(&mut zmm_t2, &mut zmm_t4);
-    coex(&mut zmm_t5, &mut zmm_t7);
-    coex(&mut zmm_t6, &mut zmm_t8);
-    coex(&mut zmm_t1, &mut zmm_t2);
-    coex(&mut zmm_t3, &mut zmm_t4);
-    coex(&mut zmm_t5, &mut zmm_t6);
-    coex(&mut zmm_t7, &mut zmm_t8);
-    zmm[0] = bitonic_merge_zmm_64bit(zmm_t1);
-    zmm[1] = bitonic_merge_zmm_64bit(zmm_t2);
-    zmm[2] = bitonic_merge_zmm_64bit(zmm_t3);
-    zmm[3] = bitonic_merge_zmm_64bit(zmm_t4);
-    zmm[4] = bitonic_merge_zmm_64bit(zmm_t5);
-    zmm[5] = bitonic_merge_zmm_64bit(zmm_t6);
-    zmm[6] = bitonic_merge_zmm_64bit(zmm_t7);
-    zmm[7] = bitonic_merge_zmm_64bit(zmm_t8);
+    /// Sorts a vector holding a bitonic sequence like [`sort_vec`](Self::sort_vec)
+    fn merge_vec(a: Self) -> Self;
 }
 
-#[inline]
-fn bitonic_merge_sixteen_zmm_64bit<U: Bit64Element, T: SimdCompare<U, 8> + Bit64Simd<U>>(
-    zmm: &mut [T],
-) {
-    let zmm8r = T::network64bit2(zmm[8]);
-    let zmm9r = T::network64bit2(zmm[9]);
-    let zmm10r = T::network64bit2(zmm[10]);
-    let zmm11r = T::network64bit2(zmm[11]);
-    let zmm12r = T::network64bit2(zmm[12]);
-    let zmm13r = T::network64bit2(zmm[13]);
-    let zmm14r = T::network64bit2(zmm[14]);
-    let zmm15r = T::network64bit2(zmm[15]);
-    let mut zmm_t1 = T::min(zmm[0], zmm15r);
-    let mut zmm_t2 = T::min(zmm[1], zmm14r);
-    let mut zmm_t3 = T::min(zmm[2], zmm13r);
-    let mut zmm_t4 = T::min(zmm[3], zmm12r);
-    let mut zmm_t5 = T::min(zmm[4], zmm11r);
-    let mut zmm_t6 = T::min(zmm[5], zmm10r);
-    let mut zmm_t7 = T::min(zmm[6], zmm9r);
-    let mut zmm_t8 = T::min(zmm[7], zmm8r);
-    let mut zmm_t9 = T::network64bit2(T::max(zmm[7], zmm8r));
-    let mut zmm_t10 = T::network64bit2(T::max(zmm[6], zmm9r));
-    let mut zmm_t11 = T::network64bit2(T::max(zmm[5], zmm10r));
-    let mut zmm_t12 = T::network64bit2(T::max(zmm[4], zmm11r));
-    let mut zmm_t13 = T::network64bit2(T::max(zmm[3], zmm12r));
-    let mut zmm_t14 = T::network64bit2(T::max(zmm[2], zmm13r));
-    let mut zmm_t15 = T::network64bit2(T::max(zmm[1], zmm14r));
-    let mut zmm_t16 = T::network64bit2(T::max(zmm[0], zmm15r));
-    // Recusive half clear 16 zmm regs
-    coex(&mut zmm_t1, &mut zmm_t5);
-    coex(&mut zmm_t2, &mut zmm_t6);
-    coex(&mut zmm_t3, &mut zmm_t7);
-    coex(&mut zmm_t4, &mut zmm_t8);
-    coex(&mut zmm_t9, &mut zmm_t13);
-    coex(&mut zmm_t10, &mut zmm_t14);
-    coex(&mut zmm_t11, &mut zmm_t15);
-    coex(&mut zmm_t12, &mut zmm_t16);
-    //
-    coex(&mut zmm_t1, &mut zmm_t3);
-    coex(&mut zmm_t2, &mut zmm_t4);
-    coex(&mut zmm_t5, &mut zmm_t7);
-    coex(&mut zmm_t6, &mut zmm_t8);
-    coex(&mut zmm_t9, &mut zmm_t11);
-    coex(&mut zmm_t10, &mut zmm_t12);
-    coex(&mut zmm_t13, &mut zmm_t15);
-    coex(&mut zmm_t14, &mut zmm_t16);
-    //
-    coex(&mut zmm_t1, &mut zmm_t2);
-    coex(&mut zmm_t3, &mut zmm_t4);
-    coex(&mut zmm_t5, &mut zmm_t6);
-    coex(&mut zmm_t7, &mut zmm_t8);
-    coex(&mut zmm_t9, &mut zmm_t10);
-    coex(&mut zmm_t11, &mut zmm_t12);
-    coex(&mut zmm_t13, &mut zmm_t14);
-    coex(&mut zmm_t15, &mut zmm_t16);
-    //
-    zmm[0] = bitonic_merge_zmm_64bit(zmm_t1);
-    zmm[1] = bitonic_merge_zmm_64bit(zmm_t2);
-    zmm[2] = bitonic_merge_zmm_64bit(zmm_t3);
-    zmm[3] = bitonic_merge_zmm_64bit(zmm_t4);
-    zmm[4] = bitonic_merge_zmm_64bit(zmm_t5);
-    zmm[5] = bitonic_merge_zmm_64bit(zmm_t6);
-    zmm[6] = bitonic_merge_zmm_64bit(zmm_t7);
-    zmm[7] = bitonic_merge_zmm_64bit(zmm_t8);
-    zmm[8] = bitonic_merge_zmm_64bit(zmm_t9);
-    zmm[9] = bitonic_merge_zmm_64bit(zmm_t10);
-    zmm[10] = bitonic_merge_zmm_64bit(zmm_t11);
-    zmm[11] = bitonic_merge_zmm_64bit(zmm_t12);
-    zmm[12] = bitonic_merge_zmm_64bit(zmm_t13);
-    zmm[13] = bitonic_merge_zmm_64bit(zmm_t14);
-    zmm[14] = bitonic_merge_zmm_64bit(zmm_t15);
-    zmm[15] = bitonic_merge_zmm_64bit(zmm_t16);
+impl<T, U> BitonicNetwork<T, 8> for U
+where
+    T: Bit64Element,
+    U: SimdCompare<T, 8> + Bit64Simd<T>,
+{
+    #[inline(always)]
+    fn reverse(a: Self) -> Self {
+        U::network64bit2(a)
+    }
+
+    #[inline(always)]
+    fn sort_vec(a: Self) -> Self {
+        sort_zmm_64bit(a)
+    }
+
+    #[inline(always)]
+    fn merge_vec(a: Self) -> Self {
+        bitonic_merge_zmm_64bit(a)
+    }
 }
 
-#[inline]
-fn bitonic_merge_thirtytwo_zmm_64bit<U: Bit64Element, T: SimdCompare<U, 8> + Bit64Simd<U>>(
-    zmm: &mut [T],
-) {
-    let zmm16r = T::network64bit2(zmm[16]);
-    let zmm17r = T::network64bit2(zmm[17]);
-    let zmm18r = T::network64bit2(zmm[18]);
-    let zmm19r = T::network64bit2(zmm[19]);
-    let zmm20r = T::network64bit2(zmm[20]);
-    let zmm21r = T::network64bit2(zmm[21]);
-    let zmm22r = T::network64bit2(zmm[22]);
-    let zmm23r = T::network64bit2(zmm[23]);
-    let zmm24r = T::network64bit2(zmm[24]);
-    let zmm25r = T::network64bit2(zmm[25]);
-    let zmm26r = T::network64bit2(zmm[26]);
-    let zmm27r = T::network64bit2(zmm[27]);
-    let zmm28r = T::network64bit2(zmm[28]);
-    let zmm29r = T::network64bit2(zmm[29]);
-    let zmm30r = T::network64bit2(zmm[30]);
-    let zmm31r = T::network64bit2(zmm[31]);
-    let mut zmm_t1 = T::min(zmm[0], zmm31r);
-    let mut zmm_t2 = T::min(zmm[1], zmm30r);
-    let mut zmm_t3 = T::min(zmm[2], zmm29r);
-    let mut zmm_t4 = T::min(zmm[3], zmm28r);
-    let mut zmm_t5 = T::min(zmm[4], zmm27r);
-    let mut zmm_t6 = T::min(zmm[5], zmm26r);
-    let mut zmm_t7 = T::min(zmm[6], zmm25r);
-    let mut zmm_t8 = T::min(zmm[7], zmm24r);
-    let mut zmm_t9 = T::min(zmm[8], zmm23r);
-    let mut zmm_t10 = T::min(zmm[9], zmm22r);
-    let mut zmm_t11 = T::min(zmm[10], zmm21r);
-    let mut zmm_t12 = T::min(zmm[11], zmm20r);
-    let mut zmm_t13 = T::min(zmm[12], zmm19r);
-    let mut zmm_t14 = T::min(zmm[13], zmm18r);
-    let mut zmm_t15 = T::min(zmm[14], zmm17r);
-    let mut zmm_t16 = T::min(zmm[15], zmm16r);
-    let mut zmm_t17 = T::network64bit2(T::max(zmm[15], zmm16r));
-    let mut zmm_t18 = T::network64bit2(T::max(zmm[14], zmm17r));
-    let mut zmm_t19 = T::network64bit2(T::max(zmm[13], zmm18r));
-    let mut zmm_t20 = T::network64bit2(T::max(zmm[12], zmm19r));
-    let mut zmm_t21 = T::network64bit2(T::max(zmm[11], zmm20r));
-    let mut zmm_t22 = T::network64bit2(T::max(zmm[10], zmm21r));
-    let mut zmm_t23 = T::network64bit2(T::max(zmm[9], zmm22r));
-    let mut zmm_t24 = T::network64bit2(T::max(zmm[8], zmm23r));
-    let mut zmm_t25 = T::network64bit2(T::max(zmm[7], zmm24r));
-    let mut zmm_t26 = T::network64bit2(T::max(zmm[6], zmm25r));
-    let mut zmm_t27 = T::network64bit2(T::max(zmm[5], zmm26r));
-    let mut zmm_t28 = T::network64bit2(T::max(zmm[4], zmm27r));
-    let mut zmm_t29 = T::network64bit2(T::max(zmm[3], zmm28r));
-    let mut zmm_t30 = T::network64bit2(T::max(zmm[2], zmm29r));
-    let mut zmm_t31 = T::network64bit2(T::max(zmm[1], zmm30r));
-    let mut zmm_t32 = T::network64bit2(T::max(zmm[0], zmm31r));
-    // Recusive half clear 16 zmm regs
-    coex(&mut zmm_t1, &mut zmm_t9);
-    coex(&mut zmm_t2, &mut zmm_t10);
-    coex(&mut zmm_t3, &mut zmm_t11);
-    coex(&mut zmm_t4, &mut zmm_t12);
-    coex(&mut zmm_t5, &mut zmm_t13);
-    coex(&mut zmm_t6, &mut zmm_t14);
-    coex(&mut zmm_t7, &mut zmm_t15);
-    coex(&mut zmm_t8, &mut zmm_t16);
-    coex(&mut zmm_t17, &mut zmm_t25);
-    coex(&mut zmm_t18, &mut zmm_t26);
-    coex(&mut zmm_t19, &mut zmm_t27);
-    coex(&mut zmm_t20, &mut zmm_t28);
-    coex(&mut zmm_t21, &mut zmm_t29);
-    coex(&mut zmm_t22, &mut zmm_t30);
-    coex(&mut zmm_t23, &mut zmm_t31);
-    coex(&mut zmm_t24, &mut zmm_t32);
-    //
-    coex(&mut zmm_t1, &mut zmm_t5);
-    coex(&mut zmm_t2, &mut zmm_t6);
-    coex(&mut zmm_t3, &mut zmm_t7);
-    coex(&mut zmm_t4, &mut zmm_t8);
-    coex(&mut zmm_t9, &mut zmm_t13);
-    coex(&mut zmm_t10, &mut zmm_t14);
-    coex(&mut zmm_t11, &mut zmm_t15);
-    coex(&mut zmm_t12, &mut zmm_t16);
-    coex(&mut zmm_t17, &mut zmm_t21);
-    coex(&mut zmm_t18, &mut zmm_t22);
-    coex(&mut zmm_t19, &mut zmm_t23);
-    coex(&mut zmm_t20, &mut zmm_t24);
-    coex(&mut zmm_t25, &mut zmm_t29);
-    coex(&mut zmm_t26, &mut zmm_t30);
-    coex(&mut zmm_t27, &mut zmm_t31);
-    coex(&mut zmm_t28, &mut zmm_t32);
-    //
-    coex(&mut zmm_t1, &mut zmm_t3);
-    coex(&mut zmm_t2, &mut zmm_t4);
-    coex(&mut zmm_t5, &mut zmm_t7);
-    coex(&mut zmm_t6, &mut zmm_t8);
-    coex(&mut zmm_t9, &mut zmm_t11);
-    coex(&mut zmm_t10, &mut zmm_t12);
-    coex(&mut zmm_t13, &mut zmm_t15);
-    coex(&mut zmm_t14, &mut zmm_t16);
-    coex(&mut zmm_t17, &mut zmm_t19);
-    coex(&mut zmm_t18, &mut zmm_t20);
-    coex(&mut zmm_t21, &mut zmm_t23);
-    coex(&mut zmm_t22, &mut zmm_t24);
-    coex(&mut zmm_t25, &mut zmm_t27);
-    coex(&mut zmm_t26, &mut zmm_t28);
-    coex(&mut zmm_t29, &mut zmm_t31);
-    coex(&mut zmm_t30, &mut zmm_t32);
-    //
-    coex(&mut zmm_t1, &mut zmm_t2);
-    coex(&mut zmm_t3, &mut zmm_t4);
-    coex(&mut zmm_t5, &mut zmm_t6);
-    coex(&mut zmm_t7, &mut zmm_t8);
-    coex(&mut zmm_t9, &mut zmm_t10);
-    coex(&mut zmm_t11, &mut zmm_t12);
-    coex(&mut zmm_t13, &mut zmm_t14);
-    coex(&mut zmm_t15, &mut zmm_t16);
-    coex(&mut zmm_t17, &mut zmm_t18);
-    coex(&mut zmm_t19, &mut zmm_t20);
-    coex(&mut zmm_t21, &mut zmm_t22);
-    coex(&mut zmm_t23, &mut zmm_t24);
-    coex(&mut zmm_t25, &mut zmm_t26);
-    coex(&mut zmm_t27, &mut zmm_t28);
-    coex(&mut zmm_t29, &mut zmm_t30);
-    coex(&mut zmm_t31, &mut zmm_t32);
-    //
-    zmm[0] = bitonic_merge_zmm_64bit(zmm_t1);
-    zmm[1] = bitonic_merge_zmm_64bit(zmm_t2);
-    zmm[2] = bitonic_merge_zmm_64bit(zmm_t3);
-    zmm[3] = bitonic_merge_zmm_64bit(zmm_t4);
-    zmm[4] = bitonic_merge_zmm_64bit(zmm_t5);
-    zmm[5] = bitonic_merge_zmm_64bit(zmm_t6);
-    zmm[6] = bitonic_merge_zmm_64bit(zmm_t7);
-    zmm[7] = bitonic_merge_zmm_64bit(zmm_t8);
-    zmm[8] = bitonic_merge_zmm_64bit(zmm_t9);
-    zmm[9] = bitonic_merge_zmm_64bit(zmm_t10);
-    zmm[10] = bitonic_merge_zmm_64bit(zmm_t11);
-    zmm[11] = bitonic_merge_zmm_64bit(zmm_t12);
-    zmm[12] = bitonic_merge_zmm_64bit(zmm_t13);
-    zmm[13] = bitonic_merge_zmm_64bit(zmm_t14);
-    zmm[14] = bitonic_merge_zmm_64bit(zmm_t15);
-    zmm[15] = bitonic_merge_zmm_64bit(zmm_t16);
-    zmm[16] = bitonic_merge_zmm_64bit(zmm_t17);
-    zmm[17] = bitonic_merge_zmm_64bit(zmm_t18);
-    zmm[18] = bitonic_merge_zmm_64bit(zmm_t19);
-    zmm[19] = bitonic_merge_zmm_64bit(zmm_t20);
-    zmm[20] = bitonic_merge_zmm_64bit(zmm_t21);
-    zmm[21] = bitonic_merge_zmm_64bit(zmm_t22);
-    zmm[22] = bitonic_merge_zmm_64bit(zmm_t23);
-    zmm[23] = bitonic_merge_zmm_64bit(zmm_t24);
-    zmm[24] = bitonic_merge_zmm_64bit(zmm_t25);
-    zmm[25] = bitonic_merge_zmm_64bit(zmm_t26);
-    zmm[26] = bitonic_merge_zmm_64bit(zmm_t27);
-    zmm[27] = bitonic_merge_zmm_64bit(zmm_t28);
-    zmm[28] = bitonic_merge_zmm_64bit(zmm_t29);
-    zmm[29] = bitonic_merge_zmm_64bit(zmm_t30);
-    zmm[30] = bitonic_merge_zmm_64bit(zmm_t31);
-    zmm[31] = bitonic_merge_zmm_64bit(zmm_t32);
+/*
+ * Assumes both halves of regs are sorted and merges them. The first step
+ * compares the first half with the reversed second half, then a recursive
+ * half cleaner runs across the registers and finally within each register.
+ */
+#[inline(always)]
+fn bitonic_merge_registers<T, const N: usize, U>(regs: &mut [U])
+where
+    T: Bit64Element,
+    U: BitonicNetwork<T, N>,
+{
+    let n = regs.len();
+    // 1) First step of a merging network
+    for i in 0..n / 2 {
+        let reversed = U::reverse(regs[n - 1 - i]);
+        let min = U::min(regs[i], reversed);
+        let max = U::max(regs[i], reversed);
+        regs[i] = min;
+        regs[n - 1 - i] = U::reverse(max);
+    }
+    // 2) Recursive half cleaner across registers
+    let mut distance = n / 4;
+    while distance > 0 {
+        for i in 0..n {
+            if i & distance == 0 {
+                let (low, high) = regs.split_at_mut(i + distance);
+                coex(&mut low[i], &mut high[0]);
+            }
+        }
+        distance /= 2;
+    }
+    // 3) Recursive half cleaner within each register
+    for reg in regs.iter_mut() {
+        *reg = U::merge_vec(*reg);
+    }
 }
 
-pub(crate) fn sort_8<T, U>(data: &mut [T])
+/// Sorts up to `R * N` values using `R` registers of `N` lanes
+#[inline(always)]
+pub(crate) fn sort_registers<T, const N: usize, U, const R: usize>(data: &mut [T])
 where
     T: Bit64Element,
-    U: Bit64Simd<T> + SimdCompare<T, 8>,
+    U: BitonicNetwork<T, N>,
 {
-    let simd = U::mask_loadu(data);
-    let zmm = sort_zmm_64bit(simd);
-    U::mask_storeu(zmm, data);
+    let len = data.len();
+    debug_assert!(len <= R * N);
+    let chunk = |i: usize| cmp::min(i * N, len)..cmp::min((i + 1) * N, len);
+
+    let mut regs: [U; R] = array::from_fn(|i| {
+        let values = &data[chunk(i)];
+        if values.len() == N {
+            U::loadu(values)
+        } else {
+            U::mask_loadu(values)
+        }
+    });
+    for reg in regs.iter_mut() {
+        *reg = U::sort_vec(*reg);
+    }
+    let mut width = 2;
+    while width <= R {
+        for run in regs.chunks_mut(width) {
+            bitonic_merge_registers(run);
+        }
+        width *= 2;
+    }
+    for (i, reg) in regs.into_iter().enumerate() {
+        let values = &mut data[chunk(i)];
+        if values.len() == N {
+            U::storeu(reg, values);
+        } else {
+            U::mask_storeu(reg, values);
+        }
+    }
 }
 
-pub(crate) fn sort_16<T, U>(data: &mut [T])
+/// Sorts up to `32 * N` values with bitonic networks
+#[inline(always)]
+pub(crate) fn bitonic_sort<T, const N: usize, U>(data: &mut [T])
 where
     T: Bit64Element,
-    U: Bit64Simd<T> + SimdCompare<T, 8>,
+    U: BitonicNetwork<T, N>,
 {
-    if data.len() <= 8 {
-        sort_8::<T, U>(data);
+    let len = data.len();
+    if len <= N {
+        sort_registers::<T, N, U, 1>(data);
+    } else if len <= 2 * N {
+        sort_registers::<T, N, U, 2>(data);
+    } else if len <= 4 * N {
+        sort_registers::<T, N, U, 4>(data);
+    } else if len <= 8 * N {
+        sort_registers::<T, N, U, 8>(data);
+    } else if len <= 16 * N {
+        sort_registers::<T, N, U, 16>(data);
     } else {
-        let zmm = data.split_at_mut(8);
-        let mut zmm1 = U::loadu(zmm.0);
-        let mut zmm2 = U::mask_loadu(zmm.1);
-        zmm1 = sort_zmm_64bit(zmm1);
-        zmm2 = sort_zmm_64bit(zmm2);
-
-        bitonic_merge_two_zmm_64bit(&mut zmm1, &mut zmm2);
-        U::storeu(zmm1, zmm.0);
-        U::mask_storeu(zmm2, zmm.1);
+        sort_registers::<T, N, U, 32>(data);
     }
 }
 
-pub(crate) fn sort_32<T, U>(data: &mut [T])
+#[inline(always)]
+fn get_pivot_64bit<T, const N: usize, U>(data: &[T]) -> T
 where
     T: Bit64Element,
-    U: Bit64Simd<T> + SimdCompare<T, 8>,
+    U: BitonicNetwork<T, N>,
 {
-    if data.len() <= 16 {
-        sort_16::<T, U>(data);
-        return;
-    };
-    let (data_16_0, data_16_1) = data.split_at_mut(16);
-    let (data_8_0, data_8_1) = data_16_0.split_at_mut(8);
-    let index_2 = cmp::min(data_16_1.len(), 8);
-    let (data_8_2, data_8_3) = data_16_1.split_at_mut(index_2);
-
-    let mut zmm_0 = U::loadu(data_8_0);
-    let mut zmm_1 = U::loadu(data_8_1);
-    let mut zmm_2 = U::mask_loadu(data_8_2);
-    let mut zmm_3 = U::mask_loadu(data_8_3);
-
-    zmm_0 = sort_zmm_64bit(zmm_0);
-    zmm_1 = sort_zmm_64bit(zmm_1);
-    zmm_2 = sort_zmm_64bit(zmm_2);
-    zmm_3 = sort_zmm_64bit(zmm_3);
-    bitonic_merge_two_zmm_64bit(&mut zmm_0, &mut zmm_1);
-    bitonic_merge_two_zmm_64bit(&mut zmm_2, &mut zmm_3);
-
-    let mut zmm = [zmm_0, zmm_1, zmm_2, zmm_3];
-    bitonic_merge_four_zmm_64bit(&mut zmm);
-
-    U::storeu(zmm[0], data_8_0);
-    U::storeu(zmm[1], data_8_1);
-    U::mask_storeu(zmm[2], data_8_2);
-    U::mask_storeu(zmm[3], data_8_3);
+    // median of N
+    let size = (data.len() - 1) / N;
+    let rand_index = array::from_fn(|i| (i + 1) * size);
+
+    let rand_vec = U::gather_from_idx(rand_index, data);
+    // pivot will never be a nan, since there are no nan's!
+    let sort = U::sort_vec(rand_vec);
+    U::get_value_at_idx(sort, N / 2)
 }
 
-pub(crate) fn sort_64<T, U>(data: &mut [T])
+/// Sorts `data` with quicksort down to parts of at most 32 registers, which
+/// are sorted with bitonic networks.
+///
+/// The parts still to sort wait on a stack instead of the call stack, so the
+/// whole sort inlines into a caller compiled with the target features of `U`.
+#[inline(always)]
+pub(crate) fn qsort_64bit_<T, const N: usize, U>(data: &mut [T], max_iters: i64)
 where
     T: Bit64Element,
-    U: Bit64Simd<T> + SimdCompare<T, 8>,
+    U: BitonicNetwork<T, N>,
 {
-    if data.len() <= 32 {
-        sort_32::<T, U>(data);
-        return;
+    let mut pending = Vec::new();
+    let mut next = Some((0, data.len(), max_iters));
+    while let Some((start, end, max_iters)) = next.take().or_else(|| pending.pop()) {
+        let part = &mut data[start..end];
+        /*
+         * Resort to std::sort if quicksort isnt making any progress
+         */
+        if max_iters <= 0 {
+            part.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            continue;
+        }
+        /*
+         * Base case: use bitonic networks to sort arrays <= 32 registers
+         */
+        if part.len() <= 32 * N {
+            bitonic_sort::<T, N, U>(part);
+            continue;
+        }
+
+        let pivot = get_pivot_64bit::<T, N, U>(part);
+        let mut smallest = T::MAX_VALUE;
+        let mut biggest = T::MIN_VALUE;
+        let pivot_index = start
+            + partition_avx512_unrolled::<T, N, U, 8>(part, pivot, &mut smallest, &mut biggest);
+        let left = (start, pivot_index, max_iters - 1);
+        let right = (pivot_index, end, max_iters - 1);
+        match (pivot != smallest, pivot != biggest) {
+            (true, true) => {
+                pending.push(right);
+                next = Some(left);
+            }
+            (true, false) => next = Some(left),
+            (false, true) => next = Some(right),
+            (false, false) => {}
+        }
     }
-    let (data_32_0, data_32_1) = data.split_at_mut(32);
-    let (data_16_0, data_16_1) = data_32_0.split_at_mut(16);
-    let (data_8_0, data_8_1) = data_16_0.split_at_mut(8);
-    let (data_8_2, data_8_3) = data_16_1.split_at_mut(8);
-    let mut zmm_0 = U::loadu(data_8_0);
-    let mut zmm_1 = U::loadu(data_8_1);
-    let mut zmm_2 = U::loadu(data_8_2);
-    let mut zmm_3 = U::loadu(data_8_3);
-    zmm_0 = sort_zmm_64bit(zmm_0);
-    zmm_1 = sort_zmm_64bit(zmm_1);
-    zmm_2 = sort_zmm_64bit(zmm_2);
-    zmm_3 = sort_zmm_64bit(zmm_3);
-
-    let split_index = cmp::min(data_32_1.len(), 8);
-    let (data_8_4, data_32_1) = data_32_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_32_1.len(), 8);
-    let (data_8_5, data_32_1) = data_32_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_32_1.len(), 8);
-    let (data_8_6, data_8_7) = data_32_1.split_at_mut(split_index);
-
-    let mut zmm_4 = U::mask_loadu(data_8_4);
-    let mut zmm_5 = U::mask_loadu(data_8_5);
-    let mut zmm_6 = U::mask_loadu(data_8_6);
-    let mut zmm_7 = U::mask_loadu(data_8_7);
-    zmm_4 = sort_zmm_64bit(zmm_4);
-    zmm_5 = sort_zmm_64bit(zmm_5);
-    zmm_6 = sort_zmm_64bit(zmm_6);
-    zmm_7 = sort_zmm_64bit(zmm_7);
-    bitonic_merge_two_zmm_64bit(&mut zmm_0, &mut zmm_1);
-    bitonic_merge_two_zmm_64bit(&mut zmm_2, &mut zmm_3);
-    bitonic_merge_two_zmm_64bit(&mut zmm_4, &mut zmm_5);
-    bitonic_merge_two_zmm_64bit(&mut zmm_6, &mut zmm_7);
-    let mut zmm = [zmm_0, zmm_1, zmm_2, zmm_3];
-    bitonic_merge_four_zmm_64bit(&mut zmm);
-
-    let mut zmm_1 = [zmm_4, zmm_5, zmm_6, zmm_7];
-    bitonic_merge_four_zmm_64bit(&mut zmm_1);
-
-    let mut zmm = [
-        zmm[0], zmm[1], zmm[2], zmm[3], zmm_1[0], zmm_1[1], zmm_1[2], zmm_1[3],
-    ];
-    bitonic_merge_eight_zmm_64bit(&mut zmm);
-    U::storeu(zmm[0], data_8_0);
-    U::storeu(zmm[1], data_8_1);
-    U::storeu(zmm[2], data_8_2);
-    U::storeu(zmm[3], data_8_3);
-    U::mask_storeu(zmm[4], data_8_4);
-    U::mask_storeu(zmm[5], data_8_5);
-    U::mask_storeu(zmm[6], data_8_6);
-    U::mask_storeu(zmm[7], data_8_7);
 }
 
-pub(crate) fn sort_128<T, U>(data: &mut [T])
+/// Moves the values of `data` below `pivot` to the front and returns how many
+/// there are
+#[inline(always)]
+pub(crate) fn partition_64bit_<T, const N: usize, U>(data: &mut [T], pivot: T) -> usize
 where
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
+{
+    let mut smallest = T::MAX_VALUE;
+    let mut biggest = T::MIN_VALUE;
+    partition_avx512_unrolled::<T, N, U, 8>(data, pivot, &mut smallest, &mut biggest)
+}
+
+/// Reorders `data` so that `data[rank]` holds the value that would be there
+/// after sorting for every rank in the sorted `ranks`, partitioning only the
+/// parts of `data` that contain a requested rank.
+#[inline(always)]
+pub(crate) fn select_ranks_64bit_<T, const N: usize, U>(
+    data: &mut [T],
+    ranks: &[usize],
+    max_iters: i64,
+) where
     T: Bit64Element,
-    U: Bit64Simd<T> + SimdCompare<T, 8>,
+    U: BitonicNetwork<T, N>,
 {
-    if data.len() <= 64 {
-        sort_64::<T, U>(data);
+    if ranks.is_empty() {
         return;
     }
+    // parts of `data` with the range of `ranks` that falls into them, the
+    // ranks stay relative to the whole slice so they are never copied
+    let mut pending = vec![(0, data.len(), 0, ranks.len(), max_iters)];
+    while let Some((start, end, first, last, max_iters)) = pending.pop() {
+        if first == last {
+            continue;
+        }
+        let part = &mut data[start..end];
+        if max_iters <= 0 {
+            part.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            continue;
+        }
+        if part.len() <= 32 * N {
+            bitonic_sort::<T, N, U>(part);
+            continue;
+        }
+
+        let pivot = get_pivot_64bit::<T, N, U>(part);
+        let mut smallest = T::MAX_VALUE;
+        let mut biggest = T::MIN_VALUE;
+        let pivot_index = start
+            + partition_avx512_unrolled::<T, N, U, 8>(part, pivot, &mut smallest, &mut biggest);
+        let split = first + ranks[first..last].partition_point(|&rank| rank < pivot_index);
+        if pivot != smallest {
+            pending.push((start, pivot_index, first, split, max_iters - 1));
+        }
+        if pivot != biggest {
+            pending.push((pivot_index, end, split, last, max_iters - 1));
+        }
+    }
+}
 
-    let (data_64_0, data_64_1) = data.split_at_mut(64);
-    let split_index = cmp::min(data_64_0.len(), 8);
-    let (data_8_0, data_64_0) = data_64_0.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_0.len(), 8);
-    let (data_8_1, data_64_0) = data_64_0.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_0.len(), 8);
-    let (data_8_2, data_64_0) = data_64_0.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_0.len(), 8);
-    let (data_8_3, data_64_0) = data_64_0.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_0.len(), 8);
-    let (data_8_4, data_64_0) = data_64_0.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_0.len(), 8);
-    let (data_8_5, data_64_0) = data_64_0.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_0.len(), 8);
-    let (data_8_6, data_8_7) = data_64_0.split_at_mut(split_index);
-
-    let mut zmm_0 = U::loadu(data_8_0);
-    let mut zmm_1 = U::loadu(data_8_1);
-    let mut zmm_2 = U::loadu(data_8_2);
-    let mut zmm_3 = U::loadu(data_8_3);
-    let mut zmm_4 = U::loadu(data_8_4);
-    let mut zmm_5 = U::loadu(data_8_5);
-    let mut zmm_6 = U::loadu(data_8_6);
-    let mut zmm_7 = U::loadu(data_8_7);
-    zmm_0 = sort_zmm_64bit(zmm_0);
-    zmm_1 = sort_zmm_64bit(zmm_1);
-    zmm_2 = sort_zmm_64bit(zmm_2);
-    zmm_3 = sort_zmm_64bit(zmm_3);
-    zmm_4 = sort_zmm_64bit(zmm_4);
-    zmm_5 = sort_zmm_64bit(zmm_5);
-    zmm_6 = sort_zmm_64bit(zmm_6);
-    zmm_7 = sort_zmm_64bit(zmm_7);
-
-    let split_index = cmp::min(data_64_1.len(), 8);
-    let (data_8_8, data_64_1) = data_64_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_1.len(), 8);
-    let (data_8_9, data_64_1) = data_64_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_1.len(), 8);
-    let (data_8_10, data_64_1) = data_64_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_1.len(), 8);
-    let (data_8_11, data_64_1) = data_64_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_1.len(), 8);
-    let (data_8_12, data_64_1) = data_64_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_1.len(), 8);
-    let (data_8_13, data_64_1) = data_64_1.split_at_mut(split_index);
-
-    let split_index = cmp::min(data_64_1.len(), 8);
-    let (data_8_14, data_8_15) = data_64_1.split_at_mut(split_index);
-
-    let mut zmm_8 = U::mask_loadu(data_8_8);
-    let mut zmm_9 = U::mask_loadu(data_8_9);
-    let mut zmm_10 = U::mask_loadu(data_8_10);
-    let mut zmm_11 = U::mask_loadu(data_8_11);
-    let mut zmm_12 = U::mask_loadu(data_8_12);
-    let mut zmm_13 = U::mask_loadu(data_8_13);
-    let mut zmm_14 = U::mask_loadu(data_8_14);
-    let mut zmm_15 = U::mask_loadu(data_8_15);
-    zmm_8 = sort_zmm_64bit(zmm_8);
-    zmm_9 = sort_zmm_64bit(zmm_9);
-    zmm_10 = sort_zmm_64bit(zmm_10);
-    zmm_11 = sort_zmm_64bit(zmm_11);
-    zmm_12 = sort_zmm_64bit(zmm_12);
-    zmm_13 = sort_zmm_64bit(zmm_13);
-    zmm_14 = sort_zmm_64bit(zmm_14);
-    zmm_15 = sort_zmm_64bit(zmm_15);
-    bitonic_merge_two_zmm_64bit(&mut zmm_0, &mut zmm_1);
-    bitonic_merge_two_zmm_64bit(&mut zmm_2, &mut zmm_3);
-    bitonic_merge_two_zmm_64bit(&mut zmm_4, &mut zmm_5);
-    bitonic_merge_two_zmm_64bit(&mut zmm_6, &mut zmm_7);
-    bitonic_merge_two_zmm_64bit(&mut zmm_8, &mut zmm_9);
-    bitonic_merge_two_zmm_64bit(&mut zmm_10, &mut zmm_11);
-    bitonic_merge_two_zmm_64bit(&mut zmm_12, &mut zmm_13);
-    bitonic_merge_two_zmm_64bit(&mut zmm_14, &mut zmm_15);
-    let mut zmm_4_1 = [zmm_0, zmm_1, zmm_2, zmm_3];
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_1);
-    let mut zmm_4_2 = [zmm_4, zmm_5, zmm_6, zmm_7];
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_2);
-    let mut zmm_4_3 = [zmm_8, zmm_9, zmm_10, zmm_11];
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_3);
-    let mut zmm_4_4 = [zmm_12, zmm_13, zmm_14, zmm_15];
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_4);
-    let mut zmm_8_1 = [
-        zmm_4_1[0], zmm_4_1[1], zmm_4_1[2], zmm_4_1[3], zmm_4_2[0], zmm_4_2[1], zmm_4_2[2],
-        zmm_4_2[3],
-    ];
-    bitonic_merge_eight_zmm_64bit(&mut zmm_8_1);
-    let mut zmm_8_2 = [
-        zmm_4_3[0], zmm_4_3[1], zmm_4_3[2], zmm_4_3[3], zmm_4_4[0], zmm_4_4[1], zmm_4_4[2],
-        zmm_4_4[3],
-    ];
-    bitonic_merge_eight_zmm_64bit(&mut zmm_8_2);
-    let mut zmm = [
-        zmm_8_1[0], zmm_8_1[1], zmm_8_1[2], zmm_8_1[3], zmm_8_1[4], zmm_8_1[5], zmm_8_1[6],
-        zmm_8_1[7], zmm_8_2[0], zmm_8_2[1], zmm_8_2[2], zmm_8_2[3], zmm_8_2[4], zmm_8_2[5],
-        zmm_8_2[6], zmm_8_2[7],
-    ];
-    bitonic_merge_sixteen_zmm_64bit(&mut zmm);
-
-    U::storeu(zmm[0], data_8_0);
-    U::storeu(zmm[1], data_8_1);
-    U::storeu(zmm[2], data_8_2);
-    U::storeu(zmm[3], data_8_3);
-    U::storeu(zmm[4], data_8_4);
-    U::storeu(zmm[5], data_8_5);
-    U::storeu(zmm[6], data_8_6);
-    U::storeu(zmm[7], data_8_7);
-    U::mask_storeu(zmm[8], data_8_8);
-    U::mask_storeu(zmm[9], data_8_9);
-    U::mask_storeu(zmm[10], data_8_10);
-    U::mask_storeu(zmm[11], data_8_11);
-    U::mask_storeu(zmm[12], data_8_12);
-    U::mask_storeu(zmm[13], data_8_13);
-    U::mask_storeu(zmm[14], data_8_14);
-    U::mask_storeu(zmm[15], data_8_15);
+/// Moves the first value of every run of equal values in the sorted `data` to
+/// the front, keeping their order, and returns how many there are.
+#[inline(always)]
+pub(crate) fn dedup_sorted<T, const N: usize, U>(data: &mut [T]) -> usize
+where
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
+{
+    if data.len() < 2 {
+        return data.len();
+    }
+    let mut write = 1;
+    let mut read = 1;
+    // The stores never pass `read + N - 1`, so the previous value of the next
+    // vector is still the original one when it gets loaded
+    while read + N <= data.len() {
+        let prev = U::loadu(&data[read - 1..]);
+        let curr = U::loadu(&data[read..]);
+        // the input is sorted, so prev >= curr only holds for duplicates
+        let unique = U::not_mask(U::ge(prev, curr));
+        U::mask_compressstoreu(&mut data[write..], unique, curr);
+        write += U::ones_count(unique);
+        read += N;
+    }
+    while read < data.len() {
+        if data[read] != data[read - 1] {
+            data[write] = data[read];
+            write += 1;
+        }
+        read += 1;
+    }
+    write
 }
 
-pub(crate) fn sort_256<T, U>(data: &mut [T])
+/// Appends a `(value, count)` pair for every run of equal values in the
+/// sorted `data` to `counts`.
+#[inline(always)]
+pub(crate) fn count_runs<T, const N: usize, U>(data: &[T], counts: &mut Vec<(T, usize)>)
 where
-    T: Bit64Element,
-    U: Bit64Simd<T> + SimdCompare<T, 8>,
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
 {
-    let n = data.len();
-    if n <= 128 {
-        sort_128::<T, U>(data);
+    if data.is_empty() {
         return;
     }
+    let lanes = u64::MAX >> (64 - N);
+    let mut run_start = 0;
+    let mut i = 1;
+    while i + N <= data.len() {
+        let prev = U::loadu(&data[i - 1..]);
+        let curr = U::loadu(&data[i..]);
+        // a new run starts at every lane that differs from the one before it
+        let mut boundaries = U::to_bitmask(U::not_mask(U::eq(prev, curr))) & lanes;
+        while boundaries != 0 {
+            let j = i + boundaries.trailing_zeros() as usize;
+            counts.push((data[run_start], j - run_start));
+            run_start = j;
+            boundaries &= boundaries - 1;
+        }
+        i += N;
+    }
+    for j in i..data.len() {
+        if data[j] != data[j - 1] {
+            counts.push((data[run_start], j - run_start));
+            run_start = j;
+        }
+    }
+    counts.push((data[run_start], data.len() - run_start));
+}
 
-    let (data_128_0, data_128_1) = data.split_at_mut(128);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_0, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_1, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_2, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_3, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_4, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_5, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_6, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_7, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_8, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_9, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_10, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_11, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_12, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_13, data_128_0) = data_128_0.split_at_mut(split_index);
-    let split_index = cmp::min(data_128_0.len(), 8);
-    let (data_8_14, data_8_15) = data_128_0.split_at_mut(split_index);
-
-    let mut zmm_0 = U::loadu(data_8_0);
-    let mut zmm_1 = U::loadu(data_8_1);
-    let mut zmm_2 = U::loadu(data_8_2);
-    let mut zmm_3 = U::loadu(data_8_3);
-    let mut zmm_4 = U::loadu(data_8_4);
-    let mut zmm_5 = U::loadu(data_8_5);
-    let mut zmm_6 = U::loadu(data_8_6);
-    let mut zmm_7 = U::loadu(data_8_7);
-    let mut zmm_8 = U::loadu(data_8_8);
-    let mut zmm_9 = U::loadu(data_8_9);
-    let mut zmm_10 = U::loadu(data_8_10);
-    let mut zmm_11 = U::loadu(data_8_11);
-    let mut zmm_12 = U::loadu(data_8_12);
-    let mut zmm_13 = U::loadu(data_8_13);
-    let mut zmm_14 = U::loadu(data_8_14);
-    let mut zmm_15 = U::loadu(data_8_15);
-    zmm_0 = sort_zmm_64bit(zmm_0);
-    zmm_1 = sort_zmm_64bit(zmm_1);
-    zmm_2 = sort_zmm_64bit(zmm_2);
-    zmm_3 = sort_zmm_64bit(zmm_3);
-    zmm_4 = sort_zmm_64bit(zmm_4);
-    zmm_5 = sort_zmm_64bit(zmm_5);
-    zmm_6 = sort_zmm_64bit(zmm_6);
-    zmm_7 = sort_zmm_64bit(zmm_7);
-    zmm_8 = sort_zmm_64bit(zmm_8);
-    zmm_9 = sort_zmm_64bit(zmm_9);
-    zmm_10 = sort_zmm_64bit(zmm_10);
-    zmm_11 = sort_zmm_64bit(zmm_11);
-    zmm_12 = sort_zmm_64bit(zmm_12);
-    zmm_13 = sort_zmm_64bit(zmm_13);
-    zmm_14 = sort_zmm_64bit(zmm_14);
-    zmm_15 = sort_zmm_64bit(zmm_15);
-
-    bitonic_merge_two_zmm_64bit(&mut zmm_0, &mut zmm_1);
-    bitonic_merge_two_zmm_64bit(&mut zmm_2, &mut zmm_3);
-    bitonic_merge_two_zmm_64bit(&mut zmm_4, &mut zmm_5);
-    bitonic_merge_two_zmm_64bit(&mut zmm_6, &mut zmm_7);
-    bitonic_merge_two_zmm_64bit(&mut zmm_8, &mut zmm_9);
-    bitonic_merge_two_zmm_64bit(&mut zmm_10, &mut zmm_11);
-    bitonic_merge_two_zmm_64bit(&mut zmm_12, &mut zmm_13);
-    bitonic_merge_two_zmm_64bit(&mut zmm_14, &mut zmm_15);
-
-    let mut zmm_4_1 = [zmm_0, zmm_1, zmm_2, zmm_3];
-    let mut zmm_4_2 = [zmm_4, zmm_5, zmm_6, zmm_7];
-    let mut zmm_4_3 = [zmm_8, zmm_9, zmm_10, zmm_11];
-    let mut zmm_4_4 = [zmm_12, zmm_13, zmm_14, zmm_15];
-
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_1);
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_2);
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_3);
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_4);
-
-    let mut zmm_8_1 = [
-        zmm_4_1[0], zmm_4_1[1], zmm_4_1[2], zmm_4_1[3], zmm_4_2[0], zmm_4_2[1], zmm_4_2[2],
-        zmm_4_2[3],
-    ];
-
-    let mut zmm_8_2 = [
-        zmm_4_3[0], zmm_4_3[1], zmm_4_3[2], zmm_4_3[3], zmm_4_4[0], zmm_4_4[1], zmm_4_4[2],
-        zmm_4_4[3],
-    ];
-
-    bitonic_merge_eight_zmm_64bit(&mut zmm_8_1);
-    bitonic_merge_eight_zmm_64bit(&mut zmm_8_2);
-
-    let mut zmm_16_1 = [
-        zmm_8_1[0], zmm_8_1[1], zmm_8_1[2], zmm_8_1[3], zmm_8_1[4], zmm_8_1[5], zmm_8_1[6],
-        zmm_8_1[7], zmm_8_2[0], zmm_8_2[1], zmm_8_2[2], zmm_8_2[3], zmm_8_2[4], zmm_8_2[5],
-        zmm_8_2[6], zmm_8_2[7],
-    ];
-
-    bitonic_merge_sixteen_zmm_64bit(&mut zmm_16_1);
-
-    let ([mut zmm_4_5, mut zmm_4_6, mut zmm_4_7, mut zmm_4_8], data_splits) = {
-        if n >= 192 {
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_0, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_1, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_2, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_3, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_4, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_5, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_6, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_7, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_8, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_9, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_10, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_11, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_12, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_13, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_14, data_8_15) = data_128_1.split_at_mut(split_index);
-
-            let mut zmm_0 = U::mask_loadu(data_8_0);
-            let mut zmm_1 = U::mask_loadu(data_8_1);
-            let mut zmm_2 = U::mask_loadu(data_8_2);
-            let mut zmm_3 = U::mask_loadu(data_8_3);
-            let mut zmm_4 = U::mask_loadu(data_8_4);
-            let mut zmm_5 = U::mask_loadu(data_8_5);
-            let mut zmm_6 = U::mask_loadu(data_8_6);
-            let mut zmm_7 = U::mask_loadu(data_8_7);
-            let mut zmm_8 = U::mask_loadu(data_8_8);
-            let mut zmm_9 = U::mask_loadu(data_8_9);
-            let mut zmm_10 = U::mask_loadu(data_8_10);
-            let mut zmm_11 = U::mask_loadu(data_8_11);
-            let mut zmm_12 = U::mask_loadu(data_8_12);
-            let mut zmm_13 = U::mask_loadu(data_8_13);
-            let mut zmm_14 = U::mask_loadu(data_8_14);
-            let mut zmm_15 = U::mask_loadu(data_8_15);
-
-            zmm_0 = sort_zmm_64bit(zmm_0);
-            zmm_1 = sort_zmm_64bit(zmm_1);
-            zmm_2 = sort_zmm_64bit(zmm_2);
-            zmm_3 = sort_zmm_64bit(zmm_3);
-            zmm_4 = sort_zmm_64bit(zmm_4);
-            zmm_5 = sort_zmm_64bit(zmm_5);
-            zmm_6 = sort_zmm_64bit(zmm_6);
-            zmm_7 = sort_zmm_64bit(zmm_7);
-            zmm_8 = sort_zmm_64bit(zmm_8);
-            zmm_9 = sort_zmm_64bit(zmm_9);
-            zmm_10 = sort_zmm_64bit(zmm_10);
-            zmm_11 = sort_zmm_64bit(zmm_11);
-            zmm_12 = sort_zmm_64bit(zmm_12);
-            zmm_13 = sort_zmm_64bit(zmm_13);
-            zmm_14 = sort_zmm_64bit(zmm_14);
-            zmm_15 = sort_zmm_64bit(zmm_15);
-
-            bitonic_merge_two_zmm_64bit(&mut zmm_0, &mut zmm_1);
-            bitonic_merge_two_zmm_64bit(&mut zmm_2, &mut zmm_3);
-            bitonic_merge_two_zmm_64bit(&mut zmm_4, &mut zmm_5);
-            bitonic_merge_two_zmm_64bit(&mut zmm_6, &mut zmm_7);
-            bitonic_merge_two_zmm_64bit(&mut zmm_8, &mut zmm_9);
-            bitonic_merge_two_zmm_64bit(&mut zmm_10, &mut zmm_11);
-            bitonic_merge_two_zmm_64bit(&mut zmm_12, &mut zmm_13);
-            bitonic_merge_two_zmm_64bit(&mut zmm_14, &mut zmm_15);
-
-            (
-                [
-                    [zmm_0, zmm_1, zmm_2, zmm_3],
-                    [zmm_4, zmm_5, zmm_6, zmm_7],
-                    [zmm_8, zmm_9, zmm_10, zmm_11],
-                    [zmm_12, zmm_13, zmm_14, zmm_15],
-                ],
-                [
-                    data_8_0, data_8_1, data_8_2, data_8_3, data_8_4, data_8_5, data_8_6, data_8_7,
-                    data_8_8, data_8_9, data_8_10, data_8_11, data_8_12, data_8_13, data_8_14,
-                    data_8_15,
-                ],
-            )
+/// Returns the index of the first value at or after `pos` in the sorted
+/// `data` that is not below `value`, or not below or equal with `or_equal`
+#[inline(always)]
+fn skip_below<T, const N: usize, U>(data: &[T], mut pos: usize, value: T, or_equal: bool) -> usize
+where
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
+{
+    let bound = U::set(value);
+    while pos + N <= data.len() {
+        let vec = U::loadu(&data[pos..]);
+        let below = if or_equal {
+            U::ones_count(U::ge(bound, vec))
         } else {
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_0, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_1, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_2, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_3, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_4, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_5, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_6, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_7, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_8, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_9, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_10, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_11, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_12, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_13, data_128_1) = data_128_1.split_at_mut(split_index);
-            let split_index = cmp::min(data_128_1.len(), 8);
-            let (data_8_14, data_8_15) = data_128_1.split_at_mut(split_index);
-            let mut zmm_0 = U::mask_loadu(data_8_0);
-            let mut zmm_1 = U::mask_loadu(data_8_1);
-            let mut zmm_2 = U::mask_loadu(data_8_2);
-            let mut zmm_3 = U::mask_loadu(data_8_3);
-            let mut zmm_4 = U::mask_loadu(data_8_4);
-            let mut zmm_5 = U::mask_loadu(data_8_5);
-            let mut zmm_6 = U::mask_loadu(data_8_6);
-            let mut zmm_7 = U::mask_loadu(data_8_7);
-
-            zmm_0 = sort_zmm_64bit(zmm_0);
-            zmm_1 = sort_zmm_64bit(zmm_1);
-            zmm_2 = sort_zmm_64bit(zmm_2);
-            zmm_3 = sort_zmm_64bit(zmm_3);
-            zmm_4 = sort_zmm_64bit(zmm_4);
-            zmm_5 = sort_zmm_64bit(zmm_5);
-            zmm_6 = sort_zmm_64bit(zmm_6);
-            zmm_7 = sort_zmm_64bit(zmm_7);
-
-            let mut zmm_8 = U::set(T::MAX_VALUE);
-            let mut zmm_9 = U::set(T::MAX_VALUE);
-            let mut zmm_10 = U::set(T::MAX_VALUE);
-            let mut zmm_11 = U::set(T::MAX_VALUE);
-            let mut zmm_12 = U::set(T::MAX_VALUE);
-            let mut zmm_13 = U::set(T::MAX_VALUE);
-            let mut zmm_14 = U::set(T::MAX_VALUE);
-            let mut zmm_15 = U::set(T::MAX_VALUE);
-
-            bitonic_merge_two_zmm_64bit(&mut zmm_0, &mut zmm_1);
-            bitonic_merge_two_zmm_64bit(&mut zmm_2, &mut zmm_3);
-            bitonic_merge_two_zmm_64bit(&mut zmm_4, &mut zmm_5);
-            bitonic_merge_two_zmm_64bit(&mut zmm_6, &mut zmm_7);
-            bitonic_merge_two_zmm_64bit(&mut zmm_8, &mut zmm_9);
-            bitonic_merge_two_zmm_64bit(&mut zmm_10, &mut zmm_11);
-            bitonic_merge_two_zmm_64bit(&mut zmm_12, &mut zmm_13);
-            bitonic_merge_two_zmm_64bit(&mut zmm_14, &mut zmm_15);
-            (
-                [
-                    [zmm_0, zmm_1, zmm_2, zmm_3],
-                    [zmm_4, zmm_5, zmm_6, zmm_7],
-                    [zmm_8, zmm_9, zmm_10, zmm_11],
-                    [zmm_12, zmm_13, zmm_14, zmm_15],
-                ],
-                [
-                    data_8_0, data_8_1, data_8_2, data_8_3, data_8_4, data_8_5, data_8_6, data_8_7,
-                    data_8_8, data_8_9, data_8_10, data_8_11, data_8_12, data_8_13, data_8_14,
-                    data_8_15,
-                ],
-            )
+            N - U::ones_count(U::ge(vec, bound))
+        };
+        pos += below;
+        if below < N {
+            return pos;
         }
-    };
+    }
+    while pos < data.len() && (data[pos] < value || or_equal && data[pos] == value) {
+        pos += 1;
+    }
+    pos
+}
 
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_5);
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_6);
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_7);
-    bitonic_merge_four_zmm_64bit(&mut zmm_4_8);
-
-    let mut zmm_8_3 = [
-        zmm_4_5[0], zmm_4_5[1], zmm_4_5[2], zmm_4_5[3], zmm_4_6[0], zmm_4_6[1], zmm_4_6[2],
-        zmm_4_6[3],
-    ];
-
-    let mut zmm_8_4 = [
-        zmm_4_7[0], zmm_4_7[1], zmm_4_7[2], zmm_4_7[3], zmm_4_8[0], zmm_4_8[1], zmm_4_8[2],
-        zmm_4_8[3],
-    ];
-
-    bitonic_merge_eight_zmm_64bit(&mut zmm_8_3);
-    bitonic_merge_eight_zmm_64bit(&mut zmm_8_4);
-
-    let mut zmm_16_2 = [
-        zmm_8_3[0], zmm_8_3[1], zmm_8_3[2], zmm_8_3[3], zmm_8_3[4], zmm_8_3[5], zmm_8_3[6],
-        zmm_8_3[7], zmm_8_4[0], zmm_8_4[1], zmm_8_4[2], zmm_8_4[3], zmm_8_4[4], zmm_8_4[5],
-        zmm_8_4[6], zmm_8_4[7],
-    ];
-    bitonic_merge_sixteen_zmm_64bit(&mut zmm_16_2);
-
-    let mut zmm = [
-        zmm_16_1[0],
-        zmm_16_1[1],
-        zmm_16_1[2],
-        zmm_16_1[3],
-        zmm_16_1[4],
-        zmm_16_1[5],
-        zmm_16_1[6],
-        zmm_16_1[7],
-        zmm_16_1[8],
-        zmm_16_1[9],
-        zmm_16_1[10],
-        zmm_16_1[11],
-        zmm_16_1[12],
-        zmm_16_1[13],
-        zmm_16_1[14],
-        zmm_16_1[15],
-        zmm_16_2[0],
-        zmm_16_2[1],
-        zmm_16_2[2],
-        zmm_16_2[3],
-        zmm_16_2[4],
-        zmm_16_2[5],
-        zmm_16_2[6],
-        zmm_16_2[7],
-        zmm_16_2[8],
-        zmm_16_2[9],
-        zmm_16_2[10],
-        zmm_16_2[11],
-        zmm_16_2[12],
-        zmm_16_2[13],
-        zmm_16_2[14],
-        zmm_16_2[15],
-    ];
-
-    bitonic_merge_thirtytwo_zmm_64bit(&mut zmm);
-
-    U::storeu(zmm[0], data_8_0);
-    U::storeu(zmm[1], data_8_1);
-    U::storeu(zmm[2], data_8_2);
-    U::storeu(zmm[3], data_8_3);
-    U::storeu(zmm[4], data_8_4);
-    U::storeu(zmm[5], data_8_5);
-    U::storeu(zmm[6], data_8_6);
-    U::storeu(zmm[7], data_8_7);
-    U::storeu(zmm[8], data_8_8);
-    U::storeu(zmm[9], data_8_9);
-    U::storeu(zmm[10], data_8_10);
-    U::storeu(zmm[11], data_8_11);
-    U::storeu(zmm[12], data_8_12);
-    U::storeu(zmm[13], data_8_13);
-    U::storeu(zmm[14], data_8_14);
-    U::storeu(zmm[15], data_8_15);
-
-    U::mask_storeu(zmm[16], data_splits[0]);
-    U::mask_storeu(zmm[17], data_splits[1]);
-    U::mask_storeu(zmm[18], data_splits[2]);
-    U::mask_storeu(zmm[19], data_splits[3]);
-    U::mask_storeu(zmm[20], data_splits[4]);
-    U::mask_storeu(zmm[21], data_splits[5]);
-    U::mask_storeu(zmm[22], data_splits[6]);
-    U::mask_storeu(zmm[23], data_splits[7]);
-    if n >= 192 {
-        U::mask_storeu(zmm[24], data_splits[8]);
-        U::mask_storeu(zmm[25], data_splits[9]);
-        U::mask_storeu(zmm[26], data_splits[10]);
-        U::mask_storeu(zmm[27], data_splits[11]);
-        U::mask_storeu(zmm[28], data_splits[12]);
-        U::mask_storeu(zmm[29], data_splits[13]);
-        U::mask_storeu(zmm[30], data_splits[14]);
-        U::mask_storeu(zmm[31], data_splits[15]);
+/// Marks the lanes of `vec` that are equal to one of the `values`, which must
+/// not be empty
+#[inline(always)]
+fn match_lanes<T, const N: usize, U>(vec: U, values: &[T]) -> U::OPMask
+where
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
+{
+    let mut mask = U::eq(vec, U::set(values[0]));
+    for &value in &values[1..] {
+        mask = U::or_mask(mask, U::eq(vec, U::set(value)));
     }
+    mask
 }
 
-fn get_pivot_64bit<T, U>(data: &[T]) -> T
+/// Returns the values of the sorted and duplicate free `a` that are (with
+/// `keep_matches`) or are not in the sorted and duplicate free `b`.
+///
+/// Every vector of `a` is compared with all values of `b` in its range, which
+/// are each broadcast once since the ranges of the vectors do not overlap.
+#[inline(always)]
+fn filter_sorted<T, const N: usize, U>(a: &[T], b: &[T], keep_matches: bool) -> Vec<T>
 where
-    T: Bit64Element,
-    U: SimdCompare<T, 8> + Bit64Simd<T>,
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
 {
-    // median of 8
-    let size = data.len() / 8;
-    let rand_index = [
-        size,
-        2 * size,
-        3 * size,
-        4 * size,
-        5 * size,
-        6 * size,
-        7 * size,
-        8 * size,
-    ];
+    // compressed stores never write past the kept values, the extra vector
+    // leaves room for the full stores of vectors without matches
+    let mut out = vec![T::MIN_VALUE; a.len() + N];
+    let mut len = 0;
+    let mut lo = 0;
+    let mut i = 0;
+    while i + N <= a.len() {
+        lo = skip_below::<T, N, U>(b, lo, a[i], false);
+        let hi = skip_below::<T, N, U>(b, lo, a[i + N - 1], true);
+        let vec = U::loadu(&a[i..]);
+        if lo < hi {
+            let mut mask = match_lanes::<T, N, U>(vec, &b[lo..hi]);
+            if !keep_matches {
+                mask = U::not_mask(mask);
+            }
+            U::mask_compressstoreu(&mut out[len..], mask, vec);
+            len += U::ones_count(mask);
+        } else if !keep_matches {
+            U::storeu(vec, &mut out[len..]);
+            len += N;
+        }
+        lo = hi;
+        i += N;
+    }
+    for &value in &a[i..] {
+        lo = skip_below::<T, N, U>(b, lo, value, false);
+        if (lo < b.len() && b[lo] == value) == keep_matches {
+            out[len] = value;
+            len += 1;
+        }
+    }
+    out.truncate(len);
+    out
+}
 
-    let rand_vec = U::gather_from_idx(rand_index, data);
-    // pivot will never be a nan, since there are no nan's!
-    let sort = sort_zmm_64bit(rand_vec);
-    return U::get_value_at_idx(sort, 4);
+/// Applies `op` to the sorted and duplicate free `a` and `b`, returning the
+/// sorted result
+#[inline(always)]
+pub(crate) fn set_operation<T, const N: usize, U>(op: SetOperation, a: &[T], b: &[T]) -> Vec<T>
+where
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
+{
+    match op {
+        SetOperation::Intersection => {
+            if a.len() <= b.len() {
+                filter_sorted::<T, N, U>(a, b, true)
+            } else {
+                filter_sorted::<T, N, U>(b, a, true)
+            }
+        }
+        SetOperation::Difference => filter_sorted::<T, N, U>(a, b, false),
+        SetOperation::Union => merge_sorted(a, &filter_sorted::<T, N, U>(b, a, false)),
+    }
+}
+
+/// Merges the sorted `a` and `b`
+pub(crate) fn merge_sorted<T: SimdSortable>(a: &[T], b: &[T]) -> Vec<T> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if b[j] < a[i] {
+            out.push(b[j]);
+            j += 1;
+        } else {
+            out.push(a[i]);
+            i += 1;
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
 }
 
-pub(crate) fn qsort_64bit_<T, U>(data: &mut [T], max_iters: i64)
+/// Writes the index of the first value of `sorted` that is not below
+/// `probes[i]` to `out[i]`, running the binary searches of a vector of probes
+/// in lockstep.
+#[inline(always)]
+pub(crate) fn lower_bound_batch<T, const N: usize, U>(sorted: &[T], probes: &[T], out: &mut [usize])
 where
-    T: Bit64Element,
-    U: SimdCompare<T, 8> + Bit64Simd<T>,
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
 {
-    /*
-     * Resort to std::sort if quicksort isnt making any progress
-     */
-    if max_iters <= 0 {
-        data.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    if sorted.is_empty() {
+        out.fill(0);
         return;
     }
-    /*
-     * Base case: use bitonic networks to sort arrays <= 128
-     */
-    if data.len() <= 256 {
-        sort_256::<T, U>(data);
-        return;
+    let mut i = 0;
+    while i + N <= probes.len() {
+        let probe_vec = U::loadu(&probes[i..]);
+        let mut base = [0; N];
+        // every lane halves the same range length, only the bases differ
+        let mut len = sorted.len();
+        while len > 1 {
+            let half = len / 2;
+            let values = U::gather_from_idx(array::from_fn(|j| base[j] + half), sorted);
+            let below = U::to_bitmask(U::not_mask(U::ge(values, probe_vec)));
+            for (j, base) in base.iter_mut().enumerate() {
+                *base += half * (below >> j & 1) as usize;
+            }
+            len -= half;
+        }
+        let values = U::gather_from_idx(base, sorted);
+        let below = U::to_bitmask(U::not_mask(U::ge(values, probe_vec)));
+        for j in 0..N {
+            out[i + j] = base[j] + (below >> j & 1) as usize;
+        }
+        i += N;
+    }
+    for (probe, index) in probes[i..].iter().zip(&mut out[i..]) {
+        *index = sorted.partition_point(|value| value < probe);
     }
+}
 
-    let pivot = get_pivot_64bit::<T, U>(data);
-    let mut smallest = T::MAX_VALUE;
-    let mut biggest = T::MIN_VALUE;
-    //let pivot_index = partition_avx512::<T, 8, U>(data, pivot, &mut smallest, &mut biggest);
-    let pivot_index =
-        partition_avx512_unrolled::<T, 8, U, 8>(data, pivot, &mut smallest, &mut biggest);
-    let (left, right) = data.split_at_mut(pivot_index);
-    if pivot != smallest {
-        qsort_64bit_::<T, U>(left, max_iters - 1);
+/// Returns the smallest and largest value of `data`. NaN values are skipped,
+/// or returned as both results with `propagate_nan`.
+#[allow(clippy::eq_op)]
+#[inline(always)]
+pub(crate) fn minmax<T, const N: usize, U>(data: &[T], propagate_nan: bool) -> Option<(T, T)>
+where
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
+{
+    fn update<T: SimdSortable>(result: &mut Option<(T, T)>, value: T) {
+        *result = match *result {
+            None => Some((value, value)),
+            Some((min, max)) => Some((
+                if value < min { value } else { min },
+                if value > max { value } else { max },
+            )),
+        };
+    }
+
+    let mut vectors: Option<(U, U)> = None;
+    let mut result = None;
+    let mut i = 0;
+    while i + N <= data.len() {
+        let vec = U::loadu(&data[i..]);
+        // only NaN lanes are not equal to themselves
+        if U::ones_count(U::eq(vec, vec)) == N {
+            vectors = match vectors {
+                None => Some((vec, vec)),
+                Some((min, max)) => Some((U::min(min, vec), U::max(max, vec))),
+            };
+        } else {
+            for &value in &data[i..i + N] {
+                if value == value {
+                    update(&mut result, value);
+                } else if propagate_nan {
+                    return Some((value, value));
+                }
+            }
+        }
+        i += N;
+    }
+    for &value in &data[i..] {
+        if value == value {
+            update(&mut result, value);
+        } else if propagate_nan {
+            return Some((value, value));
+        }
     }
-    if pivot != biggest {
-        qsort_64bit_::<T, U>(right, max_iters - 1);
+    if let Some((min, max)) = vectors {
+        update(&mut result, U::reducemin(min));
+        update(&mut result, U::reducemax(max));
     }
+    result
 }
 
 #[cfg(test)]
 pub(crate) mod test {
+    /// Whether the cpu can run the backend the tests are generated for. The
+    /// test modules of backends detected at runtime import their own check,
+    /// which takes precedence over this one.
+    pub fn cpu_supports_backend() -> bool {
+        true
+    }
+
+    /// Xorshift values from a fixed seed, so failures reproduce
+    pub fn random_u64(len: usize) -> Vec<u64> {
+        let mut state = 0x2545f4914f6cdd1du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pivot_within_slice() {
+        // the last sample used to be one past the end for lengths that are a
+        // multiple of 8, which the bounds checked scalar gather catches
+        for len in [257, 264, 512, 1000] {
+            let data: Vec<i64> = (0..len).collect();
+            let pivot = super::get_pivot_64bit::<i64, 8, [i64; 8]>(&data);
+            assert!(data.contains(&pivot), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_partition_nothing_below_pivot() {
+        // every value goes to the right, so the right store region ends up
+        // starting at index 0
+        for len in [16, 64, 192, 200] {
+            let mut data: Vec<i64> = (0..len).rev().collect();
+            let (mut smallest, mut biggest) = (i64::MAX, i64::MIN);
+            let index = crate::partition_avx512_unrolled::<i64, 8, [i64; 8], 8>(
+                &mut data,
+                0,
+                &mut smallest,
+                &mut biggest,
+            );
+            assert_eq!(index, 0, "len {}", len);
+            assert_eq!((smallest, biggest), (0, len - 1), "len {}", len);
+            data.sort_unstable();
+            assert!(data.iter().copied().eq(0..len), "len {}", len);
+        }
+    }
+
     macro_rules! test_min_max {
         ($ty: ident, $simd: ident, $into_array: ident) => {
             paste::paste! {
                 #[test]
                 fn [<test_min_max_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::loadu(&[
                         1 as $ty,
                         20 as $ty,
@@ -1143,6 +738,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_loadu_storeu_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let mut input_slice = [
                         1 as $ty,
                         2 as $ty,
@@ -1189,6 +787,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_mask_loadu_mask_storeu_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let mut input_slice = [
                         1 as $ty,
                         2 as $ty,
@@ -1238,6 +839,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_get_at_index_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::mask_loadu(&[
                         1 as $ty,
                         2 as $ty,
@@ -1261,6 +865,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_ge_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::mask_loadu(&[
                         1 as $ty, 20 as $ty,
                         3 as $ty,
@@ -1292,6 +899,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_gather_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let input_slice = [
                         1 as $ty,
                         2 as $ty,
@@ -1325,6 +935,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_not_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first: <$simd as SimdCompare<$ty, 8>>::OPMask = $mask_input;
                     assert_eq!(<$simd as SimdCompare<$ty, 8>>::not_mask(first), $mask_result);
                 }
@@ -1337,6 +950,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_count_ones_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     for i in 0u8..8 {
                         let mask = $mask_fn(i);
                         assert_eq!(<$simd as SimdCompare<$ty, 8>>::ones_count(mask), i.count_ones() as usize);
@@ -1351,6 +967,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_reduce_min_max_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::mask_loadu(&[
                         1 as $ty,
                         6 as $ty,
@@ -1373,6 +992,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_compress_store_u_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let input_slice = [
                         1 as $ty,
                         2 as $ty,
@@ -1387,16 +1009,13 @@ pub(crate) mod test {
                     let first = $simd::loadu(input_slice.as_ref());
                     for i in 0..255 {
                         let (mask, new_values) = $generate_fn::<$ty, $mask_ty>(i, &input_slice);
-                        format!("{:?}", mask);
                         let mut new_array = input_slice.clone();
                         $simd::mask_compressstoreu(&mut new_array[2..], mask, first);
-                        format!("{:?}", new_array);
-                        format!("{:?}", new_values);
                         println!("{:?}", new_array);
                         for j in 0..(i as usize).count_ones() as usize {
                             assert_eq!(new_array[2 + j], new_values[j]);
                         }
-                        for j in i as usize..8 {
+                        for j in (i as usize).count_ones() as usize..8 {
                             assert_eq!(new_array[2 + j], input_slice[2 + j]);
                         }
                     }
@@ -1410,6 +1029,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_shuffle1_1_1_1_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::loadu(&[
                         1 as $ty,
                         2 as $ty,
@@ -1438,12 +1060,15 @@ pub(crate) mod test {
         };
     }
 
-    macro_rules! test_swizzle2_0xaa {
+    macro_rules! test_mask_mov {
         ($ty: ident, $simd: ident, $into_array: ident) => {
             paste::paste! {
                 #[test]
-                fn [<test_swizzle2_0xaa_ $ty>]() {
-                    let first = $simd::loadu(&[
+                fn [<test_mask_mov_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    let first_values = [
                         1 as $ty,
                         2 as $ty,
                         3 as $ty,
@@ -1452,8 +1077,8 @@ pub(crate) mod test {
                         6 as $ty,
                         7 as $ty,
                         8 as $ty,
-                    ]);
-                    let second = $simd::loadu(&[
+                    ];
+                    let second_values = [
                         10 as $ty,
                         20 as $ty,
                         30 as $ty,
@@ -1461,107 +1086,46 @@ pub(crate) mod test {
                         50 as $ty,
                         60 as $ty,
                         70 as $ty,
-                        80 as $ty
-                    ]);
+                        80 as $ty,
+                    ];
+                    let first = $simd::loadu(&first_values);
+                    let second = $simd::loadu(&second_values);
+                    let expected = |mask: u64| -> [$ty; 8] {
+                        std::array::from_fn(|i| {
+                            if mask >> i & 1 == 1 {
+                                second_values[i]
+                            } else {
+                                first_values[i]
+                            }
+                        })
+                    };
                     assert_eq!(
-                        $into_array(<$simd as Bit64Simd<$ty>>::swizzle2_0xaa(first, second)),
-                        [
-                            1 as $ty,
-                            20 as $ty,
-                            3 as $ty,
-                            40 as $ty,
-                            5 as $ty,
-                            60 as $ty,
-                            7 as $ty,
-                            80 as $ty
-                        ]
+                        $into_array(<$simd as SimdCompare<$ty, 8>>::mask_mov::<0xAA>(first, second)),
+                        expected(0xAA)
                     );
-                }
-            }
-        };
-    }
-
-    macro_rules! test_swizzle2_0xcc {
-        ($ty: ident, $simd: ident, $into_array: ident) => {
-            paste::paste! {
-                #[test]
-                fn [<test_swizzle2_0xcc_ $ty>]() {
-                    let first = $simd::loadu(&[
-                        1 as $ty,
-                        2 as $ty,
-                        3 as $ty,
-                        4 as $ty,
-                        5 as $ty,
-                        6 as $ty,
-                        7 as $ty,
-                        8  as $ty
-                    ]);
-                    let second = $simd::loadu(&[
-                        10 as $ty,
-                        20 as $ty,
-                        30 as $ty,
-                        40 as $ty,
-                        50 as $ty,
-                        60 as $ty,
-                        70 as $ty,
-                        80 as $ty
-                    ]);
                     assert_eq!(
-                        $into_array(<$simd as Bit64Simd<$ty>>::swizzle2_0xcc(first, second)),
-                        [
-                            1 as $ty,
-                            2 as $ty,
-                            30 as $ty,
-                            40 as $ty,
-                            5 as $ty,
-                            6 as $ty,
-                            70 as $ty,
-                            80 as $ty
-
-                       ]
+                        $into_array(<$simd as SimdCompare<$ty, 8>>::mask_mov::<0xCC>(first, second)),
+                        expected(0xCC)
                     );
-                }
-            }
-        };
-    }
-
-    macro_rules! test_swizzle2_0xf0 {
-        ($ty: ident, $simd: ident, $into_array: ident) => {
-            paste::paste! {
-                #[test]
-                fn [<test_swizzle2_0xf0_ $ty>]() {
-                    let first = $simd::loadu(&[
-                        1 as $ty,
-                        2 as $ty,
-                        3 as $ty,
-                        4 as $ty,
-                        5 as $ty,
-                        6 as $ty,
-                        7 as $ty,
-                        8  as $ty
-                    ]);
-                    let second = $simd::loadu(&[
-                        10 as $ty,
-                        20 as $ty,
-                        30 as $ty,
-                        40 as $ty,
-                        50 as $ty,
-                        60 as $ty,
-                        70 as $ty,
-                        80 as $ty
-                    ]);
                     assert_eq!(
-                        $into_array(<$simd as Bit64Simd<$ty>>::swizzle2_0xf0(first, second)),
-                        [
-                            1 as $ty,
-                            2 as $ty,
-                            3 as $ty,
-                            4 as $ty,
-                            50 as $ty,
-                            60 as $ty,
-                            70 as $ty,
-                            80 as $ty,
-                        ]
+                        $into_array(<$simd as SimdCompare<$ty, 8>>::mask_mov::<0xF0>(first, second)),
+                        expected(0xF0)
+                    );
+                    assert_eq!(
+                        $into_array(<$simd as SimdCompare<$ty, 8>>::mask_mov::<0x00>(first, second)),
+                        expected(0x00)
+                    );
+                    assert_eq!(
+                        $into_array(<$simd as SimdCompare<$ty, 8>>::mask_mov::<0xFF>(first, second)),
+                        expected(0xFF)
+                    );
+                    assert_eq!(
+                        $into_array(<$simd as SimdCompare<$ty, 8>>::mask_mov::<0x5A>(first, second)),
+                        expected(0x5A)
+                    );
+                    assert_eq!(
+                        $into_array(<$simd as SimdCompare<$ty, 8>>::mask_mov::<0x81>(first, second)),
+                        expected(0x81)
                     );
                 }
             }
@@ -1573,6 +1137,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<network64bit1_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::loadu(&[
                         0 as $ty,
                         1 as $ty,
@@ -1606,6 +1173,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<network64bit2_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::loadu(&[
                         0 as $ty,
                         1 as $ty,
@@ -1639,6 +1209,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<network64bit3_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::loadu(&[
                         0 as $ty,
                         1 as $ty,
@@ -1672,6 +1245,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<network64bit4_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let first = $simd::loadu(&[
                         0 as $ty,
                         1 as $ty,
@@ -1701,16 +1277,19 @@ pub(crate) mod test {
     }
 
     macro_rules! test_sort_n {
-        ($ty: ident, $simd: ident, $n: literal) => {
+        ($ty: ident, $simd: ty, $lanes: literal, $n: literal) => {
             paste::paste! {
                 #[test]
                 fn [<test_sort_ $n _ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let result: Vec<$ty> = (0..$n).into_iter().map(|x| x as $ty).collect();
                     for i in 0..$n {
                         let mut array = Vec::with_capacity(i);
                         array.extend_from_slice(&result[..i]);
                         array.reverse();
-                        [<sort_ $n>]::<$ty, $simd>(&mut array);
+                        sort_registers::<$ty, $lanes, $simd, { $n / $lanes }>(&mut array);
                         assert_eq!(&array, &result[..i]);
                     }
                 }
@@ -1723,6 +1302,9 @@ pub(crate) mod test {
             paste::paste! {
                 #[test]
                 fn [<test_sort_e2e_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
                     let start = 0;
                     let end = 1024;
                     let result: Vec<$ty> = (0..end).into_iter().map(|x| x as $ty).collect();
@@ -1739,11 +1321,290 @@ pub(crate) mod test {
         };
     }
 
+    macro_rules! test_dedup_sorted {
+        ($ty: ident, $simd: ty, $lanes: literal) => {
+            paste::paste! {
+                #[test]
+                fn [<test_dedup_sorted_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    for len in 0..100 {
+                        for run in 1..$lanes + 2 {
+                            let mut array: Vec<$ty> =
+                                (0..len).map(|x| (x / run) as $ty).collect();
+                            let mut expected = array.clone();
+                            expected.dedup();
+                            let unique = dedup_sorted::<$ty, $lanes, $simd>(&mut array);
+                            assert_eq!(&array[..unique], &expected[..]);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    macro_rules! test_count_runs {
+        ($ty: ident, $simd: ty, $lanes: literal) => {
+            paste::paste! {
+                #[test]
+                fn [<test_count_runs_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    for len in 0..100 {
+                        for run in 1..$lanes * 2 + 2 {
+                            let array: Vec<$ty> = (0..len).map(|x| (x / run) as $ty).collect();
+                            let mut expected: Vec<($ty, usize)> = Vec::new();
+                            for &value in &array {
+                                match expected.last_mut() {
+                                    Some((last, count)) if *last == value => *count += 1,
+                                    _ => expected.push((value, 1)),
+                                }
+                            }
+                            let mut counts = Vec::new();
+                            count_runs::<$ty, $lanes, $simd>(&array, &mut counts);
+                            assert_eq!(counts, expected);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    macro_rules! test_eq_or_mask {
+        ($ty: ident, $simd: ty) => {
+            paste::paste! {
+                #[test]
+                fn [<test_eq_or_mask_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    let values = [1, 2, 3, 4, 5, 6, 7, 8].map(|x| x as $ty);
+                    let first = <$simd as SimdCompare<$ty, 8>>::loadu(&values);
+                    let second = <$simd as SimdCompare<$ty, 8>>::loadu(&[1, 0, 3, 0, 0, 6, 0, 8].map(|x| x as $ty));
+                    let third = <$simd as SimdCompare<$ty, 8>>::loadu(&[0, 2, 3, 0, 0, 0, 0, 0].map(|x| x as $ty));
+
+                    let mut result = [0 as $ty; 8];
+                    let mask = <$simd as SimdCompare<$ty, 8>>::eq(first, second);
+                    assert_eq!(<$simd as SimdCompare<$ty, 8>>::ones_count(mask), 4);
+                    <$simd as SimdCompare<$ty, 8>>::mask_compressstoreu(&mut result, mask, first);
+                    assert_eq!(result[..4], [1, 3, 6, 8].map(|x| x as $ty));
+
+                    let mask = <$simd as SimdCompare<$ty, 8>>::or_mask(mask, <$simd as SimdCompare<$ty, 8>>::eq(first, third));
+                    assert_eq!(<$simd as SimdCompare<$ty, 8>>::ones_count(mask), 5);
+                    <$simd as SimdCompare<$ty, 8>>::mask_compressstoreu(&mut result, mask, first);
+                    assert_eq!(result[..5], [1, 2, 3, 6, 8].map(|x| x as $ty));
+                }
+            }
+        };
+    }
+
+    macro_rules! test_set_operations {
+        ($ty: ident, $simd: ty, $lanes: literal) => {
+            paste::paste! {
+                #[test]
+                fn [<test_set_operations_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    use crate::platform::SetOperation;
+
+                    for a_len in [0, 1, $lanes - 1, $lanes, $lanes + 1, 100] {
+                        for b_step in 1..5 {
+                            for b_len in [0, 1, $lanes, 3 * $lanes + 1, 100] {
+                                let a: Vec<$ty> = (0..a_len).map(|x| (3 * x) as $ty).collect();
+                                let b: Vec<$ty> =
+                                    (0..b_len).map(|x| (b_step * x + 1) as $ty).collect();
+                                let intersection: Vec<$ty> =
+                                    a.iter().copied().filter(|x| b.contains(x)).collect();
+                                let difference: Vec<$ty> =
+                                    a.iter().copied().filter(|x| !b.contains(x)).collect();
+                                let mut union = [a.clone(), b.clone()].concat();
+                                union.sort_by(|x, y| x.partial_cmp(y).unwrap());
+                                union.dedup();
+
+                                let op = |op| set_operation::<$ty, $lanes, $simd>(op, &a, &b);
+                                assert_eq!(op(SetOperation::Intersection), intersection);
+                                assert_eq!(op(SetOperation::Difference), difference);
+                                assert_eq!(op(SetOperation::Union), union);
+                            }
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    macro_rules! test_to_bitmask {
+        ($ty: ident, $simd: ty) => {
+            paste::paste! {
+                #[test]
+                fn [<test_to_bitmask_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    let first = <$simd>::loadu(&[1, 2, 3, 4, 5, 6, 7, 8].map(|x| x as $ty));
+                    let second = <$simd>::loadu(&[1, 0, 3, 0, 0, 6, 0, 8].map(|x| x as $ty));
+                    let mask = <$simd as SimdCompare<$ty, 8>>::eq(first, second);
+                    assert_eq!(<$simd as SimdCompare<$ty, 8>>::to_bitmask(mask), 0b10100101);
+                    let mask = <$simd as SimdCompare<$ty, 8>>::not_mask(mask);
+                    assert_eq!(<$simd as SimdCompare<$ty, 8>>::to_bitmask(mask), 0b01011010);
+                }
+            }
+        };
+    }
+
+    macro_rules! test_and_mask_blend {
+        ($ty: ident, $simd: ty) => {
+            paste::paste! {
+                #[test]
+                fn [<test_and_mask_blend_ $ty>]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    let first = <$simd>::loadu(&[1, 2, 3, 4, 5, 6, 7, 8].map(|x| x as $ty));
+                    let second = <$simd>::loadu(&[1, 0, 3, 0, 0, 6, 0, 8].map(|x| x as $ty));
+                    let third = <$simd>::loadu(&[1, 2, 0, 0, 5, 6, 0, 0].map(|x| x as $ty));
+                    let mask = <$simd as SimdCompare<$ty, 8>>::and_mask(
+                        <$simd as SimdCompare<$ty, 8>>::eq(first, second),
+                        <$simd as SimdCompare<$ty, 8>>::eq(first, third),
+                    );
+                    assert_eq!(<$simd as SimdCompare<$ty, 8>>::to_bitmask(mask), 0b00100001);
+
+                    let mask = <$simd as SimdCompare<$ty, 8>>::eq(first, third);
+                    let blended = <$simd as SimdCompare<$ty, 8>>::blend(mask, first, second);
+                    let mut result = [0 as $ty; 8];
+                    <$simd as SimdCompare<$ty, 8>>::storeu(blended, &mut result);
+                    assert_eq!(result, [1, 0, 3, 4, 0, 6, 7, 8].map(|x| x as $ty));
+                }
+            }
+        };
+    }
+
+    macro_rules! test_lower_bound_batch {
+        ($ty: ident, $simd: ty, $lanes: literal) => {
+            paste::paste! {
+                #[test]
+                fn [<test_lower_bound_batch_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    for len in [0, 1, 2, 3, $lanes, 100, 257] {
+                        let sorted: Vec<$ty> = (0..len).map(|x| (2 * (x / 3)) as $ty).collect();
+                        let probes: Vec<$ty> = (0..2 * len as i64 + $lanes + 3)
+                            .map(|x| (x - 1) as $ty)
+                            .rev()
+                            .collect();
+                        let expected: Vec<usize> = probes
+                            .iter()
+                            .map(|probe| sorted.partition_point(|x| x < probe))
+                            .collect();
+                        let mut out = vec![usize::MAX; probes.len()];
+                        lower_bound_batch::<$ty, $lanes, $simd>(&sorted, &probes, &mut out);
+                        assert_eq!(out, expected, "failed for len {}", len);
+                    }
+                }
+            }
+        };
+    }
+
+    macro_rules! test_minmax_slice {
+        ($ty: ident, $simd: ty, $lanes: literal) => {
+            paste::paste! {
+                #[test]
+                fn [<test_minmax_slice_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    assert_eq!(minmax::<$ty, $lanes, $simd>(&[], false), None);
+                    assert_eq!(
+                        minmax::<$ty, $lanes, $simd>(&[7 as $ty], false),
+                        Some((7 as $ty, 7 as $ty))
+                    );
+                    for len in 2..4 * $lanes + 3 {
+                        for extreme in 0..len {
+                            let mut array: Vec<$ty> =
+                                (0..len).map(|x| (50 + x % 7) as $ty).collect();
+                            array[extreme] = 3 as $ty;
+                            array[(extreme + 1) % len] = 200 as $ty;
+                            assert_eq!(
+                                minmax::<$ty, $lanes, $simd>(&array, false),
+                                Some((3 as $ty, 200 as $ty))
+                            );
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    macro_rules! test_select_ranks {
+        ($ty: ident, $simd: ty, $lanes: literal) => {
+            paste::paste! {
+                #[test]
+                fn [<test_select_ranks_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    for len in [1usize, 2, $lanes, 32 * $lanes, 32 * $lanes + 1, 5000] {
+                        let sorted: Vec<$ty> = (0..len).map(|x| (x / 3) as $ty).collect();
+                        // a fixed shuffle, reversing alternating blocks
+                        let mut array = sorted.clone();
+                        for (i, block) in array.chunks_mut(7).enumerate() {
+                            if i % 2 == 0 {
+                                block.reverse();
+                            }
+                        }
+                        array.reverse();
+                        let mut ranks: Vec<usize> = [0, 1, len / 3, len / 2, len.saturating_sub(2), len - 1]
+                            .into_iter()
+                            .filter(|&rank| rank < len)
+                            .collect();
+                        ranks.dedup();
+                        let max_iters = f64::log2(len as f64) as i64;
+                        select_ranks_64bit_::<$ty, $lanes, $simd>(&mut array, &ranks, max_iters);
+                        for &rank in &ranks {
+                            assert_eq!(array[rank], sorted[rank], "failed for len {}", len);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    macro_rules! test_partition {
+        ($ty: ident, $simd: ty, $lanes: literal) => {
+            paste::paste! {
+                #[test]
+                fn [<test_partition_ $ty >]() {
+                    if !cpu_supports_backend() {
+                        return;
+                    }
+                    for len in [0, 1, $lanes - 1, $lanes, 2 * $lanes + 1, 16 * $lanes + 3, 1000] {
+                        for pivot in [0, 1, len / 2, len, len + 1] {
+                            let mut array: Vec<$ty> =
+                                (0..len).map(|x| ((x * 7) % (len + 1)) as $ty).collect();
+                            let below = array.iter().filter(|&&x| x < pivot as $ty).count();
+                            assert_eq!(
+                                partition_64bit_::<$ty, $lanes, $simd>(&mut array, pivot as $ty),
+                                below
+                            );
+                            assert!(array[..below].iter().all(|&x| x < pivot as $ty));
+                            assert!(array[below..].iter().all(|&x| x >= pivot as $ty));
+                        }
+                    }
+                }
+            }
+        };
+    }
+
     pub(crate) use {
-        network64bit1, network64bit2, network64bit3, network64bit4, test_compress_store_u,
-        test_count_ones, test_gather, test_ge, test_get_at_index, test_loadu_storeu,
-        test_mask_loadu_mask_storeu, test_min_max, test_not, test_reduce_min_max,
-        test_shuffle1_1_1_1, test_sort_e2e, test_sort_n, test_swizzle2_0xaa, test_swizzle2_0xcc,
-        test_swizzle2_0xf0,
+        network64bit1, network64bit2, network64bit3, network64bit4, test_and_mask_blend,
+        test_compress_store_u, test_count_ones, test_count_runs, test_dedup_sorted,
+        test_eq_or_mask, test_gather, test_ge, test_get_at_index, test_loadu_storeu,
+        test_lower_bound_batch, test_mask_loadu_mask_storeu, test_mask_mov, test_min_max,
+        test_minmax_slice, test_not, test_partition, test_reduce_min_max, test_select_ranks,
+        test_set_operations, test_shuffle1_1_1_1, test_sort_e2e, test_sort_n, test_to_bitmask,
     };
 }
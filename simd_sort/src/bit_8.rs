@@ -0,0 +1,68 @@
+/// Histograms filled in turn, so that runs of equal bytes do not serialise
+/// on the increments of a single counter
+const HISTOGRAMS: usize = 4;
+
+/// Sorts `data` by counting how often every byte occurs and writing the
+/// bytes back in order. Bytes are ordered by `byte ^ bias`, so a bias of
+/// `0x80` sorts the bytes as `i8`.
+///
+/// The bytes are read 8 at a time and spread over [`HISTOGRAMS`] tables.
+/// Merging the tables and filling the runs are plain loops over arrays
+/// which the compiler vectorises for the enabled target features.
+pub(crate) fn counting_sort_8bit(data: &mut [u8], bias: u8) {
+    let mut histograms = [[0usize; 256]; HISTOGRAMS];
+    let mut words = data.chunks_exact(8);
+    for word in &mut words {
+        let word = u64::from_le_bytes(word.try_into().unwrap());
+        for i in 0..8 {
+            histograms[i % HISTOGRAMS][(word >> (8 * i)) as u8 as usize] += 1;
+        }
+    }
+    for &byte in words.remainder() {
+        histograms[0][byte as usize] += 1;
+    }
+
+    let mut counts = histograms[0];
+    for histogram in &histograms[1..] {
+        for (count, other) in counts.iter_mut().zip(histogram) {
+            *count += other;
+        }
+    }
+
+    let mut start = 0;
+    for key in 0..=u8::MAX {
+        let byte = key ^ bias;
+        let count = counts[byte as usize];
+        data[start..start + count].fill(byte);
+        start += count;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::counting_sort_8bit;
+    use crate::bit_64::test::random_u64;
+
+    #[test]
+    fn test_counting_sort_8bit() {
+        for len in [0, 1, 7, 8, 9, 255, 256, 1000, 100_000] {
+            let data: Vec<u8> = random_u64(len)
+                .into_iter()
+                .map(|x| (x >> 32) as u8)
+                .collect();
+
+            let mut unsigned = data.clone();
+            counting_sort_8bit(&mut unsigned, 0);
+            let mut expected = data.clone();
+            expected.sort_unstable();
+            assert_eq!(unsigned, expected, "len {}", len);
+
+            let mut signed = data.clone();
+            counting_sort_8bit(&mut signed, 0x80);
+            let mut expected: Vec<i8> = data.iter().map(|&x| x as i8).collect();
+            expected.sort_unstable();
+            let signed: Vec<i8> = signed.into_iter().map(|x| x as i8).collect();
+            assert_eq!(signed, expected, "len {}", len);
+        }
+    }
+}
@@ -1,8 +1,31 @@
 #![cfg_attr(feature = "nightly", feature(portable_simd))]
-#![cfg_attr(feature = "nightly", feature(stdsimd))]
 
+pub(crate) mod bit_128;
 pub(crate) mod bit_64;
+pub(crate) mod bit_8;
 pub mod platform;
+pub(crate) mod sort_key;
+pub(crate) mod strings;
+pub(crate) mod time;
+pub(crate) mod top_k;
+
+pub use bit_64::{Bit64Element, Bit64Simd, BitonicNetwork};
+pub use platform::{
+    available_backends, dedup_sorted_f64, dedup_sorted_i64, dedup_sorted_u64, difference_sorted,
+    intersect_sorted, lower_bound_batch, median_f64, minmax_f64, minmax_f64_propagate_nan,
+    minmax_i64, minmax_u64, percentiles_f64, percentiles_f64_with, selected_backend, sort,
+    sort_and_count, sort_and_count_into, sort_i128, sort_pairs_u64, sort_segments, sort_small,
+    sort_small_batch, sort_u128, sort_unique, sort_with_backend, union_sorted, Backend,
+    QuantileMethod, SetOperation, SMALL_SORT_MAX_LEN,
+};
+pub use sort_key::SimdSortKey;
+pub use strings::sort_strings;
+pub use time::{
+    argsort_durations, argsort_system_times, duration_from_key, duration_from_key_u64,
+    duration_key, duration_key_u64, sort_durations, sort_system_times, system_time_from_key,
+    system_time_key,
+};
+pub use top_k::TopK;
 
 use std::{
     cmp::{max_by, min_by, Ordering},
@@ -18,14 +41,8 @@ pub trait SimdCompare<T: SimdSortable, const NUM_LANES: usize>: Copy + Debug {
 
     fn min(a: Self, b: Self) -> Self;
     fn max(a: Self, b: Self) -> Self;
-    #[inline]
-    fn mask_mov(a: Self, b: Self, mask: fn(Self, Self) -> Self) -> Self {
-        mask(a, b)
-    }
-    #[inline]
-    fn shuffle(a: Self, shuffle_fn: fn(Self) -> Self) -> Self {
-        shuffle_fn(a)
-    }
+    /// Takes lane `i` from `b` if bit `i` of `MASK` is set, otherwise from `a`
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self;
     fn loadu(data: &[T]) -> Self;
     fn storeu(input: Self, data: &mut [T]);
 
@@ -39,9 +56,16 @@ pub trait SimdCompare<T: SimdSortable, const NUM_LANES: usize>: Copy + Debug {
     fn set(value: T) -> Self;
 
     fn ge(a: Self, b: Self) -> Self::OPMask;
+    fn eq(a: Self, b: Self) -> Self::OPMask;
 
     fn ones_count(mask: Self::OPMask) -> usize;
+    /// Returns the mask with bit `i` set if lane `i` is set
+    fn to_bitmask(mask: Self::OPMask) -> u64;
     fn not_mask(mask: Self::OPMask) -> Self::OPMask;
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask;
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask;
+    /// Takes lane `i` from `b` if lane `i` of `mask` is set, otherwise from `a`
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self;
 
     fn reducemin(x: Self) -> T;
     fn reducemax(x: Self) -> T;
@@ -49,6 +73,137 @@ pub trait SimdCompare<T: SimdSortable, const NUM_LANES: usize>: Copy + Debug {
     fn mask_compressstoreu(array: &mut [T], mask: Self::OPMask, data: Self);
 }
 
+/// Register types of `N` lanes that [`sort_with_backend`] can sort with.
+///
+/// The lane operations come from [`SimdCompare`] and the in-register sorting
+/// networks from [`BitonicNetwork`]. The sort of the crate backends that
+/// need runtime cpu detection, like avx512, is only available through
+/// [`Backend`].
+///
+/// # Safety
+///
+/// The quicksort indexes the data without bounds checks based on what the
+/// register type returns, so implementations must make sure that
+///
+/// - `ones_count` returns the number of set lanes, at most `N`, and
+///   `not_mask` leaves no lanes above the `N`th set
+/// - `mask_compressstoreu` writes exactly `ones_count(mask)` values
+/// - `loadu` and `storeu` access the first `N` values, `mask_loadu` and
+///   `mask_storeu` at most `data.len()` values and `mask_loadu` pads with
+///   `T::MAX_VALUE`
+/// - every method can run on any cpu the crate runs on, that is the target
+///   features it uses are enabled at compile time
+///
+/// Compares that disagree with the `PartialOrd` of `T` leave the data
+/// unsorted but are not unsafe.
+///
+/// # Example
+///
+/// A backend of 4 lanes on a plain array:
+///
+/// ```
+/// use std::array;
+///
+/// use simd_sort::{sort_with_backend, BitonicNetwork, SimdCompare, SortBackend};
+///
+/// #[derive(Debug, Clone, Copy)]
+/// struct I64x4([i64; 4]);
+///
+/// impl SimdCompare<i64, 4> for I64x4 {
+///     type OPMask = u8;
+///
+///     fn min(a: Self, b: Self) -> Self {
+///         I64x4(array::from_fn(|i| a.0[i].min(b.0[i])))
+///     }
+///     fn max(a: Self, b: Self) -> Self {
+///         I64x4(array::from_fn(|i| a.0[i].max(b.0[i])))
+///     }
+///     fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+///         Self::blend(MASK as u8, a, b)
+///     }
+///     fn loadu(data: &[i64]) -> Self {
+///         I64x4(array::from_fn(|i| data[i]))
+///     }
+///     fn storeu(input: Self, data: &mut [i64]) {
+///         data[..4].copy_from_slice(&input.0);
+///     }
+///     fn mask_loadu(data: &[i64]) -> Self {
+///         I64x4(array::from_fn(|i| data.get(i).copied().unwrap_or(i64::MAX)))
+///     }
+///     fn mask_storeu(input: Self, data: &mut [i64]) {
+///         let len = data.len();
+///         data.copy_from_slice(&input.0[..len]);
+///     }
+///     fn gather_from_idx(idx: [usize; 4], data: &[i64]) -> Self {
+///         I64x4(idx.map(|i| data[i]))
+///     }
+///     fn get_value_at_idx(input: Self, idx: usize) -> i64 {
+///         input.0[idx]
+///     }
+///     fn set(value: i64) -> Self {
+///         I64x4([value; 4])
+///     }
+///     fn ge(a: Self, b: Self) -> u8 {
+///         (0..4).fold(0, |mask, i| mask | ((a.0[i] >= b.0[i]) as u8) << i)
+///     }
+///     fn eq(a: Self, b: Self) -> u8 {
+///         (0..4).fold(0, |mask, i| mask | ((a.0[i] == b.0[i]) as u8) << i)
+///     }
+///     fn ones_count(mask: u8) -> usize {
+///         mask.count_ones() as usize
+///     }
+///     fn to_bitmask(mask: u8) -> u64 {
+///         mask as u64
+///     }
+///     fn not_mask(mask: u8) -> u8 {
+///         !mask & 0b1111
+///     }
+///     fn or_mask(a: u8, b: u8) -> u8 {
+///         a | b
+///     }
+///     fn and_mask(a: u8, b: u8) -> u8 {
+///         a & b
+///     }
+///     fn blend(mask: u8, a: Self, b: Self) -> Self {
+///         I64x4(array::from_fn(|i| if mask >> i & 1 == 1 { b.0[i] } else { a.0[i] }))
+///     }
+///     fn reducemin(x: Self) -> i64 {
+///         x.0.into_iter().min().unwrap()
+///     }
+///     fn reducemax(x: Self) -> i64 {
+///         x.0.into_iter().max().unwrap()
+///     }
+///     fn mask_compressstoreu(array: &mut [i64], mask: u8, data: Self) {
+///         let selected = (0..4).filter(|i| mask >> i & 1 == 1);
+///         for (slot, i) in array.iter_mut().zip(selected) {
+///             *slot = data.0[i];
+///         }
+///     }
+/// }
+///
+/// impl BitonicNetwork<i64, 4> for I64x4 {
+///     fn reverse(mut a: Self) -> Self {
+///         a.0.reverse();
+///         a
+///     }
+///     fn sort_vec(mut a: Self) -> Self {
+///         a.0.sort_unstable();
+///         a
+///     }
+///     fn merge_vec(a: Self) -> Self {
+///         Self::sort_vec(a)
+///     }
+/// }
+///
+/// // This is safe since the methods only use bounds checked indexing
+/// unsafe impl SortBackend<i64, 4> for I64x4 {}
+///
+/// let mut data: Vec<i64> = (0..1000).map(|x| x * 7919 % 1000 - 500).collect();
+/// sort_with_backend::<i64, 4, I64x4>(&mut data);
+/// assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+/// ```
+pub unsafe trait SortBackend<T: Bit64Element, const N: usize>: BitonicNetwork<T, N> {}
+
 #[cfg(feature = "nightly")]
 pub trait SimdSortable: PartialOrd + SimdElement + Debug {
     const MAX_VALUE: Self;
@@ -71,6 +226,16 @@ impl SimdSortable for i64 {
     const MIN_VALUE: Self = i64::MIN;
 }
 
+impl SimdSortable for usize {
+    const MAX_VALUE: Self = usize::MAX;
+    const MIN_VALUE: Self = usize::MIN;
+}
+
+impl SimdSortable for isize {
+    const MAX_VALUE: Self = isize::MAX;
+    const MIN_VALUE: Self = isize::MIN;
+}
+
 impl SimdSortable for f64 {
     const MAX_VALUE: Self = f64::MAX;
     const MIN_VALUE: Self = f64::MIN;
@@ -83,28 +248,29 @@ pub(crate) fn comparison_func<T: SimdSortable>(a: &T, b: &T) -> Ordering {
 ///
 /// COEX == Compare and Exchange two registers by swapping min and max values
 ///
-#[inline]
+#[inline(always)]
 pub(crate) fn coex<T: SimdSortable, const N: usize, U: SimdCompare<T, N>>(a: &mut U, b: &mut U) {
     let temp = *a;
     *a = U::min(*a, *b);
     *b = U::max(temp, *b);
 }
 
-#[inline]
-pub(crate) fn cmp_merge<T: SimdSortable, const N: usize, U: SimdCompare<T, N>>(
-    in1: U,
-    in2: U,
-    mask: fn(U, U) -> U,
-) -> U {
+#[inline(always)]
+pub(crate) fn cmp_merge<T, const N: usize, U, const MASK: u64>(in1: U, in2: U) -> U
+where
+    T: SimdSortable,
+    U: SimdCompare<T, N>,
+{
     let min_values = U::min(in2, in1);
     let max_values = U::max(in2, in1);
-    U::mask_mov(min_values, max_values, mask) // 0 -> min, 1 -> max
+    U::mask_mov::<MASK>(min_values, max_values) // 0 -> min, 1 -> max
 }
 
 /*
  * Parition one ZMM register based on the pivot and returns the index of the
  * last element that is less than equal to the pivot.
  */
+#[inline(always)]
 fn partition_vec<T: SimdSortable, const N: usize, U: SimdCompare<T, N>>(
     data: &mut [T],
     left: usize,
@@ -133,10 +299,10 @@ fn partition_vec<T: SimdSortable, const N: usize, U: SimdCompare<T, N>>(
     }
     *smallest_vec = U::min(*curr_vec, *smallest_vec);
     *biggest_vec = U::max(*curr_vec, *biggest_vec);
-    return amount_gt_pivot;
+    amount_gt_pivot
 }
 
-#[inline]
+#[inline(always)]
 pub(crate) fn partition_avx512<T: SimdSortable, const N: usize, U: SimdCompare<T, N>>(
     data: &mut [T],
     pivot: T,
@@ -189,7 +355,7 @@ pub(crate) fn partition_avx512<T: SimdSortable, const N: usize, U: SimdCompare<T
     let vec_left = U::loadu(&data[left..]);
     let vec_right = U::loadu(&data[(right - N)..]);
     // store points of the vectors
-    let mut r_store = right - N;
+    let mut r_end = right;
     let mut l_store = left;
     // indices for loading the elements
     left += N;
@@ -201,7 +367,7 @@ pub(crate) fn partition_avx512<T: SimdSortable, const N: usize, U: SimdCompare<T
          * then next elements are loaded from the right side,
          * otherwise from the left side
          */
-        if (r_store + N) - right < left - l_store {
+        if r_end - right < left - l_store {
             right -= N;
             curr_vec = U::loadu(&data[right..]);
         } else {
@@ -212,13 +378,13 @@ pub(crate) fn partition_avx512<T: SimdSortable, const N: usize, U: SimdCompare<T
         let amount_gt_pivot = partition_vec(
             data,
             l_store,
-            r_store + N,
+            r_end,
             &curr_vec,
             &pivot_vec,
             &mut min_vec,
             &mut max_vec,
         );
-        r_store -= amount_gt_pivot;
+        r_end -= amount_gt_pivot;
         l_store += N - amount_gt_pivot;
     }
 
@@ -226,7 +392,7 @@ pub(crate) fn partition_avx512<T: SimdSortable, const N: usize, U: SimdCompare<T
     let mut amount_gt_pivot = partition_vec(
         data,
         l_store,
-        r_store + N,
+        r_end,
         &vec_left,
         &pivot_vec,
         &mut min_vec,
@@ -245,10 +411,30 @@ pub(crate) fn partition_avx512<T: SimdSortable, const N: usize, U: SimdCompare<T
     l_store += N - amount_gt_pivot;
     *smallest = U::reducemin(min_vec);
     *biggest = U::reducemax(max_vec);
-    return l_store;
+    l_store
+}
+
+/*
+ * Expands the block once for every index below the length of the unrolled
+ * partition, which is at most 8. The loops over the registers are written out
+ * since the compiler stops unrolling them once the register operations are
+ * inlined.
+ */
+macro_rules! unroll_8 {
+    ($len: expr, |$i: ident| $body: block) => {
+        unroll_8!(@ $len, $i, $body, 0 1 2 3 4 5 6 7)
+    };
+    (@ $len: expr, $i: ident, $body: block, $($index: literal)*) => {
+        $(
+            if $index < $len {
+                let $i = $index;
+                $body
+            }
+        )*
+    };
 }
 
-#[inline]
+#[inline(always)]
 pub(crate) fn partition_avx512_unrolled<T, const N: usize, U, const UNROLL: usize>(
     data: &mut [T],
     pivot: T,
@@ -259,6 +445,7 @@ where
     T: SimdSortable,
     U: SimdCompare<T, N>,
 {
+    const { assert!(UNROLL <= 8) };
     let mut left = 0;
     let mut right = data.len();
     if right - left <= 2 * UNROLL * N {
@@ -312,7 +499,7 @@ where
     };
 
     // store points of the vectors
-    let mut r_store = right - N;
+    let mut r_end = right;
     let mut l_store = left;
 
     left += N * UNROLL;
@@ -329,16 +516,14 @@ where
              * then next elements are loaded from the right side,
              * otherwise from the left side
              */
-            if (r_store + N) - right < left - l_store {
+            if r_end - right < left - l_store {
                 right -= UNROLL * N;
-                for i in 0..UNROLL {
-                    current_vec[i] =
-                        MaybeUninit::new(U::loadu(data.get_unchecked((right + (N * i))..)));
+                for (i, vec) in current_vec.iter_mut().enumerate() {
+                    *vec = MaybeUninit::new(U::loadu(data.get_unchecked((right + (N * i))..)));
                 }
             } else {
-                for i in 0..UNROLL {
-                    current_vec[i] =
-                        MaybeUninit::new(U::loadu(data.get_unchecked((left + (N * i))..)));
+                for (i, vec) in current_vec.iter_mut().enumerate() {
+                    *vec = MaybeUninit::new(U::loadu(data.get_unchecked((left + (N * i))..)));
                 }
                 left += UNROLL * N;
             }
@@ -347,51 +532,39 @@ where
         };
 
         // partition the current vector and save it on both sides of the array
-        for i in 0..UNROLL {
+        unroll_8!(UNROLL, |i| {
             let amount_ge_pivot = partition_vec(
                 data,
                 l_store,
-                r_store + N,
+                r_end,
                 &current_vec[i],
                 &pivot_vec,
                 &mut min_vec,
                 &mut max_vec,
             );
             l_store += N - amount_ge_pivot;
-            r_store -= amount_ge_pivot;
-        }
-    }
-
-    //  partition and save vec_left[8] and vec_right[8]
-    for i in 0..UNROLL {
-        let amount_ge_pivot = partition_vec(
-            data,
-            l_store,
-            r_store + N,
-            &vec_left[i],
-            &pivot_vec,
-            &mut min_vec,
-            &mut max_vec,
-        );
-        l_store += N - amount_ge_pivot;
-        r_store -= amount_ge_pivot;
+            r_end -= amount_ge_pivot;
+        });
     }
 
-    for i in 0..UNROLL {
-        let amount_ge_pivot = partition_vec(
-            data,
-            l_store,
-            r_store + N,
-            &vec_right[i],
-            &pivot_vec,
-            &mut min_vec,
-            &mut max_vec,
-        );
-        l_store += N - amount_ge_pivot;
-        r_store -= amount_ge_pivot;
+    //  partition and save vec_left and vec_right
+    for vecs in [&vec_left, &vec_right] {
+        unroll_8!(UNROLL, |i| {
+            let amount_ge_pivot = partition_vec(
+                data,
+                l_store,
+                r_end,
+                &vecs[i],
+                &pivot_vec,
+                &mut min_vec,
+                &mut max_vec,
+            );
+            l_store += N - amount_ge_pivot;
+            r_end -= amount_ge_pivot;
+        });
     }
 
     *smallest = U::reducemin(min_vec);
     *biggest = U::reducemax(max_vec);
-    return l_store;
+    l_store
 }
@@ -1,34 +1,2127 @@
 #[cfg(feature = "nightly")]
 pub mod nightly;
 
+pub mod scalar;
+
 #[cfg(target_arch = "x86_64")]
 pub mod x86;
 
 #[cfg(target_family = "wasm")]
 pub mod wasm;
 
+use std::fmt;
+
+use crate::{
+    bit_128::{qsort_128bit_, sort_128bit_std},
+    bit_64::{
+        self, bitonic_sort, count_runs, dedup_sorted, merge_sorted, minmax, partition_64bit_,
+        qsort_64bit_, select_ranks_64bit_, set_operation, Bit64Element,
+    },
+    bit_8::counting_sort_8bit,
+    SimdSortKey, SimdSortable, SortBackend,
+};
+
+/// The sort implementations that can be selected at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Avx512,
+    Avx2,
+    Sse,
+    Wasm128,
+    PortableSimd,
+    Scalar,
+    Std,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 7] = [
+        Backend::Avx512,
+        Backend::Avx2,
+        Backend::Sse,
+        Backend::Wasm128,
+        Backend::PortableSimd,
+        Backend::Scalar,
+        Backend::Std,
+    ];
+
+    /// Returns true if the backend can run on this target and cpu
+    pub fn is_available(self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx512 => is_x86_feature_detected!("avx512f"),
+            Backend::Avx2 => cfg!(all(target_arch = "x86_64", target_feature = "avx2")),
+            // sse4.2 implies the ssse3 and sse4.1 instructions the backend also uses
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse => is_x86_feature_detected!("sse4.2"),
+            Backend::Wasm128 => cfg!(all(target_family = "wasm", target_feature = "simd128")),
+            Backend::PortableSimd => cfg!(feature = "nightly"),
+            Backend::Scalar | Backend::Std => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Human readable name of the backend
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Avx512 => "avx512",
+            Backend::Avx2 => "avx2",
+            Backend::Sse => "sse4.2",
+            Backend::Wasm128 => "wasm-simd128",
+            Backend::PortableSimd => "portable-simd",
+            Backend::Scalar => "scalar",
+            Backend::Std => "std",
+        }
+    }
+
+    /// Target features the backend needs, the portable simd backend
+    /// additionally needs the `nightly` crate feature
+    pub fn required_features(self) -> &'static [&'static str] {
+        match self {
+            Backend::Avx512 => &["avx512f"],
+            Backend::Avx2 => &["avx2"],
+            Backend::Sse => &["sse4.2"],
+            Backend::Wasm128 => &["simd128"],
+            Backend::PortableSimd | Backend::Scalar | Backend::Std => &[],
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The operations of [`BackendSortable::set_operation_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperation {
+    /// The values in both inputs
+    Intersection,
+    /// The values in either input
+    Union,
+    /// The values in the first input but not in the second
+    Difference,
+}
+
+/// How [`percentiles_f64_with`] picks a value for a percentile that falls
+/// between two ranks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantileMethod {
+    /// The smallest value that at least the percentile of the values are
+    /// smaller than or equal to
+    NearestRank,
+    /// Linear interpolation between the two closest ranks, like the default
+    /// of numpy's `percentile`
+    Linear,
+}
+
+/// Longest slice [`sort_small`] accepts
+pub const SMALL_SORT_MAX_LEN: usize = 256;
+
+/// Element types with a runtime dispatched sort
+pub trait BackendSortable: SimdSortable {
+    /// Backends in order of preference, the sort uses the first available one
+    const BACKENDS: &'static [Backend];
+
+    /// Returns the bitonic network sort of `backend` for slices of at most
+    /// [`SMALL_SORT_MAX_LEN`] values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn small_sort_kernel(backend: Backend) -> fn(&mut [Self]);
+
+    /// Sorts `data` with the given backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn sort_with(backend: Backend, data: &mut [Self]);
+
+    /// Moves the unique values of the sorted `data` to the front with the
+    /// given backend and returns how many there are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn dedup_sorted_with(backend: Backend, data: &mut [Self]) -> usize;
+
+    /// Appends a `(value, count)` pair for every run of equal values in the
+    /// sorted `data` to `counts` with the given backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn count_runs_with(backend: Backend, data: &[Self], counts: &mut Vec<(Self, usize)>);
+
+    /// Applies `op` to the sorted and duplicate free `a` and `b` with the
+    /// given backend and returns the sorted result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn set_operation_with(backend: Backend, op: SetOperation, a: &[Self], b: &[Self]) -> Vec<Self>;
+
+    /// Writes the index of the first value of `sorted` that is not below
+    /// `probes[i]` to `out[i]` with the given backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn lower_bound_batch_with(
+        backend: Backend,
+        sorted: &[Self],
+        probes: &[Self],
+        out: &mut [usize],
+    );
+
+    /// Returns the smallest and largest value of `data` with the given
+    /// backend. NaN values are skipped, or returned as both results with
+    /// `propagate_nan`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn minmax_with(backend: Backend, data: &[Self], propagate_nan: bool) -> Option<(Self, Self)>;
+
+    /// Reorders `data` with the given backend so that `data[rank]` holds the
+    /// value that would be there after sorting, for every rank in the sorted
+    /// `ranks`. Like [`slice::select_nth_unstable`], the values before a rank
+    /// are smaller or equal and the values after it larger or equal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn select_ranks_with(backend: Backend, data: &mut [Self], ranks: &[usize]);
+
+    /// Moves the values of `data` below `pivot` to the front with the given
+    /// backend and returns how many there are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    fn partition_with(backend: Backend, data: &mut [Self], pivot: Self) -> usize;
+}
+
+/// Implements [`BackendSortable`] for a 64-bit type. The avx512 and sse
+/// kernels go through the `#[target_feature]` wrappers named after the
+/// operation and `$suffix`, the others are instantiated here with the vector
+/// types of the backend. Backends without a vector type for the element
+/// type, like avx2 for u64, are left out.
+macro_rules! impl_backend_sortable {
+    (
+        $ty: ident,
+        $sort_with: ident,
+        backends: [$($backend: expr),* $(,)?],
+        avx2: [$($avx2: ty)?],
+        wasm: $wasm: ty,
+        portable: $portable: ty,
+        wrappers: {
+            small_sort: $small_sort: ident,
+            dedup_sorted: $dedup_sorted: ident,
+            count_runs: $count_runs: ident,
+            set_operation: $set_operation: ident,
+            lower_bound_batch: $lower_bound_batch: ident,
+            minmax: $minmax: ident,
+            select_ranks: $select_ranks: ident,
+            partition: $partition: ident $(,)?
+        } $(,)?
+    ) => {
+        impl BackendSortable for $ty {
+            const BACKENDS: &'static [Backend] = &[$($backend),*];
+
+            fn small_sort_kernel(backend: Backend) -> fn(&mut [Self]) {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => |data| unsafe { x86::avx512::$small_sort(data) },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => bitonic_sort::<$ty, 8, $avx2>,
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => |data| unsafe { x86::sse::$small_sort(data) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => bitonic_sort::<$ty, 8, $wasm>,
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => bitonic_sort::<$ty, 8, $portable>,
+                    Backend::Scalar => bitonic_sort::<$ty, 8, [$ty; 8]>,
+                    Backend::Std => |data| $sort_with(Backend::Std, data),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+
+            fn sort_with(backend: Backend, data: &mut [Self]) {
+                $sort_with(backend, data)
+            }
+
+            fn dedup_sorted_with(backend: Backend, data: &mut [Self]) -> usize {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => unsafe { x86::avx512::$dedup_sorted(data) },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => dedup_sorted::<$ty, 8, $avx2>(data),
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => unsafe { x86::sse::$dedup_sorted(data) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => dedup_sorted::<$ty, 8, $wasm>(data),
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => dedup_sorted::<$ty, 8, $portable>(data),
+                    Backend::Scalar => dedup_sorted::<$ty, 8, [$ty; 8]>(data),
+                    Backend::Std => dedup_sorted_std(data),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+
+            fn count_runs_with(backend: Backend, data: &[Self], counts: &mut Vec<(Self, usize)>) {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => unsafe { x86::avx512::$count_runs(data, counts) },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => count_runs::<$ty, 8, $avx2>(data, counts),
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => unsafe { x86::sse::$count_runs(data, counts) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => count_runs::<$ty, 8, $wasm>(data, counts),
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => count_runs::<$ty, 8, $portable>(data, counts),
+                    Backend::Scalar => count_runs::<$ty, 8, [$ty; 8]>(data, counts),
+                    Backend::Std => count_runs_std(data, counts),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+
+            fn set_operation_with(
+                backend: Backend,
+                op: SetOperation,
+                a: &[Self],
+                b: &[Self],
+            ) -> Vec<Self> {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => unsafe { x86::avx512::$set_operation(op, a, b) },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => set_operation::<$ty, 8, $avx2>(op, a, b),
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => unsafe { x86::sse::$set_operation(op, a, b) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => set_operation::<$ty, 8, $wasm>(op, a, b),
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => set_operation::<$ty, 8, $portable>(op, a, b),
+                    Backend::Scalar => set_operation::<$ty, 8, [$ty; 8]>(op, a, b),
+                    Backend::Std => set_operation_std(op, a, b),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+
+            fn lower_bound_batch_with(
+                backend: Backend,
+                sorted: &[Self],
+                probes: &[Self],
+                out: &mut [usize],
+            ) {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => unsafe {
+                        x86::avx512::$lower_bound_batch(sorted, probes, out)
+                    },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => {
+                            bit_64::lower_bound_batch::<$ty, 8, $avx2>(sorted, probes, out)
+                        }
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => unsafe { x86::sse::$lower_bound_batch(sorted, probes, out) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => {
+                        bit_64::lower_bound_batch::<$ty, 8, $wasm>(sorted, probes, out)
+                    }
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => {
+                        bit_64::lower_bound_batch::<$ty, 8, $portable>(sorted, probes, out)
+                    }
+                    Backend::Scalar => {
+                        bit_64::lower_bound_batch::<$ty, 8, [$ty; 8]>(sorted, probes, out)
+                    }
+                    Backend::Std => lower_bound_batch_std(sorted, probes, out),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+
+            fn minmax_with(
+                backend: Backend,
+                data: &[Self],
+                propagate_nan: bool,
+            ) -> Option<(Self, Self)> {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => unsafe { x86::avx512::$minmax(data, propagate_nan) },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => minmax::<$ty, 8, $avx2>(data, propagate_nan),
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => unsafe { x86::sse::$minmax(data, propagate_nan) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => minmax::<$ty, 8, $wasm>(data, propagate_nan),
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => minmax::<$ty, 8, $portable>(data, propagate_nan),
+                    Backend::Scalar => minmax::<$ty, 8, [$ty; 8]>(data, propagate_nan),
+                    Backend::Std => minmax_std(data, propagate_nan),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+
+            fn select_ranks_with(backend: Backend, data: &mut [Self], ranks: &[usize]) {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                let max_iters = f64::log2(data.len() as f64) as i64;
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => unsafe { x86::avx512::$select_ranks(data, ranks) },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => {
+                            select_ranks_64bit_::<$ty, 8, $avx2>(data, ranks, max_iters)
+                        }
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => unsafe { x86::sse::$select_ranks(data, ranks) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => {
+                        select_ranks_64bit_::<$ty, 8, $wasm>(data, ranks, max_iters)
+                    }
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => {
+                        select_ranks_64bit_::<$ty, 8, $portable>(data, ranks, max_iters)
+                    }
+                    Backend::Scalar => {
+                        select_ranks_64bit_::<$ty, 8, [$ty; 8]>(data, ranks, max_iters)
+                    }
+                    Backend::Std => select_ranks_std(data, ranks),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+
+            fn partition_with(backend: Backend, data: &mut [Self], pivot: Self) -> usize {
+                assert!(
+                    backend.is_available(),
+                    "{:?} backend is not available",
+                    backend
+                );
+                match backend {
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports avx512f
+                    Backend::Avx512 => unsafe { x86::avx512::$partition(data, pivot) },
+                    $(
+                        #[cfg(target_arch = "x86_64")]
+                        Backend::Avx2 => partition_64bit_::<$ty, 8, $avx2>(data, pivot),
+                    )?
+                    #[cfg(target_arch = "x86_64")]
+                    // This is safe since we have verified the cpu supports sse4.2
+                    Backend::Sse => unsafe { x86::sse::$partition(data, pivot) },
+                    #[cfg(target_family = "wasm")]
+                    Backend::Wasm128 => partition_64bit_::<$ty, 8, $wasm>(data, pivot),
+                    #[cfg(feature = "nightly")]
+                    Backend::PortableSimd => partition_64bit_::<$ty, 8, $portable>(data, pivot),
+                    Backend::Scalar => partition_64bit_::<$ty, 8, [$ty; 8]>(data, pivot),
+                    Backend::Std => partition_std(data, pivot),
+                    #[allow(unreachable_patterns)]
+                    _ => unsupported_backend(backend, stringify!($ty)),
+                }
+            }
+        }
+    };
+}
+
+// The backends after Std are slower than the sort of the standard library, so
+// they are only used when asked for
+impl_backend_sortable!(
+    i64,
+    sort_i64_with,
+    backends: [
+        Backend::Avx512,
+        Backend::Avx2,
+        Backend::Wasm128,
+        Backend::Std,
+        Backend::Sse,
+        Backend::PortableSimd,
+        Backend::Scalar,
+    ],
+    avx2: [x86::avx2::i64_impl::Avx2I64x2],
+    wasm: wasm::bit64::Wasmi64x8,
+    portable: std::simd::i64x8,
+    wrappers: {
+        small_sort: small_sort_i64,
+        dedup_sorted: dedup_sorted_i64,
+        count_runs: count_runs_i64,
+        set_operation: set_operation_i64,
+        lower_bound_batch: lower_bound_batch_i64,
+        minmax: minmax_i64,
+        select_ranks: select_ranks_i64,
+        partition: partition_i64,
+    },
+);
+
+impl_backend_sortable!(
+    u64,
+    sort_u64_with,
+    backends: [
+        Backend::Avx512,
+        Backend::Wasm128,
+        Backend::Std,
+        Backend::Sse,
+        Backend::PortableSimd,
+        Backend::Scalar,
+    ],
+    avx2: [],
+    wasm: wasm::bit64::Wasmu64x8,
+    portable: std::simd::u64x8,
+    wrappers: {
+        small_sort: small_sort_u64,
+        dedup_sorted: dedup_sorted_u64,
+        count_runs: count_runs_u64,
+        set_operation: set_operation_u64,
+        lower_bound_batch: lower_bound_batch_u64,
+        minmax: minmax_u64,
+        select_ranks: select_ranks_u64,
+        partition: partition_u64,
+    },
+);
+
+impl_backend_sortable!(
+    f64,
+    sort_f64_with,
+    backends: [
+        Backend::Avx512,
+        Backend::Avx2,
+        Backend::Wasm128,
+        Backend::Std,
+        Backend::Sse,
+        Backend::PortableSimd,
+        Backend::Scalar,
+    ],
+    avx2: [x86::avx2::f64_impl::Avx2F64x2],
+    wasm: wasm::bit64::Wasmf64x8,
+    portable: std::simd::f64x8,
+    wrappers: {
+        small_sort: small_sort_f64,
+        dedup_sorted: dedup_sorted_f64,
+        count_runs: count_runs_f64,
+        set_operation: set_operation_f64,
+        lower_bound_batch: lower_bound_batch_f64,
+        minmax: minmax_f64,
+        select_ranks: select_ranks_f64,
+        partition: partition_f64,
+    },
+);
+
+/// Panics for a backend without kernels for `ty`
+fn unsupported_backend(backend: Backend, ty: &str) -> ! {
+    panic!("{:?} backend does not support {}", backend, ty)
+}
+
+/// Returns the backend used to sort `T` on this target and cpu
+pub fn selected_backend<T: BackendSortable>() -> Backend {
+    T::BACKENDS
+        .iter()
+        .copied()
+        .find(|backend| backend.is_available())
+        .unwrap_or(Backend::Std)
+}
+
+/// Backends with a counting sort for `u8` and `i8`, in order of preference
+pub const BACKENDS_8BIT: &[Backend] = &[Backend::Avx512, Backend::Scalar];
+
+/// Returns the backend used by [`sort_u8`] and [`sort_i8`] on this target
+/// and cpu
+pub fn selected_8bit_backend() -> Backend {
+    BACKENDS_8BIT
+        .iter()
+        .copied()
+        .find(|backend| backend.is_available())
+        .unwrap_or(Backend::Std)
+}
+
+/// Returns all backends that can run on this target and cpu
+pub fn available_backends() -> Vec<Backend> {
+    Backend::ALL
+        .into_iter()
+        .filter(|backend| backend.is_available())
+        .collect()
+}
+
 pub fn sort_i64(data: &mut [i64]) {
-    #[cfg(all(target_arch = "x86_64", not(feature = "nightly")))]
-    {
-        if cfg!(target_feature = "avx2") {
-            x86::avx2::avx2_sort_i64(data)
+    sort_i64_with(selected_backend::<i64>(), data)
+}
+
+pub fn sort_u64(data: &mut [u64]) {
+    sort_u64_with(selected_backend::<u64>(), data)
+}
+
+pub fn sort_f64(data: &mut [f64]) {
+    sort_f64_with(selected_backend::<f64>(), data)
+}
+
+/// Sorts `data` by the keys of [`SimdSortKey`] with the selected backend of
+/// the key type. Types that are their own key, like `i64` and `u64`, are
+/// sorted in place, the keys of the others are sorted in a separate buffer
+/// and mapped back.
+pub fn sort<T: SimdSortKey>(data: &mut [T]) {
+    if let Some(keys) = T::as_keys(data) {
+        return T::Key::sort_with(selected_backend::<T::Key>(), keys);
+    }
+    let mut keys: Vec<T::Key> = data.iter().map(|&value| value.to_key()).collect();
+    T::Key::sort_with(selected_backend::<T::Key>(), &mut keys);
+    for (value, key) in data.iter_mut().zip(keys) {
+        *value = T::from_key(key);
+    }
+}
+
+/// Sorts `data` with the quicksort and bitonic networks built on the
+/// register type `B`, like a custom or emulated backend.
+pub fn sort_with_backend<T: Bit64Element, const N: usize, B: SortBackend<T, N>>(data: &mut [T]) {
+    qsort_64bit_::<T, N, B>(data, f64::log2(data.len() as f64) as i64)
+}
+
+/// Sorts `data` with the selected backend of `u64`, see [`sort_usize_with`]
+pub fn sort_usize(data: &mut [usize]) {
+    sort_usize_with(selected_backend::<u64>(), data)
+}
+
+/// Sorts `data` with the selected backend of `i64`, see [`sort_isize_with`]
+pub fn sort_isize(data: &mut [isize]) {
+    sort_isize_with(selected_backend::<i64>(), data)
+}
+
+/// Sorts `data` with a counting sort on the first available backend of
+/// [`BACKENDS_8BIT`], see [`sort_u8_with`]
+pub fn sort_u8(data: &mut [u8]) {
+    sort_u8_with(selected_8bit_backend(), data)
+}
+
+/// Sorts `data` with a counting sort on the first available backend of
+/// [`BACKENDS_8BIT`], see [`sort_i8_with`]
+pub fn sort_i8(data: &mut [i8]) {
+    sort_i8_with(selected_8bit_backend(), data)
+}
+
+/// Sorts a slice of at most [`SMALL_SORT_MAX_LEN`] values with the bitonic
+/// networks of the selected backend, skipping the quicksort driver.
+///
+/// # Panics
+///
+/// Panics if `data` is longer than [`SMALL_SORT_MAX_LEN`].
+pub fn sort_small<T: BackendSortable>(data: &mut [T]) {
+    assert!(
+        data.len() <= SMALL_SORT_MAX_LEN,
+        "sort_small called with {} values, at most {} are supported",
+        data.len(),
+        SMALL_SORT_MAX_LEN
+    );
+    T::small_sort_kernel(selected_backend::<T>())(data)
+}
+
+/// Sorts every slice with [`sort_small`], selecting the backend only once.
+///
+/// # Panics
+///
+/// Panics if a slice is longer than [`SMALL_SORT_MAX_LEN`].
+pub fn sort_small_batch<'a, T, I>(slices: I)
+where
+    T: BackendSortable + 'a,
+    I: IntoIterator<Item = &'a mut [T]>,
+{
+    let kernel = T::small_sort_kernel(selected_backend::<T>());
+    for data in slices {
+        assert!(
+            data.len() <= SMALL_SORT_MAX_LEN,
+            "sort_small_batch called with {} values, at most {} are supported",
+            data.len(),
+            SMALL_SORT_MAX_LEN
+        );
+        kernel(data);
+    }
+}
+
+/// Sorts every segment `data[offsets[i]..offsets[i + 1]]` independently.
+///
+/// `offsets` holds the segment boundaries, so `n` segments need `n + 1`
+/// offsets. Values before the first and after the last offset are not
+/// touched. Segments of at most [`SMALL_SORT_MAX_LEN`] values go straight to
+/// the bitonic networks, longer ones to the quicksort of the same backend.
+///
+/// # Panics
+///
+/// Panics if `offsets` is decreasing anywhere or exceeds `data.len()`.
+pub fn sort_segments<T: BackendSortable>(data: &mut [T], offsets: &[usize]) {
+    let backend = selected_backend::<T>();
+    let kernel = T::small_sort_kernel(backend);
+    for bounds in offsets.windows(2) {
+        let segment = &mut data[bounds[0]..bounds[1]];
+        if segment.len() <= SMALL_SORT_MAX_LEN {
+            kernel(segment);
         } else {
-            data.sort_unstable()
+            T::sort_with(backend, segment);
+        }
+    }
+}
+
+/// Moves the first value of every run of equal values in the sorted `data` to
+/// the front, keeping their order, and returns how many there are. The
+/// values after the returned count are left in an unspecified order.
+pub fn dedup_sorted_i64(data: &mut [i64]) -> usize {
+    i64::dedup_sorted_with(selected_backend::<i64>(), data)
+}
+
+/// Moves the first value of every run of equal values in the sorted `data` to
+/// the front, keeping their order, and returns how many there are. The
+/// values after the returned count are left in an unspecified order.
+pub fn dedup_sorted_u64(data: &mut [u64]) -> usize {
+    u64::dedup_sorted_with(selected_backend::<u64>(), data)
+}
+
+/// Moves the first value of every run of equal values in the sorted `data` to
+/// the front, keeping their order, and returns how many there are. `-0.0` and
+/// `0.0` count as equal. The values after the returned count are left in an
+/// unspecified order.
+pub fn dedup_sorted_f64(data: &mut [f64]) -> usize {
+    f64::dedup_sorted_with(selected_backend::<f64>(), data)
+}
+
+/// Sorts `data` and moves its unique values to the front, returning how many
+/// there are, so `&data[..sort_unique(data)]` is sorted and free of
+/// duplicates.
+pub fn sort_unique<T: BackendSortable>(data: &mut [T]) -> usize {
+    let backend = selected_backend::<T>();
+    T::sort_with(backend, data);
+    T::dedup_sorted_with(backend, data)
+}
+
+/// Sorts `data` and returns a `(value, count)` pair for every distinct value,
+/// in ascending order of the values.
+pub fn sort_and_count<T: BackendSortable>(data: &mut [T]) -> Vec<(T, usize)> {
+    let mut counts = Vec::new();
+    sort_and_count_into(data, &mut counts);
+    counts
+}
+
+/// Like [`sort_and_count`], but writes the pairs into `counts` after clearing
+/// it, so its allocation can be reused.
+pub fn sort_and_count_into<T: BackendSortable>(data: &mut [T], counts: &mut Vec<(T, usize)>) {
+    let backend = selected_backend::<T>();
+    T::sort_with(backend, data);
+    counts.clear();
+    T::count_runs_with(backend, data, counts);
+}
+
+/// Returns the sorted values that are in both of the sorted and duplicate
+/// free `a` and `b`, as produced by [`sort_unique`].
+pub fn intersect_sorted<T: BackendSortable>(a: &[T], b: &[T]) -> Vec<T> {
+    T::set_operation_with(selected_backend::<T>(), SetOperation::Intersection, a, b)
+}
+
+/// Returns the sorted values that are in either of the sorted and duplicate
+/// free `a` and `b`, as produced by [`sort_unique`].
+pub fn union_sorted<T: BackendSortable>(a: &[T], b: &[T]) -> Vec<T> {
+    T::set_operation_with(selected_backend::<T>(), SetOperation::Union, a, b)
+}
+
+/// Returns the sorted values of the sorted and duplicate free `a` that are not
+/// in the sorted and duplicate free `b`, as produced by [`sort_unique`].
+pub fn difference_sorted<T: BackendSortable>(a: &[T], b: &[T]) -> Vec<T> {
+    T::set_operation_with(selected_backend::<T>(), SetOperation::Difference, a, b)
+}
+
+/// Writes the index of the first value of the sorted `sorted` that is not
+/// below `probes[i]` to `out[i]`, like [`slice::partition_point`], searching
+/// a vector of probes at a time.
+///
+/// # Panics
+///
+/// Panics if `probes` and `out` have different lengths.
+pub fn lower_bound_batch<T: BackendSortable>(sorted: &[T], probes: &[T], out: &mut [usize]) {
+    assert_eq!(
+        probes.len(),
+        out.len(),
+        "lower_bound_batch needs an output index for every probe"
+    );
+    T::lower_bound_batch_with(selected_backend::<T>(), sorted, probes, out)
+}
+
+/// Returns the smallest and largest value of `data`, or `None` if it is
+/// empty.
+pub fn minmax_i64(data: &[i64]) -> Option<(i64, i64)> {
+    i64::minmax_with(selected_backend::<i64>(), data, false)
+}
+
+/// Returns the smallest and largest value of `data`, or `None` if it is
+/// empty.
+pub fn minmax_u64(data: &[u64]) -> Option<(u64, u64)> {
+    u64::minmax_with(selected_backend::<u64>(), data, false)
+}
+
+/// Returns the smallest and largest value of `data`, skipping NaN like
+/// [`f64::min`] and [`f64::max`] do, or `None` if there are only NaN values.
+pub fn minmax_f64(data: &[f64]) -> Option<(f64, f64)> {
+    f64::minmax_with(selected_backend::<f64>(), data, false)
+}
+
+/// Returns the smallest and largest value of `data`, or NaN as both if there
+/// is a NaN value, or `None` if it is empty.
+pub fn minmax_f64_propagate_nan(data: &[f64]) -> Option<(f64, f64)> {
+    f64::minmax_with(selected_backend::<f64>(), data, true)
+}
+
+/// Returns the given percentiles of `data`, interpolating linearly between
+/// ranks. See [`percentiles_f64_with`].
+///
+/// # Panics
+///
+/// Panics if `data` is empty or contains NaN, or a percentile is outside
+/// `0.0..=100.0`.
+pub fn percentiles_f64(data: &mut [f64], percentiles: &[f64]) -> Vec<f64> {
+    percentiles_f64_with(data, percentiles, QuantileMethod::Linear)
+}
+
+/// Returns the given percentiles, from `0.0` to `100.0`, of `data`.
+///
+/// All percentiles are selected in a single quickselect descent, so `data` is
+/// left partially sorted.
+///
+/// # Panics
+///
+/// Panics if `data` is empty or contains NaN, or a percentile is outside
+/// `0.0..=100.0`.
+pub fn percentiles_f64_with(
+    data: &mut [f64],
+    percentiles: &[f64],
+    method: QuantileMethod,
+) -> Vec<f64> {
+    assert!(!data.is_empty(), "percentiles of an empty slice");
+    let backend = selected_backend::<f64>();
+    // NaN has no rank, the minimum is NaN if any value is
+    let (min, _) = f64::minmax_with(backend, data, true).unwrap();
+    assert!(!min.is_nan(), "percentiles of a slice containing NaN");
+    let len = data.len();
+    // the rank of every percentile and how far it is towards the next rank
+    let positions: Vec<(usize, f64)> = percentiles
+        .iter()
+        .map(|&percentile| {
+            assert!(
+                (0.0..=100.0).contains(&percentile),
+                "percentile {} is not within 0..=100",
+                percentile
+            );
+            match method {
+                QuantileMethod::NearestRank => {
+                    let rank = (percentile * len as f64 / 100.0).ceil() as usize;
+                    (rank.max(1) - 1, 0.0)
+                }
+                QuantileMethod::Linear => {
+                    let position = percentile * (len - 1) as f64 / 100.0;
+                    let rank = position.floor() as usize;
+                    (rank, position - rank as f64)
+                }
+            }
+        })
+        .collect();
+
+    let mut ranks = Vec::with_capacity(2 * positions.len());
+    for &(rank, fraction) in &positions {
+        ranks.push(rank);
+        if fraction > 0.0 {
+            ranks.push(rank + 1);
+        }
+    }
+    ranks.sort_unstable();
+    ranks.dedup();
+    f64::select_ranks_with(backend, data, &ranks);
+
+    positions
+        .into_iter()
+        .map(|(rank, fraction)| {
+            if fraction > 0.0 {
+                data[rank] + fraction * (data[rank + 1] - data[rank])
+            } else {
+                data[rank]
+            }
+        })
+        .collect()
+}
+
+/// Returns the median of `data`, the mean of the two middle values for an even
+/// length, or `None` if it is empty. `data` is left partially sorted.
+///
+/// # Panics
+///
+/// Panics if `data` contains NaN.
+pub fn median_f64(data: &mut [f64]) -> Option<f64> {
+    if data.is_empty() {
+        return None;
+    }
+    Some(percentiles_f64(data, &[50.0])[0])
+}
+
+fn partition_std<T: SimdSortable>(data: &mut [T], pivot: T) -> usize {
+    let mut below = 0;
+    for i in 0..data.len() {
+        if data[i] < pivot {
+            data.swap(i, below);
+            below += 1;
+        }
+    }
+    below
+}
+
+fn select_ranks_std<T: SimdSortable>(data: &mut [T], ranks: &[usize]) {
+    let mut start = 0;
+    for &rank in ranks {
+        data[start..].select_nth_unstable_by(rank - start, |a, b| a.partial_cmp(b).unwrap());
+        start = rank + 1;
+    }
+}
+
+#[allow(clippy::eq_op)]
+fn minmax_std<T: SimdSortable>(data: &[T], propagate_nan: bool) -> Option<(T, T)> {
+    let mut result: Option<(T, T)> = None;
+    for &value in data {
+        // only NaN is not equal to itself
+        if value != value {
+            if propagate_nan {
+                return Some((value, value));
+            }
+            continue;
         }
+        result = match result {
+            None => Some((value, value)),
+            Some((min, max)) => Some((
+                if value < min { value } else { min },
+                if value > max { value } else { max },
+            )),
+        };
+    }
+    result
+}
+
+fn lower_bound_batch_std<T: SimdSortable>(sorted: &[T], probes: &[T], out: &mut [usize]) {
+    for (probe, index) in probes.iter().zip(out) {
+        *index = sorted.partition_point(|value| value < probe);
+    }
+}
+
+fn set_operation_std<T: SimdSortable>(op: SetOperation, a: &[T], b: &[T]) -> Vec<T> {
+    let contains = |data: &[T], value: &T| {
+        data.binary_search_by(|x| x.partial_cmp(value).unwrap())
+            .is_ok()
+    };
+    match op {
+        SetOperation::Intersection => a.iter().copied().filter(|x| contains(b, x)).collect(),
+        SetOperation::Difference => a.iter().copied().filter(|x| !contains(b, x)).collect(),
+        SetOperation::Union => {
+            let rest: Vec<T> = b.iter().copied().filter(|x| !contains(a, x)).collect();
+            merge_sorted(a, &rest)
+        }
+    }
+}
+
+fn count_runs_std<T: SimdSortable>(data: &[T], counts: &mut Vec<(T, usize)>) {
+    for &value in data {
+        match counts.last_mut() {
+            Some((last, count)) if *last == value => *count += 1,
+            _ => counts.push((value, 1)),
+        }
+    }
+}
+
+fn dedup_sorted_std<T: SimdSortable>(data: &mut [T]) -> usize {
+    if data.is_empty() {
+        return 0;
+    }
+    let mut write = 1;
+    for read in 1..data.len() {
+        if data[read] != data[write - 1] {
+            data[write] = data[read];
+            write += 1;
+        }
+    }
+    write
+}
+
+/// Sorts `data` with the given backend.
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu.
+pub fn sort_i64_with(backend: Backend, data: &mut [i64]) {
+    assert!(
+        backend.is_available(),
+        "{:?} backend is not available",
+        backend
+    );
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports avx512f
+        Backend::Avx512 => unsafe { x86::avx512::qsort_i64(data) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 => x86::avx2::avx2_sort_i64(data),
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports sse4.2
+        Backend::Sse => unsafe { x86::sse::qsort_i64(data) },
+        #[cfg(target_family = "wasm")]
+        Backend::Wasm128 => wasm::wasm128_sort_i64(data),
+        #[cfg(feature = "nightly")]
+        Backend::PortableSimd => nightly::portable_simd_sort_i64(data),
+        Backend::Scalar => scalar::scalar_sort_i64(data),
+        Backend::Std => data.sort_unstable(),
+        #[allow(unreachable_patterns)]
+        _ => unsupported_backend(backend, "i64"),
+    }
+}
+
+/// Sorts `data` with the given backend.
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu, or
+/// does not support u64.
+pub fn sort_u64_with(backend: Backend, data: &mut [u64]) {
+    assert!(
+        backend.is_available(),
+        "{:?} backend is not available",
+        backend
+    );
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports avx512f
+        Backend::Avx512 => unsafe { x86::avx512::qsort_u64(data) },
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports sse4.2
+        Backend::Sse => unsafe { x86::sse::qsort_u64(data) },
+        #[cfg(target_family = "wasm")]
+        Backend::Wasm128 => wasm::wasm128_sort_u64(data),
+        #[cfg(feature = "nightly")]
+        Backend::PortableSimd => nightly::portable_simd_sort_u64(data),
+        Backend::Scalar => scalar::scalar_sort_u64(data),
+        Backend::Std => data.sort_unstable(),
+        #[allow(unreachable_patterns)]
+        _ => unsupported_backend(backend, "u64"),
+    }
+}
+
+/// Sorts `data` with the given backend.
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu, or
+/// does not support f64.
+pub fn sort_f64_with(backend: Backend, data: &mut [f64]) {
+    assert!(
+        backend.is_available(),
+        "{:?} backend is not available",
+        backend
+    );
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports avx512f
+        Backend::Avx512 => unsafe { x86::avx512::qsort_f64(data) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 => x86::avx2::avx2_sort_f64(data),
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports sse4.2
+        Backend::Sse => unsafe { x86::sse::qsort_f64(data) },
+        #[cfg(target_family = "wasm")]
+        Backend::Wasm128 => wasm::wasm128_sort_f64(data),
+        #[cfg(feature = "nightly")]
+        Backend::PortableSimd => nightly::portable_simd_sort_f64(data),
+        Backend::Scalar => scalar::scalar_sort_f64(data),
+        Backend::Std => data.sort_unstable_by(f64::total_cmp),
+        #[allow(unreachable_patterns)]
+        _ => unsupported_backend(backend, "f64"),
+    }
+}
+
+/// Sorts `data` with the given backend. On 64-bit targets the values are
+/// sorted in place as `u64`.
+///
+/// There is no 32-bit kernel, so on other targets like wasm32 `data` is
+/// sorted with `sort_unstable` whatever the backend.
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu, or
+/// does not support u64.
+pub fn sort_usize_with(backend: Backend, data: &mut [usize]) {
+    #[cfg(target_pointer_width = "64")]
+    {
+        // This is safe since usize and u64 have the same size and alignment
+        // on 64-bit targets
+        let data =
+            unsafe { std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u64, data.len()) };
+        sort_u64_with(backend, data)
+    }
+    #[cfg(not(target_pointer_width = "64"))]
+    {
+        assert!(
+            backend.is_available(),
+            "{:?} backend is not available",
+            backend
+        );
+        data.sort_unstable()
     }
+}
 
-    #[cfg(all(target_arch = "x86_64", feature = "nightly"))]
+/// Sorts `data` with the given backend. On 64-bit targets the values are
+/// sorted in place as `i64`.
+///
+/// There is no 32-bit kernel, so on other targets like wasm32 `data` is
+/// sorted with `sort_unstable` whatever the backend.
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu.
+pub fn sort_isize_with(backend: Backend, data: &mut [isize]) {
+    #[cfg(target_pointer_width = "64")]
+    {
+        // This is safe since isize and i64 have the same size and alignment
+        // on 64-bit targets
+        let data =
+            unsafe { std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut i64, data.len()) };
+        sort_i64_with(backend, data)
+    }
+    #[cfg(not(target_pointer_width = "64"))]
     {
-        if cfg!(target_feature = "avx512f") {
-            x86::avx512::avx512_sort_i64(data)
+        assert!(
+            backend.is_available(),
+            "{:?} backend is not available",
+            backend
+        );
+        data.sort_unstable()
+    }
+}
+
+/// Sorts `data` with a counting sort on the given backend.
+///
+/// The avx512 backend counts the bytes with gathers and scatters into a
+/// table per lane and fills the runs with whole registers. The scalar
+/// backend counts them with increments, and std sorts with `sort_unstable`.
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu, or is not
+/// one of [`BACKENDS_8BIT`] or [`Backend::Std`].
+pub fn sort_u8_with(backend: Backend, data: &mut [u8]) {
+    sort_8bit_with(backend, data, 0, "u8")
+}
+
+/// Sorts `data` with a counting sort on the given backend, see
+/// [`sort_u8_with`].
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu, or is not
+/// one of [`BACKENDS_8BIT`] or [`Backend::Std`].
+pub fn sort_i8_with(backend: Backend, data: &mut [i8]) {
+    // This is safe since i8 and u8 have the same size and alignment
+    let data = unsafe { std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, data.len()) };
+    // flipping the sign bit makes the unsigned order the signed one
+    sort_8bit_with(backend, data, 0x80, "i8")
+}
+
+fn sort_8bit_with(backend: Backend, data: &mut [u8], bias: u8, ty: &str) {
+    assert!(
+        backend.is_available(),
+        "{:?} backend is not available",
+        backend
+    );
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports avx512f
+        Backend::Avx512 => unsafe { x86::avx512::counting_sort_8bit(data, bias) },
+        Backend::Scalar => counting_sort_8bit(data, bias),
+        Backend::Std => data.sort_unstable_by_key(|&byte| byte ^ bias),
+        #[allow(unreachable_patterns)]
+        _ => unsupported_backend(backend, ty),
+    }
+}
+
+/// Flips the sign bit so the signed order of the result is the unsigned
+/// order of `value`
+pub(crate) const SIGN_BIT: u64 = 1 << 63;
+
+/// Sorts `data` in ascending order.
+pub fn sort_u128(data: &mut [u128]) {
+    sort_128bit_keys_with(
+        selected_backend::<i64>(),
+        data,
+        |key| {
+            (
+                ((key >> 64) as u64 ^ SIGN_BIT) as i64,
+                (key as u64 ^ SIGN_BIT) as i64,
+            )
+        },
+        |hi, lo| ((hi as u64 ^ SIGN_BIT) as u128) << 64 | (lo as u64 ^ SIGN_BIT) as u128,
+    )
+}
+
+/// Sorts `data` in ascending order.
+pub fn sort_i128(data: &mut [i128]) {
+    sort_128bit_keys_with(
+        selected_backend::<i64>(),
+        data,
+        |key| ((key >> 64) as i64, (key as u64 ^ SIGN_BIT) as i64),
+        |hi, lo| (hi as i128) << 64 | (lo as u64 ^ SIGN_BIT) as i128,
+    )
+}
+
+/// Sorts `data` in ascending order, by the first value and then by the
+/// second one.
+pub fn sort_pairs_u64(data: &mut [(u64, u64)]) {
+    sort_128bit_keys_with(
+        selected_backend::<i64>(),
+        data,
+        |(first, second)| ((first ^ SIGN_BIT) as i64, (second ^ SIGN_BIT) as i64),
+        |hi, lo| (hi as u64 ^ SIGN_BIT, lo as u64 ^ SIGN_BIT),
+    )
+}
+
+/// Splits the keys of `data` into halves with `split`, sorts them as pairs of
+/// `i64` and writes them back with `join`
+fn sort_128bit_keys_with<K: Copy>(
+    backend: Backend,
+    data: &mut [K],
+    split: fn(K) -> (i64, i64),
+    join: fn(i64, i64) -> K,
+) {
+    let (mut hi, mut lo): (Vec<i64>, Vec<i64>) = data.iter().map(|&key| split(key)).unzip();
+    sort_128bit_with(backend, &mut hi, &mut lo);
+    for (key, (hi, lo)) in data.iter_mut().zip(hi.into_iter().zip(lo)) {
+        *key = join(hi, lo);
+    }
+}
+
+/// Sorts the keys whose halves are `hi[i]` and `lo[i]` with the given
+/// backend, comparing `hi` first.
+///
+/// # Panics
+///
+/// Panics if the backend is not available on this target or cpu.
+pub(crate) fn sort_128bit_with(backend: Backend, hi: &mut [i64], lo: &mut [i64]) {
+    assert!(
+        backend.is_available(),
+        "{:?} backend is not available",
+        backend
+    );
+    let max_iters = f64::log2(hi.len() as f64) as i64;
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports avx512f
+        Backend::Avx512 => unsafe { x86::avx512::qsort_128bit(hi, lo) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 => qsort_128bit_::<x86::avx2::i64_impl::Avx2I64x2>(hi, lo, max_iters),
+        #[cfg(target_arch = "x86_64")]
+        // This is safe since we have verified the cpu supports sse4.2
+        Backend::Sse => unsafe { x86::sse::qsort_128bit(hi, lo) },
+        #[cfg(target_family = "wasm")]
+        Backend::Wasm128 => qsort_128bit_::<wasm::bit64::Wasmi64x8>(hi, lo, max_iters),
+        #[cfg(feature = "nightly")]
+        Backend::PortableSimd => qsort_128bit_::<std::simd::i64x8>(hi, lo, max_iters),
+        Backend::Scalar => qsort_128bit_::<[i64; 8]>(hi, lo, max_iters),
+        Backend::Std => sort_128bit_std(hi, lo),
+        #[allow(unreachable_patterns)]
+        _ => unsupported_backend(backend, "i64"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bit_64::test::random_u64;
+
+    fn random_i64(len: usize) -> Vec<i64> {
+        random_u64(len).into_iter().map(|x| x as i64).collect()
+    }
+
+    fn random_f64(len: usize) -> Vec<f64> {
+        random_i64(len).into_iter().map(|x| x as f64).collect()
+    }
+
+    const LENGTHS: [usize; 10] = [0, 1, 7, 8, 9, 255, 256, 257, 1000, 10_000];
+
+    #[test]
+    fn test_sort_i64_with_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let mut data = random_i64(len);
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                sort_i64_with(backend, &mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+            }
         }
-        if cfg!(target_feature = "avx2") {
-            x86::avx2::avx2_sort_i64(data)
-        } else {
-            data.sort_unstable()
+    }
+
+    #[test]
+    fn test_sort_u64_with_each_backend() {
+        for backend in available_backends() {
+            if backend == Backend::Avx2 {
+                continue;
+            }
+            for len in LENGTHS {
+                let mut data = random_u64(len);
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                sort_u64_with(backend, &mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_f64_with_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let mut data = random_f64(len);
+                let mut expected = data.clone();
+                expected.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                sort_f64_with(backend, &mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+            }
         }
     }
 
-    #[cfg(not(target_arch = "x86_64"))]
-    data.sort_unstable()
+    #[test]
+    fn test_sort_i64() {
+        let mut data = random_i64(10_000);
+        let mut expected = data.clone();
+        expected.sort_unstable();
+        sort_i64(&mut data);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    #[cfg(not(target_family = "wasm"))]
+    #[should_panic]
+    fn test_sort_i64_with_unavailable_backend() {
+        sort_i64_with(Backend::Wasm128, &mut [3, 2, 1]);
+    }
+
+    #[test]
+    fn test_sort_f64_std_nan() {
+        let mut data = [1.0, f64::NAN, -f64::NAN, 0.0, -0.0];
+        sort_f64_with(Backend::Std, &mut data);
+        let expected = [-f64::NAN, -0.0, 0.0, 1.0, f64::NAN];
+        assert!(data
+            .iter()
+            .zip(expected)
+            .all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+
+    #[test]
+    fn test_selected_backend() {
+        let backends = available_backends();
+        assert!(backends.contains(&Backend::Scalar));
+        assert!(backends.contains(&Backend::Std));
+        assert!(backends.contains(&selected_backend::<i64>()));
+        assert!(backends.contains(&selected_backend::<u64>()));
+        assert!(backends.contains(&selected_backend::<f64>()));
+        assert!(u64::BACKENDS.contains(&selected_backend::<u64>()));
+    }
+
+    #[test]
+    fn test_small_sort_kernel_each_backend() {
+        for backend in available_backends() {
+            for len in 0..=SMALL_SORT_MAX_LEN {
+                let mut data = random_i64(len);
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                i64::small_sort_kernel(backend)(&mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+
+                let mut data = random_f64(len);
+                let mut expected = data.clone();
+                expected.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                f64::small_sort_kernel(backend)(&mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+
+                if u64::BACKENDS.contains(&backend) {
+                    let mut data = random_u64(len);
+                    let mut expected = data.clone();
+                    expected.sort_unstable();
+                    u64::small_sort_kernel(backend)(&mut data);
+                    assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_small() {
+        for len in [0, 1, 7, 8, 9, 100, 255, 256] {
+            let mut data = random_u64(len);
+            let mut expected = data.clone();
+            expected.sort_unstable();
+            sort_small(&mut data);
+            assert_eq!(data, expected, "failed for len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_sort_small_batch() {
+        let mut data = random_i64(10_000);
+        let mut expected = data.clone();
+        for chunk in expected.chunks_mut(37) {
+            chunk.sort_unstable();
+        }
+        sort_small_batch(data.chunks_mut(37));
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sort_small_too_long() {
+        sort_small(&mut random_f64(SMALL_SORT_MAX_LEN + 1));
+    }
+
+    #[test]
+    fn test_sort_segments() {
+        let mut data = random_f64(20_000);
+        let mut offsets = vec![3];
+        for len in [0, 1, 2, 7, 8, 9, 64, 255, 256, 257, 1000, 0, 5, 10_000] {
+            offsets.push(offsets.last().unwrap() + len);
+        }
+        let mut expected = data.clone();
+        for bounds in offsets.windows(2) {
+            expected[bounds[0]..bounds[1]].sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        }
+        sort_segments(&mut data, &offsets);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_sort_segments_without_segments() {
+        let mut data = random_u64(100);
+        let expected = data.clone();
+        sort_segments(&mut data, &[]);
+        sort_segments(&mut data, &[50]);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sort_segments_out_of_bounds() {
+        sort_segments(&mut random_i64(10), &[0, 5, 11]);
+    }
+
+    #[test]
+    fn test_dedup_sorted_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let mut data: Vec<i64> = random_i64(len).into_iter().map(|x| x % 64).collect();
+                data.sort_unstable();
+                let mut expected = data.clone();
+                expected.dedup();
+                let unique = i64::dedup_sorted_with(backend, &mut data);
+                assert_eq!(
+                    &data[..unique],
+                    &expected[..],
+                    "{:?} failed for len {}",
+                    backend,
+                    len
+                );
+
+                let mut data: Vec<f64> = random_i64(len)
+                    .into_iter()
+                    .map(|x| (x % 64) as f64)
+                    .collect();
+                data.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                let mut expected = data.clone();
+                expected.dedup();
+                let unique = f64::dedup_sorted_with(backend, &mut data);
+                assert_eq!(
+                    &data[..unique],
+                    &expected[..],
+                    "{:?} failed for len {}",
+                    backend,
+                    len
+                );
+
+                if u64::BACKENDS.contains(&backend) {
+                    let mut data: Vec<u64> = random_u64(len).into_iter().map(|x| x % 64).collect();
+                    data.sort_unstable();
+                    let mut expected = data.clone();
+                    expected.dedup();
+                    let unique = u64::dedup_sorted_with(backend, &mut data);
+                    assert_eq!(
+                        &data[..unique],
+                        &expected[..],
+                        "{:?} failed for len {}",
+                        backend,
+                        len
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dedup_sorted() {
+        let mut data = vec![1, 1, 2, 3, 3, 3, 3, 3, 3, 3, 3, 3, 4, 5, 5];
+        assert_eq!(dedup_sorted_i64(&mut data), 5);
+        assert_eq!(data[..5], [1, 2, 3, 4, 5]);
+
+        let mut data = vec![u64::MAX; 20];
+        assert_eq!(dedup_sorted_u64(&mut data), 1);
+
+        let mut data = vec![-1.0, -0.0, 0.0, 0.0, 2.5, 2.5, 2.5, 2.5, 2.5, 2.5, 3.0];
+        assert_eq!(dedup_sorted_f64(&mut data), 4);
+        assert_eq!(data[..4], [-1.0, -0.0, 2.5, 3.0]);
+    }
+
+    #[test]
+    fn test_sort_unique() {
+        let mut data: Vec<u64> = random_u64(10_000).into_iter().map(|x| x % 1000).collect();
+        let mut expected = data.clone();
+        expected.sort_unstable();
+        expected.dedup();
+        let unique = sort_unique(&mut data);
+        assert_eq!(&data[..unique], &expected[..]);
+    }
+
+    #[test]
+    fn test_count_runs_each_backend() {
+        fn expected_counts<T: SimdSortable>(data: &[T]) -> Vec<(T, usize)> {
+            let mut counts = Vec::new();
+            count_runs_std(data, &mut counts);
+            counts
+        }
+
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let mut data: Vec<i64> = random_i64(len).into_iter().map(|x| x % 64).collect();
+                data.sort_unstable();
+                let mut counts = Vec::new();
+                i64::count_runs_with(backend, &data, &mut counts);
+                assert_eq!(
+                    counts,
+                    expected_counts(&data),
+                    "{:?} failed for len {}",
+                    backend,
+                    len
+                );
+
+                let mut data: Vec<f64> = random_i64(len)
+                    .into_iter()
+                    .map(|x| (x % 64) as f64)
+                    .collect();
+                data.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                let mut counts = Vec::new();
+                f64::count_runs_with(backend, &data, &mut counts);
+                assert_eq!(
+                    counts,
+                    expected_counts(&data),
+                    "{:?} failed for len {}",
+                    backend,
+                    len
+                );
+
+                if u64::BACKENDS.contains(&backend) {
+                    let mut data: Vec<u64> = random_u64(len).into_iter().map(|x| x % 64).collect();
+                    data.sort_unstable();
+                    let mut counts = Vec::new();
+                    u64::count_runs_with(backend, &data, &mut counts);
+                    assert_eq!(
+                        counts,
+                        expected_counts(&data),
+                        "{:?} failed for len {}",
+                        backend,
+                        len
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_and_count() {
+        let mut data = vec![
+            3u64, 1, 3, 2, 3, 1, 7, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 2,
+        ];
+        assert_eq!(sort_and_count(&mut data), [(1, 2), (2, 2), (3, 15), (7, 1)]);
+        assert!(sort_and_count::<f64>(&mut []).is_empty());
+
+        let mut counts = vec![(0, 0)];
+        sort_and_count_into(&mut [5i64, -5, 5], &mut counts);
+        assert_eq!(counts, [(-5, 1), (5, 2)]);
+    }
+
+    #[test]
+    fn test_set_operation_each_backend() {
+        fn unique_u64(len: usize) -> Vec<u64> {
+            let mut data: Vec<u64> = random_u64(len).into_iter().map(|x| x % 4096).collect();
+            let unique = sort_unique(&mut data);
+            data.truncate(unique);
+            data
+        }
+
+        let a = unique_u64(2000);
+        let b = unique_u64(500);
+        let intersection: Vec<u64> = a.iter().copied().filter(|x| b.contains(x)).collect();
+        let difference: Vec<u64> = a.iter().copied().filter(|x| !b.contains(x)).collect();
+        let mut union = [a.clone(), b.clone()].concat();
+        union.sort_unstable();
+        union.dedup();
+
+        for backend in available_backends() {
+            if !u64::BACKENDS.contains(&backend) {
+                continue;
+            }
+            let op = |op| u64::set_operation_with(backend, op, &a, &b);
+            assert_eq!(
+                op(SetOperation::Intersection),
+                intersection,
+                "{:?}",
+                backend
+            );
+            assert_eq!(op(SetOperation::Difference), difference, "{:?}", backend);
+            assert_eq!(op(SetOperation::Union), union, "{:?}", backend);
+
+            let i64s: Vec<i64> = a.iter().map(|&x| x as i64 - 2048).collect();
+            let f64s: Vec<f64> = b.iter().map(|&x| x as f64 - 2048.0).collect();
+            assert_eq!(
+                i64::set_operation_with(backend, SetOperation::Union, &i64s, &[]),
+                i64s
+            );
+            assert_eq!(
+                f64::set_operation_with(backend, SetOperation::Intersection, &f64s, &f64s),
+                f64s
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = [-3i64, 1, 2, 5, 8, 13, 21];
+        let b = [1i64, 3, 5, 7, 9, 11, 13, 15, 17];
+        assert_eq!(intersect_sorted(&a, &b), [1, 5, 13]);
+        assert_eq!(
+            union_sorted(&a, &b),
+            [-3, 1, 2, 3, 5, 7, 8, 9, 11, 13, 15, 17, 21]
+        );
+        assert_eq!(difference_sorted(&a, &b), [-3, 2, 8, 21]);
+        assert_eq!(difference_sorted(&b, &a), [3, 7, 9, 11, 15, 17]);
+        assert!(intersect_sorted::<f64>(&[], &[1.0]).is_empty());
+    }
+
+    #[test]
+    fn test_lower_bound_batch_each_backend() {
+        let mut sorted: Vec<i64> = random_i64(1000).into_iter().map(|x| x % 500).collect();
+        sorted.sort_unstable();
+        let probes: Vec<i64> = random_i64(999).into_iter().map(|x| x % 600).collect();
+        let expected: Vec<usize> = probes
+            .iter()
+            .map(|probe| sorted.partition_point(|x| x < probe))
+            .collect();
+        let unsigned: Vec<u64> = sorted.iter().map(|&x| (x + 500) as u64).collect();
+        let unsigned_probes: Vec<u64> = probes.iter().map(|&x| (x + 600) as u64).collect();
+        let unsigned_expected: Vec<usize> = unsigned_probes
+            .iter()
+            .map(|probe| unsigned.partition_point(|x| x < probe))
+            .collect();
+        let floats: Vec<f64> = sorted.iter().map(|&x| x as f64 / 2.0).collect();
+        let float_probes: Vec<f64> = probes.iter().map(|&x| x as f64 / 2.0).collect();
+
+        for backend in available_backends() {
+            let mut out = vec![0; probes.len()];
+            i64::lower_bound_batch_with(backend, &sorted, &probes, &mut out);
+            assert_eq!(out, expected, "{:?}", backend);
+
+            f64::lower_bound_batch_with(backend, &floats, &float_probes, &mut out);
+            assert_eq!(out, expected, "{:?}", backend);
+
+            if u64::BACKENDS.contains(&backend) {
+                u64::lower_bound_batch_with(backend, &unsigned, &unsigned_probes, &mut out);
+                assert_eq!(out, unsigned_expected, "{:?}", backend);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lower_bound_batch() {
+        let sorted = [1u64, 3, 3, 3, 7, 10];
+        let probes = [0, 1, 2, 3, 4, 7, 8, 10, 11, u64::MAX, 3];
+        let mut out = [0; 11];
+        lower_bound_batch(&sorted, &probes, &mut out);
+        assert_eq!(out, [0, 0, 1, 1, 4, 4, 5, 5, 6, 6, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_lower_bound_batch_short_output() {
+        lower_bound_batch(&[1.0, 2.0], &[1.5, 0.5], &mut [0]);
+    }
+
+    #[test]
+    fn test_minmax_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let data = random_i64(len);
+                let expected = data.iter().min().zip(data.iter().max());
+                let result = i64::minmax_with(backend, &data, false);
+                assert_eq!(
+                    result.as_ref().map(|(a, b)| (a, b)),
+                    expected,
+                    "{:?}",
+                    backend
+                );
+
+                let mut data = random_f64(len);
+                let expected = data
+                    .iter()
+                    .copied()
+                    .reduce(f64::min)
+                    .zip(data.iter().copied().reduce(f64::max));
+                assert_eq!(
+                    f64::minmax_with(backend, &data, false),
+                    expected,
+                    "{:?}",
+                    backend
+                );
+                assert_eq!(
+                    f64::minmax_with(backend, &data, true),
+                    expected,
+                    "{:?}",
+                    backend
+                );
+                if len > 0 {
+                    data[len / 2] = f64::NAN;
+                    let expected = data
+                        .iter()
+                        .copied()
+                        .reduce(f64::min)
+                        .zip(data.iter().copied().reduce(f64::max));
+                    let expected = if len == 1 { None } else { expected };
+                    assert_eq!(
+                        f64::minmax_with(backend, &data, false),
+                        expected,
+                        "{:?}",
+                        backend
+                    );
+                    let (min, max) = f64::minmax_with(backend, &data, true).unwrap();
+                    assert!(min.is_nan() && max.is_nan(), "{:?}", backend);
+                }
+
+                if u64::BACKENDS.contains(&backend) {
+                    let data = random_u64(len);
+                    let expected = data.iter().copied().min().zip(data.iter().copied().max());
+                    assert_eq!(
+                        u64::minmax_with(backend, &data, false),
+                        expected,
+                        "{:?}",
+                        backend
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_minmax() {
+        assert_eq!(minmax_i64(&[]), None);
+        assert_eq!(minmax_i64(&[4, -2, 9, 0]), Some((-2, 9)));
+        assert_eq!(minmax_u64(&[7; 33]), Some((7, 7)));
+        let data = [
+            f64::NAN,
+            1.5,
+            f64::INFINITY,
+            -0.5,
+            f64::NAN,
+            3.0,
+            2.0,
+            1.0,
+            0.0,
+            8.0,
+        ];
+        assert_eq!(minmax_f64(&data), Some((-0.5, f64::INFINITY)));
+        assert!(minmax_f64_propagate_nan(&data).unwrap().0.is_nan());
+        assert_eq!(minmax_f64(&[f64::NAN; 12]), None);
+        assert_eq!(minmax_f64_propagate_nan(&[1.0, 2.0]), Some((1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_select_ranks_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let ranks: Vec<usize> =
+                    [0, 1, len / 10, len / 2, len / 2 + 1, len.saturating_sub(1)]
+                        .into_iter()
+                        .filter(|&rank| rank < len)
+                        .collect::<std::collections::BTreeSet<_>>()
+                        .into_iter()
+                        .collect();
+
+                let mut data = random_f64(len);
+                let mut expected = data.clone();
+                expected.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+                f64::select_ranks_with(backend, &mut data, &ranks);
+                for &rank in &ranks {
+                    assert_eq!(
+                        data[rank], expected[rank],
+                        "{:?} failed for len {}",
+                        backend, len
+                    );
+                }
+
+                let mut data = random_i64(len);
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                i64::select_ranks_with(backend, &mut data, &ranks);
+                for &rank in &ranks {
+                    assert_eq!(
+                        data[rank], expected[rank],
+                        "{:?} failed for len {}",
+                        backend, len
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut data: Vec<f64> = (1..=10).rev().map(|x| x as f64).collect();
+        assert_eq!(
+            percentiles_f64_with(
+                &mut data,
+                &[0.0, 10.0, 50.0, 90.0, 95.0, 100.0],
+                QuantileMethod::NearestRank
+            ),
+            [1.0, 1.0, 5.0, 9.0, 10.0, 10.0]
+        );
+        assert_eq!(
+            percentiles_f64(&mut data, &[0.0, 50.0, 90.0, 100.0, 25.0]),
+            [1.0, 5.5, 9.1, 10.0, 3.25]
+        );
+        assert_eq!(percentiles_f64(&mut [4.0], &[0.0, 37.5, 100.0]), [4.0; 3]);
+        assert!(percentiles_f64(&mut data, &[]).is_empty());
+
+        let mut data = random_f64(10_000);
+        let mut sorted = data.clone();
+        sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        let result =
+            percentiles_f64_with(&mut data, &[50.0, 99.0, 99.99], QuantileMethod::NearestRank);
+        assert_eq!(result, [sorted[4999], sorted[9899], sorted[9998]]);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median_f64(&mut []), None);
+        assert_eq!(median_f64(&mut [3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median_f64(&mut [4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_percentiles_out_of_range() {
+        percentiles_f64(&mut [1.0, 2.0], &[100.5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN")]
+    fn test_percentiles_nan() {
+        let mut data = random_f64(1000);
+        data[500] = f64::NAN;
+        percentiles_f64(&mut data, &[50.0]);
+    }
+
+    #[test]
+    fn test_partition_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let mut data = random_f64(len);
+                let pivot = data.first().copied().unwrap_or(0.0);
+                let below = f64::partition_with(backend, &mut data, pivot);
+                assert!(data[..below].iter().all(|&x| x < pivot), "{:?}", backend);
+                assert!(data[below..].iter().all(|&x| x >= pivot), "{:?}", backend);
+
+                let mut data = random_i64(len);
+                let below = i64::partition_with(backend, &mut data, 0);
+                assert!(data[..below].iter().all(|&x| x < 0), "{:?}", backend);
+                assert!(data[below..].iter().all(|&x| x >= 0), "{:?}", backend);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_128bit_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                let mut data: Vec<u128> = random_u64(2 * len)
+                    .chunks(2)
+                    .map(|pair| (pair[0] as u128) << 64 | pair[1] as u128)
+                    .collect();
+                // equal high halves make the low halves decide
+                data.iter_mut()
+                    .step_by(3)
+                    .for_each(|x| *x &= u64::MAX as u128);
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                sort_128bit_keys_with(
+                    backend,
+                    &mut data,
+                    |key| {
+                        (
+                            ((key >> 64) as u64 ^ SIGN_BIT) as i64,
+                            (key as u64 ^ SIGN_BIT) as i64,
+                        )
+                    },
+                    |hi, lo| {
+                        ((hi as u64 ^ SIGN_BIT) as u128) << 64 | (lo as u64 ^ SIGN_BIT) as u128
+                    },
+                );
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_128bit_keys() {
+        let mut unsigned: Vec<u128> = random_u64(1000)
+            .into_iter()
+            .map(|x| (x as u128) << 64 | (x % 7) as u128)
+            .chain([0, u128::MAX, 1 << 63, 1 << 64])
+            .collect();
+        let mut expected = unsigned.clone();
+        expected.sort_unstable();
+        sort_u128(&mut unsigned);
+        assert_eq!(unsigned, expected);
+
+        let mut signed: Vec<i128> = random_u64(1000)
+            .into_iter()
+            .map(|x| (x as i64 as i128) << 64 | (x % 7) as i128)
+            .chain([0, -1, i128::MIN, i128::MAX, 1 << 63, -(1 << 63)])
+            .collect();
+        let mut expected = signed.clone();
+        expected.sort_unstable();
+        sort_i128(&mut signed);
+        assert_eq!(signed, expected);
+
+        let mut pairs: Vec<(u64, u64)> = random_u64(1000)
+            .into_iter()
+            .map(|x| (x % 10, x))
+            .chain([(0, u64::MAX), (u64::MAX, 0), (1 << 63, 1 << 63)])
+            .collect();
+        let mut expected = pairs.clone();
+        expected.sort_unstable();
+        sort_pairs_u64(&mut pairs);
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn test_sort_usize_isize_each_backend() {
+        for backend in available_backends() {
+            for len in LENGTHS {
+                if u64::BACKENDS.contains(&backend) {
+                    let mut data: Vec<usize> =
+                        random_u64(len).into_iter().map(|x| x as usize).collect();
+                    let mut expected = data.clone();
+                    expected.sort_unstable();
+                    sort_usize_with(backend, &mut data);
+                    assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+                }
+
+                let mut data: Vec<isize> =
+                    random_i64(len).into_iter().map(|x| x as isize).collect();
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                sort_isize_with(backend, &mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_u8_i8_each_backend() {
+        for backend in available_backends() {
+            if !BACKENDS_8BIT.contains(&backend) && backend != Backend::Std {
+                continue;
+            }
+            for len in LENGTHS {
+                let mut data: Vec<u8> = random_u64(len).into_iter().map(|x| x as u8).collect();
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                sort_u8_with(backend, &mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+
+                let mut data: Vec<i8> = random_u64(len).into_iter().map(|x| x as i8).collect();
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                sort_i8_with(backend, &mut data);
+                assert_eq!(data, expected, "{:?} failed for len {}", backend, len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_u8_runs() {
+        for backend in available_backends() {
+            if !BACKENDS_8BIT.contains(&backend) {
+                continue;
+            }
+            for len in [63, 64, 65, 127, 128, 129, 1000] {
+                for modulus in [1, 2, 3, 200] {
+                    let mut data: Vec<u8> = random_u64(len)
+                        .into_iter()
+                        .map(|x| ((x % modulus) as u8).wrapping_mul(3))
+                        .collect();
+                    let mut expected = data.clone();
+                    expected.sort_unstable();
+                    sort_u8_with(backend, &mut data);
+                    assert_eq!(
+                        data, expected,
+                        "{:?} failed for len {} modulus {}",
+                        backend, len, modulus
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+    #[should_panic(expected = "backend does not support u8")]
+    fn test_sort_u8_unsupported_backend() {
+        sort_u8_with(Backend::Avx2, &mut [1, 0]);
+    }
+
+    #[test]
+    fn test_sort_with_backend() {
+        for len in LENGTHS {
+            let mut expected = random_i64(len);
+            expected.sort_unstable();
+
+            let mut data = random_i64(len);
+            sort_with_backend::<i64, 8, [i64; 8]>(&mut data);
+            assert_eq!(data, expected, "failed for len {}", len);
+
+            let mut data = random_i64(len);
+            sort_with_backend::<i64, 4, [i64; 4]>(&mut data);
+            assert_eq!(data, expected, "failed for len {}", len);
+
+            let mut data = random_i64(len);
+            sort_with_backend::<i64, 16, [i64; 16]>(&mut data);
+            assert_eq!(data, expected, "failed for len {}", len);
+
+            #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+            {
+                let mut data = random_i64(len);
+                sort_with_backend::<i64, 8, x86::avx2::i64_impl::Avx2I64x2>(&mut data);
+                assert_eq!(data, expected, "failed for len {}", len);
+            }
+
+            #[cfg(feature = "nightly")]
+            {
+                let mut data = random_i64(len);
+                sort_with_backend::<i64, 8, std::simd::i64x8>(&mut data);
+                assert_eq!(data, expected, "failed for len {}", len);
+            }
+
+            let mut data = random_f64(len);
+            let mut expected = data.clone();
+            expected.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            sort_with_backend::<f64, 8, [f64; 8]>(&mut data);
+            assert_eq!(data, expected, "failed for len {}", len);
+
+            #[cfg(target_pointer_width = "64")]
+            {
+                let mut data: Vec<usize> =
+                    random_u64(len).into_iter().map(|x| x as usize).collect();
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                sort_with_backend::<usize, 8, [usize; 8]>(&mut data);
+                assert_eq!(data, expected, "failed for len {}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_backend_names() {
+        for (i, a) in Backend::ALL.iter().enumerate() {
+            for b in &Backend::ALL[i + 1..] {
+                assert_ne!(a.name(), b.name());
+            }
+        }
+        assert_eq!(Backend::Avx512.to_string(), "avx512");
+        assert_eq!(Backend::Avx512.required_features(), ["avx512f"]);
+    }
 }
@@ -1,20 +1,21 @@
 use core::slice;
 use std::simd::{
-    i64x8, u64x8, usizex8, Simd, SimdInt, SimdOrd, SimdPartialEq, SimdPartialOrd, SimdUint,
-    Swizzle, Swizzle2, ToBitMask,
-    Which::{self, *},
+    cmp::{SimdOrd, SimdPartialEq, SimdPartialOrd},
+    f64x8, i64x8,
+    num::{SimdFloat, SimdInt, SimdUint},
+    u64x8, usizex8, Mask, Select, Simd, Swizzle,
 };
 
 use crate::{
-    bit_64::{Bit64Element, Bit64Simd},
-    SimdCompare, SimdSortable,
+    bit_64::{Bit64Element, Bit64Simd, BitonicNetwork},
+    SimdCompare, SimdSortable, SortBackend,
 };
 
 //   ZMM                  7, 6, 5, 4, 3, 2, 1, 0
 //#define NETWORK_64BIT_1 4, 5, 6, 7, 0, 1, 2, 3
 struct Network64bit1;
 
-impl Swizzle<8, 8> for Network64bit1 {
+impl Swizzle<8> for Network64bit1 {
     const INDEX: [usize; 8] = [3, 2, 1, 0, 7, 6, 5, 4];
 }
 
@@ -22,7 +23,7 @@ impl Swizzle<8, 8> for Network64bit1 {
 //#define NETWORK_64BIT_2 0, 1, 2, 3, 4, 5, 6, 7
 struct Network64bit2;
 
-impl Swizzle<8, 8> for Network64bit2 {
+impl Swizzle<8> for Network64bit2 {
     const INDEX: [usize; 8] = [7, 6, 5, 4, 3, 2, 1, 0];
 }
 
@@ -30,7 +31,7 @@ impl Swizzle<8, 8> for Network64bit2 {
 //#define NETWORK_64BIT_3 5, 4, 7, 6, 1, 0, 3, 2
 struct Network64bit3;
 
-impl Swizzle<8, 8> for Network64bit3 {
+impl Swizzle<8> for Network64bit3 {
     const INDEX: [usize; 8] = [2, 3, 0, 1, 6, 7, 4, 5];
 }
 
@@ -38,68 +39,17 @@ impl Swizzle<8, 8> for Network64bit3 {
 //#define NETWORK_64BIT_4 3, 2, 1, 0, 7, 6, 5, 4
 struct Network64bit4;
 
-impl Swizzle<8, 8> for Network64bit4 {
+impl Swizzle<8> for Network64bit4 {
     const INDEX: [usize; 8] = [4, 5, 6, 7, 0, 1, 2, 3];
 }
 
 //SHUFFLE_MASK(1, 1, 1, 1)
 struct Shuffle1_1_1_1;
 
-impl Swizzle<8, 8> for Shuffle1_1_1_1 {
+impl Swizzle<8> for Shuffle1_1_1_1 {
     const INDEX: [usize; 8] = [1, 0, 3, 2, 5, 4, 7, 6];
 }
 
-//ZMM 76543210
-//    10101010 , 0 -> first, 1 -> second
-struct Swizzle2_0xAA;
-
-impl Swizzle2<8, 8> for Swizzle2_0xAA {
-    const INDEX: [Which; 8] = [
-        First(0),
-        Second(1),
-        First(2),
-        Second(3),
-        First(4),
-        Second(5),
-        First(6),
-        Second(7),
-    ];
-}
-
-//ZMM 76543210
-//    11001100, 0 -> first, 1 -> second
-struct Swizzle2_0xCC;
-
-impl Swizzle2<8, 8> for Swizzle2_0xCC {
-    const INDEX: [Which; 8] = [
-        First(0),
-        First(1),
-        Second(2),
-        Second(3),
-        First(4),
-        First(5),
-        Second(6),
-        Second(7),
-    ];
-}
-
-//ZMM 76543210
-//    11110000, 0 -> first, 1 -> second
-struct Swizzle2_0xF0;
-
-impl Swizzle2<8, 8> for Swizzle2_0xF0 {
-    const INDEX: [Which; 8] = [
-        First(0),
-        First(1),
-        First(2),
-        First(3),
-        Second(4),
-        Second(5),
-        Second(6),
-        Second(7),
-    ];
-}
-
 impl SimdCompare<u64, 8> for u64x8 {
     type OPMask = <Simd<u64, 8> as SimdPartialEq>::Mask;
 
@@ -114,13 +64,8 @@ impl SimdCompare<u64, 8> for u64x8 {
     }
 
     #[inline]
-    fn mask_mov(a: Self, b: Self, mask: fn(Self, Self) -> Self) -> Self {
-        mask(a, b)
-    }
-
-    #[inline]
-    fn shuffle(a: Self, mask: fn(Self) -> Self) -> Self {
-        mask(a)
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Mask::<i64, 8>::from_bitmask(MASK).select(b, a)
     }
 
     #[inline]
@@ -173,16 +118,41 @@ impl SimdCompare<u64, 8> for u64x8 {
         a.simd_ge(b)
     }
 
+    #[inline]
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        a.simd_eq(b)
+    }
+
     #[inline]
     fn ones_count(mask: Self::OPMask) -> usize {
         mask.to_bitmask().count_ones() as usize
     }
 
+    #[inline]
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        mask.to_bitmask()
+    }
+
     #[inline]
     fn not_mask(mask: Self::OPMask) -> Self::OPMask {
         !mask
     }
 
+    #[inline]
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a | b
+    }
+
+    #[inline]
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a & b
+    }
+
+    #[inline]
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        mask.select(b, a)
+    }
+
     #[inline]
     fn reducemin(data: Self) -> u64 {
         data.reduce_min()
@@ -222,13 +192,8 @@ impl SimdCompare<i64, 8> for i64x8 {
     }
 
     #[inline]
-    fn mask_mov(a: Self, b: Self, mask: fn(Self, Self) -> Self) -> Self {
-        mask(a, b)
-    }
-
-    #[inline]
-    fn shuffle(a: Self, mask: fn(Self) -> Self) -> Self {
-        mask(a)
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Mask::<i64, 8>::from_bitmask(MASK).select(b, a)
     }
 
     #[inline]
@@ -280,16 +245,41 @@ impl SimdCompare<i64, 8> for i64x8 {
         a.simd_ge(b)
     }
 
+    #[inline]
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        a.simd_eq(b)
+    }
+
     #[inline]
     fn ones_count(mask: Self::OPMask) -> usize {
         mask.to_bitmask().count_ones() as usize
     }
 
+    #[inline]
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        mask.to_bitmask()
+    }
+
     #[inline]
     fn not_mask(mask: Self::OPMask) -> Self::OPMask {
         !mask
     }
 
+    #[inline]
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a | b
+    }
+
+    #[inline]
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a & b
+    }
+
+    #[inline]
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        mask.select(b, a)
+    }
+
     #[inline]
     fn reducemin(data: Self) -> i64 {
         data.reduce_min()
@@ -315,19 +305,137 @@ impl SimdCompare<i64, 8> for i64x8 {
     }
 }
 
-impl<T: Bit64Element> Bit64Simd<T> for Simd<T, 8> {
-    fn swizzle2_0xaa(a: Self, b: Self) -> Self {
-        Swizzle2_0xAA::swizzle2(a, b)
+impl SimdCompare<f64, 8> for f64x8 {
+    type OPMask = <Simd<f64, 8> as SimdPartialEq>::Mask;
+
+    #[inline]
+    fn min(a: Self, b: Self) -> Self {
+        a.simd_min(b)
+    }
+
+    #[inline]
+    fn max(a: Self, b: Self) -> Self {
+        a.simd_max(b)
+    }
+
+    #[inline]
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Mask::<i64, 8>::from_bitmask(MASK).select(b, a)
+    }
+
+    #[inline]
+    fn loadu(data: &[f64]) -> Self {
+        let mut values = [0.0; 8];
+        values.copy_from_slice(unsafe { slice::from_raw_parts(data.as_ptr(), 8) });
+        Self::from_array(values)
+    }
+
+    #[inline]
+    fn storeu(input: Self, output: &mut [f64]) {
+        unsafe {
+            slice::from_raw_parts_mut(output.as_mut_ptr(), 8).copy_from_slice(input.as_array())
+        }
+    }
+
+    #[inline]
+    fn mask_loadu(data: &[f64]) -> Self {
+        let idxs = usizex8::from_array([0, 1, 2, 3, 4, 5, 6, 7]);
+        let max_values = f64x8::splat(f64::MAX_VALUE);
+        f64x8::gather_or(data, idxs, max_values)
+    }
+
+    #[inline]
+    fn mask_storeu(input: Self, output: &mut [f64]) {
+        let idxs = usizex8::from_array([0, 1, 2, 3, 4, 5, 6, 7]);
+        f64x8::scatter(input, output, idxs);
+    }
+
+    #[inline]
+    fn gather_from_idx(idx: [usize; 8], data: &[f64]) -> Self {
+        let idxs = usizex8::from_array(idx);
+        let max_values = f64x8::splat(f64::MAX_VALUE);
+        f64x8::gather_or(data, idxs, max_values)
     }
 
-    fn swizzle2_0xcc(a: Self, b: Self) -> Self {
-        Swizzle2_0xCC::swizzle2(a, b)
+    #[inline]
+    fn get_value_at_idx(input: Self, idx: usize) -> f64 {
+        input[idx]
     }
 
-    fn swizzle2_0xf0(a: Self, b: Self) -> Self {
-        Swizzle2_0xF0::swizzle2(a, b)
+    #[inline]
+    fn set(value: f64) -> Self {
+        f64x8::splat(value)
     }
 
+    #[inline]
+    fn ge(a: Self, b: Self) -> Self::OPMask {
+        a.simd_ge(b)
+    }
+
+    #[inline]
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        a.simd_eq(b)
+    }
+
+    #[inline]
+    fn ones_count(mask: Self::OPMask) -> usize {
+        mask.to_bitmask().count_ones() as usize
+    }
+
+    #[inline]
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        mask.to_bitmask()
+    }
+
+    #[inline]
+    fn not_mask(mask: Self::OPMask) -> Self::OPMask {
+        !mask
+    }
+
+    #[inline]
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a | b
+    }
+
+    #[inline]
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a & b
+    }
+
+    #[inline]
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        mask.select(b, a)
+    }
+
+    #[inline]
+    fn reducemin(data: Self) -> f64 {
+        data.reduce_min()
+    }
+
+    #[inline]
+    fn reducemax(data: Self) -> f64 {
+        data.reduce_max()
+    }
+
+    #[inline]
+    fn mask_compressstoreu(array: &mut [f64], mask: Self::OPMask, vals: Self) {
+        let mut ptr = 0;
+
+        let count = mask.to_array();
+
+        for (idx, i) in count.iter().enumerate() {
+            if *i {
+                array[ptr] = vals[idx];
+                ptr += 1;
+            }
+        }
+    }
+}
+
+// This is safe since the portable simd types work on every target
+unsafe impl<T: Bit64Element> SortBackend<T, 8> for Simd<T, 8> where Simd<T, 8>: BitonicNetwork<T, 8> {}
+
+impl<T: Bit64Element> Bit64Simd<T> for Simd<T, 8> {
     fn shuffle1_1_1_1(a: Self) -> Self {
         Shuffle1_1_1_1::swizzle(a)
     }
@@ -364,24 +472,28 @@ mod tests {
         x.into()
     }
 
+    fn into_array_f64(x: f64x8) -> [f64; 8] {
+        x.into()
+    }
+
     fn mask_fn<T: MaskElement>(x: u8) -> Mask<T, 8> {
-        Mask::<T, 8>::from_bitmask(x)
+        Mask::<T, 8>::from_bitmask(x as u64)
     }
 
     fn generate_mask_answer<T, M>(bitmask: usize, values: &[T]) -> (M, [T; 8])
     where
-        T: TryFrom<usize> + Default + Copy,
-        M: ToBitMask<BitMask = u8>,
+        T: Default + Copy,
+        M: From<Mask<i64, 8>>,
     {
         let mut new_values = [<T as Default>::default(); 8];
         let mut count = 0;
-        for i in 0..8 {
+        for (i, &value) in values.iter().take(8).enumerate() {
             if bitmask & (1 << i) != 0 {
-                new_values[count] = values[i];
+                new_values[count] = value;
                 count += 1;
             }
         }
-        (M::from_bitmask(bitmask as u8), new_values)
+        (M::from(Mask::from_bitmask(bitmask as u64)), new_values)
     }
 
     type I64Mask = Mask<i64, 8>;
@@ -391,6 +503,9 @@ mod tests {
     test_loadu_storeu!(i64, i64x8, into_array_i64);
     test_mask_loadu_mask_storeu!(i64, i64x8, into_array_i64);
     test_get_at_index!(i64, i64x8);
+    test_eq_or_mask!(i64, i64x8);
+    test_to_bitmask!(i64, i64x8);
+    test_and_mask_blend!(i64, i64x8);
     test_ge!(
         i64,
         i64x8,
@@ -407,9 +522,7 @@ mod tests {
     test_reduce_min_max!(i64, i64x8);
     test_compress_store_u!(i64, i64x8, I64Mask, generate_mask_answer);
     test_shuffle1_1_1_1!(i64, i64x8, into_array_i64);
-    test_swizzle2_0xaa!(i64, i64x8, into_array_i64);
-    test_swizzle2_0xcc!(i64, i64x8, into_array_i64);
-    test_swizzle2_0xf0!(i64, i64x8, into_array_i64);
+    test_mask_mov!(i64, i64x8, into_array_i64);
     network64bit1!(i64, i64x8, into_array_i64);
     network64bit2!(i64, i64x8, into_array_i64);
     network64bit3!(i64, i64x8, into_array_i64);
@@ -420,6 +533,9 @@ mod tests {
     test_loadu_storeu!(u64, u64x8, into_array_u64);
     test_mask_loadu_mask_storeu!(u64, u64x8, into_array_u64);
     test_get_at_index!(u64, u64x8);
+    test_eq_or_mask!(u64, u64x8);
+    test_to_bitmask!(u64, u64x8);
+    test_and_mask_blend!(u64, u64x8);
     test_ge!(
         u64,
         u64x8,
@@ -436,11 +552,39 @@ mod tests {
     test_reduce_min_max!(u64, u64x8);
     test_compress_store_u!(u64, u64x8, I64Mask, generate_mask_answer);
     test_shuffle1_1_1_1!(u64, u64x8, into_array_u64);
-    test_swizzle2_0xaa!(u64, u64x8, into_array_u64);
-    test_swizzle2_0xcc!(u64, u64x8, into_array_u64);
-    test_swizzle2_0xf0!(u64, u64x8, into_array_u64);
+    test_mask_mov!(u64, u64x8, into_array_u64);
     network64bit1!(u64, u64x8, into_array_u64);
     network64bit2!(u64, u64x8, into_array_u64);
     network64bit3!(u64, u64x8, into_array_u64);
     network64bit4!(u64, u64x8, into_array_u64);
+
+    // test f64x8
+    test_min_max!(f64, f64x8, into_array_f64);
+    test_loadu_storeu!(f64, f64x8, into_array_f64);
+    test_mask_loadu_mask_storeu!(f64, f64x8, into_array_f64);
+    test_get_at_index!(f64, f64x8);
+    test_eq_or_mask!(f64, f64x8);
+    test_to_bitmask!(f64, f64x8);
+    test_and_mask_blend!(f64, f64x8);
+    test_ge!(
+        f64,
+        f64x8,
+        Mask::from([false, true, false, true, false, true, false, true])
+    );
+    test_gather!(f64, f64x8, into_array_f64);
+    test_not!(
+        f64,
+        f64x8,
+        Mask::from([false, true, false, true, false, true, false, false]),
+        Mask::from([true, false, true, false, true, false, true, true])
+    );
+    test_count_ones!(f64, f64x8, mask_fn);
+    test_reduce_min_max!(f64, f64x8);
+    test_compress_store_u!(f64, f64x8, I64Mask, generate_mask_answer);
+    test_shuffle1_1_1_1!(f64, f64x8, into_array_f64);
+    test_mask_mov!(f64, f64x8, into_array_f64);
+    network64bit1!(f64, f64x8, into_array_f64);
+    network64bit2!(f64, f64x8, into_array_f64);
+    network64bit3!(f64, f64x8, into_array_f64);
+    network64bit4!(f64, f64x8, into_array_f64);
 }
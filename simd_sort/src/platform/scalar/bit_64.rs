@@ -0,0 +1,350 @@
+use std::array;
+
+use crate::{
+    bit_64::{Bit64Element, Bit64Simd, BitonicNetwork},
+    SimdCompare, SortBackend,
+};
+
+#[inline]
+fn permute<T: Copy>(a: [T; 8], idx: [usize; 8]) -> [T; 8] {
+    array::from_fn(|i| a[idx[i]])
+}
+
+macro_rules! impl_simd_compare {
+    ($lanes: literal, $mask: ty) => {
+        impl<T: Bit64Element> SimdCompare<T, $lanes> for [T; $lanes] {
+            type OPMask = $mask;
+
+            #[inline]
+            fn min(a: Self, b: Self) -> Self {
+                array::from_fn(|i| if b[i] < a[i] { b[i] } else { a[i] })
+            }
+
+            #[inline]
+            fn max(a: Self, b: Self) -> Self {
+                array::from_fn(|i| if b[i] > a[i] { b[i] } else { a[i] })
+            }
+
+            #[inline]
+            fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+                array::from_fn(|i| if MASK >> i & 1 == 1 { b[i] } else { a[i] })
+            }
+
+            #[inline]
+            fn loadu(data: &[T]) -> Self {
+                array::from_fn(|i| data[i])
+            }
+
+            #[inline]
+            fn storeu(input: Self, data: &mut [T]) {
+                data[..$lanes].copy_from_slice(&input);
+            }
+
+            #[inline]
+            fn mask_loadu(data: &[T]) -> Self {
+                array::from_fn(|i| {
+                    if i < data.len() {
+                        data[i]
+                    } else {
+                        T::MAX_VALUE
+                    }
+                })
+            }
+
+            #[inline]
+            fn mask_storeu(input: Self, data: &mut [T]) {
+                let len = data.len();
+                data.copy_from_slice(&input[..len]);
+            }
+
+            #[inline]
+            fn gather_from_idx(idx: [usize; $lanes], data: &[T]) -> Self {
+                array::from_fn(|i| data[idx[i]])
+            }
+
+            #[inline]
+            fn get_value_at_idx(input: Self, idx: usize) -> T {
+                input[idx]
+            }
+
+            #[inline]
+            fn set(value: T) -> Self {
+                [value; $lanes]
+            }
+
+            #[inline]
+            fn ge(a: Self, b: Self) -> Self::OPMask {
+                let mut mask = 0;
+                for i in 0..$lanes {
+                    if a[i] >= b[i] {
+                        mask |= 1 << i;
+                    }
+                }
+                mask
+            }
+
+            #[inline]
+            fn eq(a: Self, b: Self) -> Self::OPMask {
+                let mut mask = 0;
+                for i in 0..$lanes {
+                    if a[i] == b[i] {
+                        mask |= 1 << i;
+                    }
+                }
+                mask
+            }
+
+            #[inline]
+            fn ones_count(mask: Self::OPMask) -> usize {
+                mask.count_ones() as usize
+            }
+
+            #[inline]
+            fn to_bitmask(mask: Self::OPMask) -> u64 {
+                mask as u64
+            }
+
+            #[inline]
+            fn not_mask(mask: Self::OPMask) -> Self::OPMask {
+                // clear the bits above the last lane
+                !mask & (Self::OPMask::MAX >> (Self::OPMask::BITS - $lanes))
+            }
+
+            #[inline]
+            fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+                a | b
+            }
+
+            #[inline]
+            fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+                a & b
+            }
+
+            #[inline]
+            fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+                array::from_fn(|i| if mask >> i & 1 == 1 { b[i] } else { a[i] })
+            }
+
+            #[inline]
+            fn reducemin(x: Self) -> T {
+                x.into_iter()
+                    .reduce(|a, b| if b < a { b } else { a })
+                    .unwrap()
+            }
+
+            #[inline]
+            fn reducemax(x: Self) -> T {
+                x.into_iter()
+                    .reduce(|a, b| if b > a { b } else { a })
+                    .unwrap()
+            }
+
+            #[inline]
+            fn mask_compressstoreu(array: &mut [T], mask: Self::OPMask, data: Self) {
+                let mut ptr = 0;
+                for i in 0..$lanes {
+                    if mask & (1 << i) != 0 {
+                        array[ptr] = data[i];
+                        ptr += 1;
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_simd_compare!(4, u8);
+impl_simd_compare!(8, u8);
+impl_simd_compare!(16, u16);
+
+// This is safe since the methods only use bounds checked indexing
+unsafe impl<T: Bit64Element, const N: usize> SortBackend<T, N> for [T; N] where
+    [T; N]: BitonicNetwork<T, N>
+{
+}
+
+impl<T: Bit64Element> Bit64Simd<T> for [T; 8] {
+    fn shuffle1_1_1_1(a: Self) -> Self {
+        permute(a, [1, 0, 3, 2, 5, 4, 7, 6])
+    }
+
+    fn network64bit1(a: Self) -> Self {
+        permute(a, [3, 2, 1, 0, 7, 6, 5, 4])
+    }
+
+    fn network64bit2(a: Self) -> Self {
+        permute(a, [7, 6, 5, 4, 3, 2, 1, 0])
+    }
+
+    fn network64bit3(a: Self) -> Self {
+        permute(a, [2, 3, 0, 1, 6, 7, 4, 5])
+    }
+
+    fn network64bit4(a: Self) -> Self {
+        permute(a, [4, 5, 6, 7, 0, 1, 2, 3])
+    }
+}
+
+/// Compares every lane with lane `i ^ distance`, lanes with the `upper` bit
+/// set keep the larger value
+#[inline]
+fn cmp_lanes<T: Bit64Element, const N: usize>(a: [T; N], distance: usize, upper: usize) -> [T; N] {
+    array::from_fn(|i| {
+        let (x, y) = (a[i], a[i ^ distance]);
+        if i & upper != 0 {
+            if y > x {
+                y
+            } else {
+                x
+            }
+        } else if y < x {
+            y
+        } else {
+            x
+        }
+    })
+}
+
+#[inline]
+fn merge_lanes<T: Bit64Element, const N: usize>(mut a: [T; N], mut distance: usize) -> [T; N] {
+    while distance > 0 {
+        a = cmp_lanes(a, distance, distance);
+        distance /= 2;
+    }
+    a
+}
+
+#[inline]
+fn sort_lanes<T: Bit64Element, const N: usize>(mut a: [T; N]) -> [T; N] {
+    let mut size = 2;
+    while size <= N {
+        a = cmp_lanes(a, size - 1, size / 2);
+        a = merge_lanes(a, size / 4);
+        size *= 2;
+    }
+    a
+}
+
+// 8 lanes get their network through Bit64Simd
+macro_rules! impl_bitonic_network {
+    ($lanes: literal) => {
+        impl<T: Bit64Element> BitonicNetwork<T, $lanes> for [T; $lanes] {
+            #[inline]
+            fn reverse(mut a: Self) -> Self {
+                a.reverse();
+                a
+            }
+
+            #[inline]
+            fn sort_vec(a: Self) -> Self {
+                sort_lanes(a)
+            }
+
+            #[inline]
+            fn merge_vec(a: Self) -> Self {
+                merge_lanes(a, $lanes / 2)
+            }
+        }
+    };
+}
+
+impl_bitonic_network!(4);
+impl_bitonic_network!(16);
+
+#[cfg(test)]
+mod test {
+    use crate::bit_64::test::*;
+
+    use super::*;
+
+    type I64x8 = [i64; 8];
+    type U64x8 = [u64; 8];
+    type F64x8 = [f64; 8];
+
+    fn into_array<T>(x: [T; 8]) -> [T; 8] {
+        x
+    }
+
+    fn generate_mask_answer<T, M>(bitmask: usize, values: &[T]) -> (M, [T; 8])
+    where
+        T: Default + Copy,
+        M: From<u8>,
+    {
+        let mut new_values = [<T as Default>::default(); 8];
+        let mut count = 0;
+        for (i, &value) in values.iter().take(8).enumerate() {
+            if bitmask & (1 << i) != 0 {
+                new_values[count] = value;
+                count += 1;
+            }
+        }
+        (M::from(bitmask as u8), new_values)
+    }
+
+    fn mask_fn(x: u8) -> u8 {
+        x
+    }
+
+    // test [i64; 8]
+    test_min_max!(i64, I64x8, into_array);
+    test_loadu_storeu!(i64, I64x8, into_array);
+    test_mask_loadu_mask_storeu!(i64, I64x8, into_array);
+    test_get_at_index!(i64, I64x8);
+    test_eq_or_mask!(i64, I64x8);
+    test_to_bitmask!(i64, I64x8);
+    test_and_mask_blend!(i64, I64x8);
+    test_ge!(i64, I64x8, 0b10101010);
+    test_gather!(i64, I64x8, into_array);
+    test_not!(i64, I64x8, 0b10101010, !0b10101010);
+    test_count_ones!(i64, I64x8, mask_fn);
+    test_reduce_min_max!(i64, I64x8);
+    test_compress_store_u!(i64, I64x8, u8, generate_mask_answer);
+    test_shuffle1_1_1_1!(i64, I64x8, into_array);
+    test_mask_mov!(i64, I64x8, into_array);
+    network64bit1!(i64, I64x8, into_array);
+    network64bit2!(i64, I64x8, into_array);
+    network64bit3!(i64, I64x8, into_array);
+    network64bit4!(i64, I64x8, into_array);
+
+    // test [u64; 8]
+    test_min_max!(u64, U64x8, into_array);
+    test_loadu_storeu!(u64, U64x8, into_array);
+    test_mask_loadu_mask_storeu!(u64, U64x8, into_array);
+    test_get_at_index!(u64, U64x8);
+    test_eq_or_mask!(u64, U64x8);
+    test_to_bitmask!(u64, U64x8);
+    test_and_mask_blend!(u64, U64x8);
+    test_ge!(u64, U64x8, 0b10101010);
+    test_gather!(u64, U64x8, into_array);
+    test_not!(u64, U64x8, 0b10101010, !0b10101010);
+    test_count_ones!(u64, U64x8, mask_fn);
+    test_reduce_min_max!(u64, U64x8);
+    test_compress_store_u!(u64, U64x8, u8, generate_mask_answer);
+    test_shuffle1_1_1_1!(u64, U64x8, into_array);
+    test_mask_mov!(u64, U64x8, into_array);
+    network64bit1!(u64, U64x8, into_array);
+    network64bit2!(u64, U64x8, into_array);
+    network64bit3!(u64, U64x8, into_array);
+    network64bit4!(u64, U64x8, into_array);
+
+    // test [f64; 8]
+    test_min_max!(f64, F64x8, into_array);
+    test_loadu_storeu!(f64, F64x8, into_array);
+    test_mask_loadu_mask_storeu!(f64, F64x8, into_array);
+    test_get_at_index!(f64, F64x8);
+    test_eq_or_mask!(f64, F64x8);
+    test_to_bitmask!(f64, F64x8);
+    test_and_mask_blend!(f64, F64x8);
+    test_ge!(f64, F64x8, 0b10101010);
+    test_gather!(f64, F64x8, into_array);
+    test_not!(f64, F64x8, 0b10101010, !0b10101010);
+    test_count_ones!(f64, F64x8, mask_fn);
+    test_reduce_min_max!(f64, F64x8);
+    test_compress_store_u!(f64, F64x8, u8, generate_mask_answer);
+    test_shuffle1_1_1_1!(f64, F64x8, into_array);
+    test_mask_mov!(f64, F64x8, into_array);
+    network64bit1!(f64, F64x8, into_array);
+    network64bit2!(f64, F64x8, into_array);
+    network64bit3!(f64, F64x8, into_array);
+    network64bit4!(f64, F64x8, into_array);
+}
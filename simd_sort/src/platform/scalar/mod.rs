@@ -0,0 +1,138 @@
+pub(crate) mod bit_64;
+
+use crate::bit_64::qsort_64bit_;
+
+pub fn scalar_sort_i64(data: &mut [i64]) {
+    qsort_64bit_::<i64, 8, [i64; 8]>(data, f64::log2(data.len() as f64) as i64)
+}
+
+pub fn scalar_sort_u64(data: &mut [u64]) {
+    qsort_64bit_::<u64, 8, [u64; 8]>(data, f64::log2(data.len() as f64) as i64)
+}
+
+pub fn scalar_sort_f64(data: &mut [f64]) {
+    qsort_64bit_::<f64, 8, [f64; 8]>(data, f64::log2(data.len() as f64) as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bit_64::{test::*, *};
+
+    use super::*;
+
+    type I64x8 = [i64; 8];
+    type U64x8 = [u64; 8];
+    type F64x8 = [f64; 8];
+
+    test_sort_n!(i64, I64x8, 8, 8);
+    test_sort_n!(i64, I64x8, 8, 16);
+    test_sort_n!(i64, I64x8, 8, 32);
+    test_sort_n!(i64, I64x8, 8, 64);
+    test_sort_n!(i64, I64x8, 8, 128);
+    test_sort_n!(i64, I64x8, 8, 256);
+    test_sort_e2e!(i64, I64x8, scalar_sort_i64);
+    test_dedup_sorted!(i64, I64x8, 8);
+    test_count_runs!(i64, I64x8, 8);
+    test_set_operations!(i64, I64x8, 8);
+    test_lower_bound_batch!(i64, I64x8, 8);
+    test_minmax_slice!(i64, I64x8, 8);
+    test_select_ranks!(i64, I64x8, 8);
+    test_partition!(i64, I64x8, 8);
+
+    test_sort_n!(u64, U64x8, 8, 8);
+    test_sort_n!(u64, U64x8, 8, 16);
+    test_sort_n!(u64, U64x8, 8, 32);
+    test_sort_n!(u64, U64x8, 8, 64);
+    test_sort_n!(u64, U64x8, 8, 128);
+    test_sort_n!(u64, U64x8, 8, 256);
+    test_sort_e2e!(u64, U64x8, scalar_sort_u64);
+    test_dedup_sorted!(u64, U64x8, 8);
+    test_count_runs!(u64, U64x8, 8);
+    test_set_operations!(u64, U64x8, 8);
+    test_lower_bound_batch!(u64, U64x8, 8);
+    test_minmax_slice!(u64, U64x8, 8);
+    test_select_ranks!(u64, U64x8, 8);
+    test_partition!(u64, U64x8, 8);
+
+    test_sort_n!(f64, F64x8, 8, 8);
+    test_sort_n!(f64, F64x8, 8, 16);
+    test_sort_n!(f64, F64x8, 8, 32);
+    test_sort_n!(f64, F64x8, 8, 64);
+    test_sort_n!(f64, F64x8, 8, 128);
+    test_sort_n!(f64, F64x8, 8, 256);
+    test_sort_e2e!(f64, F64x8, scalar_sort_f64);
+    test_dedup_sorted!(f64, F64x8, 8);
+    test_count_runs!(f64, F64x8, 8);
+    test_set_operations!(f64, F64x8, 8);
+    test_lower_bound_batch!(f64, F64x8, 8);
+    test_minmax_slice!(f64, F64x8, 8);
+    test_select_ranks!(f64, F64x8, 8);
+    test_partition!(f64, F64x8, 8);
+
+    mod lanes_4 {
+        use crate::bit_64::{test::*, *};
+
+        fn sort_i64(data: &mut [i64]) {
+            qsort_64bit_::<i64, 4, [i64; 4]>(data, f64::log2(data.len() as f64) as i64)
+        }
+
+        fn sort_f64(data: &mut [f64]) {
+            qsort_64bit_::<f64, 4, [f64; 4]>(data, f64::log2(data.len() as f64) as i64)
+        }
+
+        test_sort_n!(i64, [i64; 4], 4, 4);
+        test_sort_n!(i64, [i64; 4], 4, 8);
+        test_sort_n!(i64, [i64; 4], 4, 16);
+        test_sort_n!(i64, [i64; 4], 4, 32);
+        test_sort_n!(i64, [i64; 4], 4, 64);
+        test_sort_n!(i64, [i64; 4], 4, 128);
+        test_sort_e2e!(i64, I64x4, sort_i64);
+        test_sort_e2e!(f64, F64x4, sort_f64);
+        test_dedup_sorted!(i64, [i64; 4], 4);
+        test_count_runs!(i64, [i64; 4], 4);
+        test_set_operations!(i64, [i64; 4], 4);
+        test_lower_bound_batch!(i64, [i64; 4], 4);
+        test_minmax_slice!(i64, [i64; 4], 4);
+        test_select_ranks!(i64, [i64; 4], 4);
+        test_partition!(i64, [i64; 4], 4);
+
+        #[test]
+        fn test_not_mask_4_lanes() {
+            // the bits above the 4 lanes must stay clear for ones_count
+            let mask = <[i64; 4] as crate::SimdCompare<i64, 4>>::not_mask(0b0101);
+            assert_eq!(mask, 0b1010);
+            assert_eq!(
+                <[i64; 4] as crate::SimdCompare<i64, 4>>::ones_count(mask),
+                2
+            );
+        }
+    }
+
+    mod lanes_16 {
+        use crate::bit_64::{test::*, *};
+
+        fn sort_u64(data: &mut [u64]) {
+            qsort_64bit_::<u64, 16, [u64; 16]>(data, f64::log2(data.len() as f64) as i64)
+        }
+
+        fn sort_f64(data: &mut [f64]) {
+            qsort_64bit_::<f64, 16, [f64; 16]>(data, f64::log2(data.len() as f64) as i64)
+        }
+
+        test_sort_n!(u64, [u64; 16], 16, 16);
+        test_sort_n!(u64, [u64; 16], 16, 32);
+        test_sort_n!(u64, [u64; 16], 16, 64);
+        test_sort_n!(u64, [u64; 16], 16, 128);
+        test_sort_n!(u64, [u64; 16], 16, 256);
+        test_sort_n!(u64, [u64; 16], 16, 512);
+        test_sort_e2e!(u64, U64x16, sort_u64);
+        test_sort_e2e!(f64, F64x16, sort_f64);
+        test_dedup_sorted!(u64, [u64; 16], 16);
+        test_count_runs!(u64, [u64; 16], 16);
+        test_set_operations!(u64, [u64; 16], 16);
+        test_lower_bound_batch!(u64, [u64; 16], 16);
+        test_minmax_slice!(u64, [u64; 16], 16);
+        test_select_ranks!(u64, [u64; 16], 16);
+        test_partition!(u64, [u64; 16], 16);
+    }
+}
@@ -1,265 +1,343 @@
 use std::{
     arch::wasm32::{
-        f64x2, i64x2, i64x2_bitmask, i64x2_extract_lane, i64x2_ge, i64x2_gt, i64x2_lt,
-        i64x2_shuffle, u64x2, v128, v128_bitselect, v128_load, v128_store,
+        f64x2, f64x2_eq, f64x2_extract_lane, f64x2_ge, f64x2_max, f64x2_min, i64x2, i64x2_bitmask,
+        i64x2_eq, i64x2_extract_lane, i64x2_ge, i64x2_gt, i64x2_lt, i64x2_shuffle, i64x2_splat,
+        u64x2, u64x2_extract_lane, v128, v128_bitselect, v128_load, v128_not, v128_store, v128_xor,
     },
-    mem::{self},
-    ptr,
+    mem, ptr,
 };
 
 use crate::{bit_64::Bit64Simd, SimdCompare};
 
-#[derive(Debug, Clone, Copy)]
-pub struct Wasmi64x8 {
-    values: [v128; 4],
+// lanes selected by the low 2 bits of mask from b, the others from a
+#[inline]
+fn mask_mov_x2(a: v128, b: v128, mask: u64) -> v128 {
+    match mask & 0b11 {
+        0b00 => a,
+        0b01 => i64x2_shuffle::<2, 1>(a, b),
+        0b10 => i64x2_shuffle::<0, 3>(a, b),
+        _ => b,
+    }
 }
 
-impl Wasmi64x8 {
-    fn min_x2(a: v128, b: v128) -> v128 {
-        v128_bitselect(a, b, i64x2_lt(a, b))
-    }
+fn i64_min_x2(a: v128, b: v128) -> v128 {
+    v128_bitselect(a, b, i64x2_lt(a, b))
+}
 
-    fn max_x2(a: v128, b: v128) -> v128 {
-        v128_bitselect(a, b, i64x2_gt(a, b))
-    }
+fn i64_max_x2(a: v128, b: v128) -> v128 {
+    v128_bitselect(a, b, i64x2_gt(a, b))
 }
 
-impl SimdCompare<i64, 8> for Wasmi64x8 {
-    type OPMask = u8;
-
-    fn min(a: Self, b: Self) -> Self {
-        Wasmi64x8 {
-            values: [
-                Self::min_x2(a.values[0], b.values[0]),
-                Self::min_x2(a.values[1], b.values[1]),
-                Self::min_x2(a.values[2], b.values[2]),
-                Self::min_x2(a.values[3], b.values[3]),
-            ],
-        }
-    }
+// wasm simd128 only has signed 64 bit compares, flip the sign bits
+// to compare unsigned values
+fn u64_gt_x2(a: v128, b: v128) -> v128 {
+    let sign = i64x2_splat(i64::MIN);
+    i64x2_gt(v128_xor(a, sign), v128_xor(b, sign))
+}
+
+fn u64_ge_x2(a: v128, b: v128) -> v128 {
+    v128_not(u64_gt_x2(b, a))
+}
+
+fn u64_min_x2(a: v128, b: v128) -> v128 {
+    v128_bitselect(b, a, u64_gt_x2(a, b))
+}
+
+fn u64_max_x2(a: v128, b: v128) -> v128 {
+    v128_bitselect(a, b, u64_gt_x2(a, b))
+}
 
-    fn max(a: Self, b: Self) -> Self {
-        Wasmi64x8 {
-            values: [
-                Self::max_x2(a.values[0], b.values[0]),
-                Self::max_x2(a.values[1], b.values[1]),
-                Self::max_x2(a.values[2], b.values[2]),
-                Self::max_x2(a.values[3], b.values[3]),
-            ],
+/// Implements the 8 lane vector of `$ty` as 4 `v128` registers. The element
+/// types only differ in how two lanes are built, extracted and compared.
+macro_rules! impl_wasm_64x8 {
+    (
+        $name: ident,
+        $ty: ident,
+        lanes: $x2: ident,
+        extract: $extract_lane: ident,
+        min: $min_x2: ident,
+        max: $max_x2: ident,
+        ge: $ge_x2: ident,
+        eq: $eq_x2: ident $(,)?
+    ) => {
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name {
+            values: [v128; 4],
         }
-    }
 
-    fn loadu(data: &[i64]) -> Self {
-        unsafe {
-            let base_ptr = data.as_ptr();
-
-            Wasmi64x8 {
-                values: [
-                    v128_load(mem::transmute(base_ptr)),
-                    v128_load(mem::transmute(base_ptr.offset(2))),
-                    v128_load(mem::transmute(base_ptr.offset(4))),
-                    v128_load(mem::transmute(base_ptr.offset(6))),
-                ],
+        impl SimdCompare<$ty, 8> for $name {
+            type OPMask = u8;
+
+            fn min(a: Self, b: Self) -> Self {
+                Self {
+                    values: [
+                        $min_x2(a.values[0], b.values[0]),
+                        $min_x2(a.values[1], b.values[1]),
+                        $min_x2(a.values[2], b.values[2]),
+                        $min_x2(a.values[3], b.values[3]),
+                    ],
+                }
             }
-        }
-    }
 
-    fn storeu(input: Self, data: &mut [i64]) {
-        unsafe {
-            let base_ptr = data.as_mut_ptr();
+            fn max(a: Self, b: Self) -> Self {
+                Self {
+                    values: [
+                        $max_x2(a.values[0], b.values[0]),
+                        $max_x2(a.values[1], b.values[1]),
+                        $max_x2(a.values[2], b.values[2]),
+                        $max_x2(a.values[3], b.values[3]),
+                    ],
+                }
+            }
 
-            v128_store(mem::transmute(base_ptr), input.values[0]);
-            v128_store(mem::transmute(base_ptr.offset(2)), input.values[1]);
-            v128_store(mem::transmute(base_ptr.offset(4)), input.values[2]);
-            v128_store(mem::transmute(base_ptr.offset(6)), input.values[3]);
-        }
-    }
+            #[inline]
+            fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+                Self {
+                    values: [
+                        mask_mov_x2(a.values[0], b.values[0], MASK),
+                        mask_mov_x2(a.values[1], b.values[1], MASK >> 2),
+                        mask_mov_x2(a.values[2], b.values[2], MASK >> 4),
+                        mask_mov_x2(a.values[3], b.values[3], MASK >> 6),
+                    ],
+                }
+            }
 
-    fn mask_loadu(data: &[i64]) -> Self {
-        let mut max = [i64::MAX; 8];
-        max[..data.len()].copy_from_slice(data);
-        unsafe {
-            ptr::copy(data.as_ptr(), max.as_mut_ptr(), data.len());
-            Wasmi64x8 {
-                values: mem::transmute(max),
+            fn loadu(data: &[$ty]) -> Self {
+                unsafe {
+                    let base_ptr = data.as_ptr();
+
+                    Self {
+                        values: [
+                            v128_load(base_ptr.cast()),
+                            v128_load(base_ptr.add(2).cast()),
+                            v128_load(base_ptr.add(4).cast()),
+                            v128_load(base_ptr.add(6).cast()),
+                        ],
+                    }
+                }
             }
-        }
-    }
 
-    fn mask_storeu(input: Self, data: &mut [i64]) {
-        unsafe {
-            ptr::copy(
-                mem::transmute(input.values.as_ptr()),
-                data.as_mut_ptr(),
-                data.len(),
-            );
-        }
-    }
+            fn storeu(input: Self, data: &mut [$ty]) {
+                unsafe {
+                    let base_ptr = data.as_mut_ptr();
 
-    fn gather_from_idx(idx: [usize; 8], data: &[i64]) -> Self {
-        unsafe {
-            let base_ptr = data.as_ptr();
-            Wasmi64x8 {
-                values: [
-                    i64x2(
-                        *base_ptr.offset(idx[0] as isize),
-                        *base_ptr.offset(idx[1] as isize),
-                    ),
-                    i64x2(
-                        *base_ptr.offset(idx[2] as isize),
-                        *base_ptr.offset(idx[3] as isize),
-                    ),
-                    i64x2(
-                        *base_ptr.offset(idx[4] as isize),
-                        *base_ptr.offset(idx[5] as isize),
-                    ),
-                    i64x2(
-                        *base_ptr.offset(idx[6] as isize),
-                        *base_ptr.offset(idx[7] as isize),
-                    ),
-                ],
+                    v128_store(base_ptr.cast(), input.values[0]);
+                    v128_store(base_ptr.add(2).cast(), input.values[1]);
+                    v128_store(base_ptr.add(4).cast(), input.values[2]);
+                    v128_store(base_ptr.add(6).cast(), input.values[3]);
+                }
             }
-        }
-    }
 
-    fn get_value_at_idx(input: Self, idx: usize) -> i64 {
-        unsafe {
-            let base_ptr: *const i64 = mem::transmute(&input);
-            *base_ptr.offset(idx as isize)
-        }
-    }
+            fn mask_loadu(data: &[$ty]) -> Self {
+                let mut max = [$ty::MAX; 8];
+                max[..data.len()].copy_from_slice(data);
+                unsafe {
+                    Self {
+                        values: mem::transmute::<[$ty; 8], [v128; 4]>(max),
+                    }
+                }
+            }
 
-    fn set(value: i64) -> Self {
-        Wasmi64x8 {
-            values: [
-                i64x2(value, value),
-                i64x2(value, value),
-                i64x2(value, value),
-                i64x2(value, value),
-            ],
-        }
-    }
+            fn mask_storeu(input: Self, data: &mut [$ty]) {
+                unsafe {
+                    ptr::copy(input.values.as_ptr().cast(), data.as_mut_ptr(), data.len());
+                }
+            }
 
-    fn ge(a: Self, b: Self) -> Self::OPMask {
-        i64x2_bitmask(i64x2_ge(a.values[0], b.values[0]))
-            | i64x2_bitmask(i64x2_ge(a.values[1], b.values[1])) << 2
-            | i64x2_bitmask(i64x2_ge(a.values[2], b.values[2])) << 4
-            | i64x2_bitmask(i64x2_ge(a.values[3], b.values[3])) << 6
-    }
+            fn gather_from_idx(idx: [usize; 8], data: &[$ty]) -> Self {
+                unsafe {
+                    let base_ptr = data.as_ptr();
+                    Self {
+                        values: [
+                            $x2(*base_ptr.add(idx[0]), *base_ptr.add(idx[1])),
+                            $x2(*base_ptr.add(idx[2]), *base_ptr.add(idx[3])),
+                            $x2(*base_ptr.add(idx[4]), *base_ptr.add(idx[5])),
+                            $x2(*base_ptr.add(idx[6]), *base_ptr.add(idx[7])),
+                        ],
+                    }
+                }
+            }
 
-    fn ones_count(mask: Self::OPMask) -> usize {
-        mask.count_ones() as usize
-    }
+            fn get_value_at_idx(input: Self, idx: usize) -> $ty {
+                unsafe {
+                    let base_ptr = input.values.as_ptr().cast::<$ty>();
+                    *base_ptr.add(idx)
+                }
+            }
 
-    fn not_mask(mask: Self::OPMask) -> Self::OPMask {
-        !mask
-    }
+            fn set(value: $ty) -> Self {
+                Self {
+                    values: [$x2(value, value); 4],
+                }
+            }
 
-    fn reducemin(x: Self) -> i64 {
-        let m1 = Self::min_x2(x.values[0], x.values[1]);
-        let m2 = Self::min_x2(x.values[2], x.values[3]);
-        let m2 = Self::min_x2(m1, m2);
-        let m2_shuffle = i64x2_shuffle::<1, 0>(m2, m2);
-        let m2 = Self::min_x2(m2, m2_shuffle);
-        i64x2_extract_lane::<0>(m2)
-    }
+            fn ge(a: Self, b: Self) -> Self::OPMask {
+                i64x2_bitmask($ge_x2(a.values[0], b.values[0]))
+                    | i64x2_bitmask($ge_x2(a.values[1], b.values[1])) << 2
+                    | i64x2_bitmask($ge_x2(a.values[2], b.values[2])) << 4
+                    | i64x2_bitmask($ge_x2(a.values[3], b.values[3])) << 6
+            }
 
-    fn reducemax(x: Self) -> i64 {
-        let m1 = Self::max_x2(x.values[0], x.values[1]);
-        let m2 = Self::max_x2(x.values[2], x.values[3]);
-        let m2 = Self::max_x2(m1, m2);
-        let m2_shuffle = i64x2_shuffle::<1, 0>(m2, m2);
-        let m2 = Self::max_x2(m2, m2_shuffle);
-        i64x2_extract_lane::<0>(m2)
-    }
+            fn eq(a: Self, b: Self) -> Self::OPMask {
+                i64x2_bitmask($eq_x2(a.values[0], b.values[0]))
+                    | i64x2_bitmask($eq_x2(a.values[1], b.values[1])) << 2
+                    | i64x2_bitmask($eq_x2(a.values[2], b.values[2])) << 4
+                    | i64x2_bitmask($eq_x2(a.values[3], b.values[3])) << 6
+            }
+
+            fn ones_count(mask: Self::OPMask) -> usize {
+                mask.count_ones() as usize
+            }
 
-    fn mask_compressstoreu(array: &mut [i64], mask: Self::OPMask, data: Self) {
-        unsafe {
-            let mut temp_mask = mask;
-            let mut base_ptr = array.as_mut_ptr();
-            let value_ptr: *const i64 = mem::transmute(&data);
+            fn to_bitmask(mask: Self::OPMask) -> u64 {
+                mask as u64
+            }
 
-            for i in 0..8 {
-                if temp_mask & 1 == 1 {
-                    *base_ptr = *value_ptr.offset(i);
-                    base_ptr = base_ptr.offset(1);
+            fn not_mask(mask: Self::OPMask) -> Self::OPMask {
+                !mask
+            }
+
+            fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+                a | b
+            }
+
+            fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+                a & b
+            }
+
+            fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+                Self {
+                    values: [
+                        mask_mov_x2(a.values[0], b.values[0], mask as u64),
+                        mask_mov_x2(a.values[1], b.values[1], (mask as u64) >> 2),
+                        mask_mov_x2(a.values[2], b.values[2], (mask as u64) >> 4),
+                        mask_mov_x2(a.values[3], b.values[3], (mask as u64) >> 6),
+                    ],
                 }
-                temp_mask = temp_mask >> 1;
             }
-        }
-    }
-}
 
-impl Bit64Simd<i64> for Wasmi64x8 {
-    fn swizzle2_0xaa(a: Self, b: Self) -> Self {
-        Self {
-            values: [
-                i64x2_shuffle::<0, 3>(a.values[0], b.values[0]),
-                i64x2_shuffle::<0, 3>(a.values[1], b.values[1]),
-                i64x2_shuffle::<0, 3>(a.values[2], b.values[2]),
-                i64x2_shuffle::<0, 3>(a.values[3], b.values[3]),
-            ],
-        }
-    }
+            fn reducemin(x: Self) -> $ty {
+                let m1 = $min_x2(x.values[0], x.values[1]);
+                let m2 = $min_x2(x.values[2], x.values[3]);
+                let m2 = $min_x2(m1, m2);
+                let m2_shuffle = i64x2_shuffle::<1, 0>(m2, m2);
+                let m2 = $min_x2(m2, m2_shuffle);
+                $extract_lane::<0>(m2)
+            }
 
-    fn swizzle2_0xcc(a: Self, b: Self) -> Self {
-        Self {
-            values: [a.values[0], b.values[1], a.values[2], b.values[3]],
-        }
-    }
+            fn reducemax(x: Self) -> $ty {
+                let m1 = $max_x2(x.values[0], x.values[1]);
+                let m2 = $max_x2(x.values[2], x.values[3]);
+                let m2 = $max_x2(m1, m2);
+                let m2_shuffle = i64x2_shuffle::<1, 0>(m2, m2);
+                let m2 = $max_x2(m2, m2_shuffle);
+                $extract_lane::<0>(m2)
+            }
 
-    fn swizzle2_0xf0(a: Self, b: Self) -> Self {
-        Self {
-            values: [a.values[0], a.values[1], b.values[2], b.values[3]],
+            fn mask_compressstoreu(array: &mut [$ty], mask: Self::OPMask, data: Self) {
+                unsafe {
+                    let mut temp_mask = mask;
+                    let mut base_ptr = array.as_mut_ptr();
+                    let value_ptr = data.values.as_ptr().cast::<$ty>();
+
+                    for i in 0..8 {
+                        if temp_mask & 1 == 1 {
+                            *base_ptr = *value_ptr.add(i);
+                            base_ptr = base_ptr.add(1);
+                        }
+                        temp_mask >>= 1;
+                    }
+                }
+            }
         }
-    }
 
-    fn shuffle1_1_1_1(a: Self) -> Self {
-        Self {
-            values: [
-                i64x2_shuffle::<1, 0>(a.values[0], a.values[0]),
-                i64x2_shuffle::<1, 0>(a.values[1], a.values[1]),
-                i64x2_shuffle::<1, 0>(a.values[2], a.values[2]),
-                i64x2_shuffle::<1, 0>(a.values[3], a.values[3]),
-            ],
-        }
-    }
+        // This is safe since the simd128 feature is enabled and the compress
+        // store writes one value per set lane
+        #[cfg(target_feature = "simd128")]
+        unsafe impl crate::SortBackend<$ty, 8> for $name {}
+
+        impl Bit64Simd<$ty> for $name {
+            fn shuffle1_1_1_1(a: Self) -> Self {
+                Self {
+                    values: [
+                        i64x2_shuffle::<1, 0>(a.values[0], a.values[0]),
+                        i64x2_shuffle::<1, 0>(a.values[1], a.values[1]),
+                        i64x2_shuffle::<1, 0>(a.values[2], a.values[2]),
+                        i64x2_shuffle::<1, 0>(a.values[3], a.values[3]),
+                    ],
+                }
+            }
 
-    fn network64bit1(a: Self) -> Self {
-        Self {
-            values: [
-                i64x2_shuffle::<1, 0>(a.values[1], a.values[1]),
-                i64x2_shuffle::<1, 0>(a.values[0], a.values[0]),
-                i64x2_shuffle::<1, 0>(a.values[3], a.values[3]),
-                i64x2_shuffle::<1, 0>(a.values[2], a.values[2]),
-            ],
-        }
-    }
+            fn network64bit1(a: Self) -> Self {
+                Self {
+                    values: [
+                        i64x2_shuffle::<1, 0>(a.values[1], a.values[1]),
+                        i64x2_shuffle::<1, 0>(a.values[0], a.values[0]),
+                        i64x2_shuffle::<1, 0>(a.values[3], a.values[3]),
+                        i64x2_shuffle::<1, 0>(a.values[2], a.values[2]),
+                    ],
+                }
+            }
 
-    fn network64bit2(a: Self) -> Self {
-        Self {
-            values: [
-                i64x2_shuffle::<1, 0>(a.values[3], a.values[3]),
-                i64x2_shuffle::<1, 0>(a.values[2], a.values[2]),
-                i64x2_shuffle::<1, 0>(a.values[1], a.values[1]),
-                i64x2_shuffle::<1, 0>(a.values[0], a.values[0]),
-            ],
-        }
-    }
+            fn network64bit2(a: Self) -> Self {
+                Self {
+                    values: [
+                        i64x2_shuffle::<1, 0>(a.values[3], a.values[3]),
+                        i64x2_shuffle::<1, 0>(a.values[2], a.values[2]),
+                        i64x2_shuffle::<1, 0>(a.values[1], a.values[1]),
+                        i64x2_shuffle::<1, 0>(a.values[0], a.values[0]),
+                    ],
+                }
+            }
 
-    fn network64bit3(a: Self) -> Self {
-        Self {
-            values: [a.values[1], a.values[0], a.values[3], a.values[2]],
-        }
-    }
+            fn network64bit3(a: Self) -> Self {
+                Self {
+                    values: [a.values[1], a.values[0], a.values[3], a.values[2]],
+                }
+            }
 
-    fn network64bit4(a: Self) -> Self {
-        Self {
-            values: [a.values[2], a.values[3], a.values[0], a.values[1]],
+            fn network64bit4(a: Self) -> Self {
+                Self {
+                    values: [a.values[2], a.values[3], a.values[0], a.values[1]],
+                }
+            }
         }
-    }
+    };
 }
 
+impl_wasm_64x8!(
+    Wasmi64x8,
+    i64,
+    lanes: i64x2,
+    extract: i64x2_extract_lane,
+    min: i64_min_x2,
+    max: i64_max_x2,
+    ge: i64x2_ge,
+    eq: i64x2_eq,
+);
+
+impl_wasm_64x8!(
+    Wasmu64x8,
+    u64,
+    lanes: u64x2,
+    extract: u64x2_extract_lane,
+    min: u64_min_x2,
+    max: u64_max_x2,
+    ge: u64_ge_x2,
+    eq: i64x2_eq,
+);
+
+impl_wasm_64x8!(
+    Wasmf64x8,
+    f64,
+    lanes: f64x2,
+    extract: f64x2_extract_lane,
+    min: f64x2_min,
+    max: f64x2_max,
+    ge: f64x2_ge,
+    eq: f64x2_eq,
+);
+
 #[cfg(test)]
 mod test {
     use crate::bit_64::test::*;
@@ -280,18 +358,43 @@ mod test {
         ]
     }
 
+    fn into_array_u64(x: Wasmu64x8) -> [u64; 8] {
+        [
+            u64x2_extract_lane::<0>(x.values[0]),
+            u64x2_extract_lane::<1>(x.values[0]),
+            u64x2_extract_lane::<0>(x.values[1]),
+            u64x2_extract_lane::<1>(x.values[1]),
+            u64x2_extract_lane::<0>(x.values[2]),
+            u64x2_extract_lane::<1>(x.values[2]),
+            u64x2_extract_lane::<0>(x.values[3]),
+            u64x2_extract_lane::<1>(x.values[3]),
+        ]
+    }
+
+    fn into_array_f64(x: Wasmf64x8) -> [f64; 8] {
+        [
+            f64x2_extract_lane::<0>(x.values[0]),
+            f64x2_extract_lane::<1>(x.values[0]),
+            f64x2_extract_lane::<0>(x.values[1]),
+            f64x2_extract_lane::<1>(x.values[1]),
+            f64x2_extract_lane::<0>(x.values[2]),
+            f64x2_extract_lane::<1>(x.values[2]),
+            f64x2_extract_lane::<0>(x.values[3]),
+            f64x2_extract_lane::<1>(x.values[3]),
+        ]
+    }
+
     fn generate_mask_answer<T, M>(bitmask: usize, values: &[T]) -> (M, [T; 8])
     where
-        T: TryFrom<usize> + Default + Copy,
-        <T as TryFrom<usize>>::Error: Debug,
+        T: Default + Copy,
         M: TryFrom<usize>,
         <M as TryFrom<usize>>::Error: Debug,
     {
         let mut new_values = [<T as Default>::default(); 8];
         let mut count = 0;
-        for i in 0..8 {
+        for (i, &value) in values.iter().take(8).enumerate() {
             if bitmask & (1 << i) != 0 {
-                new_values[count] = values[i];
+                new_values[count] = value;
                 count += 1;
             }
         }
@@ -306,6 +409,9 @@ mod test {
     test_loadu_storeu!(i64, Wasmi64x8, into_array_i64);
     test_mask_loadu_mask_storeu!(i64, Wasmi64x8, into_array_i64);
     test_get_at_index!(i64, Wasmi64x8);
+    test_eq_or_mask!(i64, Wasmi64x8);
+    test_to_bitmask!(i64, Wasmi64x8);
+    test_and_mask_blend!(i64, Wasmi64x8);
     test_ge!(i64, Wasmi64x8, 0b10101010);
     test_gather!(i64, Wasmi64x8, into_array_i64);
     test_not!(i64, Wasmi64x8, 5, !5);
@@ -313,11 +419,49 @@ mod test {
     test_reduce_min_max!(i64, Wasmi64x8);
     test_compress_store_u!(i64, Wasmi64x8, u8, generate_mask_answer);
     test_shuffle1_1_1_1!(i64, Wasmi64x8, into_array_i64);
-    test_swizzle2_0xaa!(i64, Wasmi64x8, into_array_i64);
-    test_swizzle2_0xcc!(i64, Wasmi64x8, into_array_i64);
-    test_swizzle2_0xf0!(i64, Wasmi64x8, into_array_i64);
+    test_mask_mov!(i64, Wasmi64x8, into_array_i64);
     network64bit1!(i64, Wasmi64x8, into_array_i64);
     network64bit2!(i64, Wasmi64x8, into_array_i64);
     network64bit3!(i64, Wasmi64x8, into_array_i64);
     network64bit4!(i64, Wasmi64x8, into_array_i64);
+
+    test_min_max!(u64, Wasmu64x8, into_array_u64);
+    test_loadu_storeu!(u64, Wasmu64x8, into_array_u64);
+    test_mask_loadu_mask_storeu!(u64, Wasmu64x8, into_array_u64);
+    test_get_at_index!(u64, Wasmu64x8);
+    test_eq_or_mask!(u64, Wasmu64x8);
+    test_to_bitmask!(u64, Wasmu64x8);
+    test_and_mask_blend!(u64, Wasmu64x8);
+    test_ge!(u64, Wasmu64x8, 0b10101010);
+    test_gather!(u64, Wasmu64x8, into_array_u64);
+    test_not!(u64, Wasmu64x8, 5, !5);
+    test_count_ones!(u64, Wasmu64x8, mask_fn);
+    test_reduce_min_max!(u64, Wasmu64x8);
+    test_compress_store_u!(u64, Wasmu64x8, u8, generate_mask_answer);
+    test_shuffle1_1_1_1!(u64, Wasmu64x8, into_array_u64);
+    test_mask_mov!(u64, Wasmu64x8, into_array_u64);
+    network64bit1!(u64, Wasmu64x8, into_array_u64);
+    network64bit2!(u64, Wasmu64x8, into_array_u64);
+    network64bit3!(u64, Wasmu64x8, into_array_u64);
+    network64bit4!(u64, Wasmu64x8, into_array_u64);
+
+    test_min_max!(f64, Wasmf64x8, into_array_f64);
+    test_loadu_storeu!(f64, Wasmf64x8, into_array_f64);
+    test_mask_loadu_mask_storeu!(f64, Wasmf64x8, into_array_f64);
+    test_get_at_index!(f64, Wasmf64x8);
+    test_eq_or_mask!(f64, Wasmf64x8);
+    test_to_bitmask!(f64, Wasmf64x8);
+    test_and_mask_blend!(f64, Wasmf64x8);
+    test_ge!(f64, Wasmf64x8, 0b10101010);
+    test_gather!(f64, Wasmf64x8, into_array_f64);
+    test_not!(f64, Wasmf64x8, 5, !5);
+    test_count_ones!(f64, Wasmf64x8, mask_fn);
+    test_reduce_min_max!(f64, Wasmf64x8);
+    test_compress_store_u!(f64, Wasmf64x8, u8, generate_mask_answer);
+    test_shuffle1_1_1_1!(f64, Wasmf64x8, into_array_f64);
+    test_mask_mov!(f64, Wasmf64x8, into_array_f64);
+    network64bit1!(f64, Wasmf64x8, into_array_f64);
+    network64bit2!(f64, Wasmf64x8, into_array_f64);
+    network64bit3!(f64, Wasmf64x8, into_array_f64);
+    network64bit4!(f64, Wasmf64x8, into_array_f64);
 }
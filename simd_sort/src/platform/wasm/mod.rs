@@ -1,11 +1,19 @@
 pub(crate) mod bit64;
 
-use bit64::Wasmi64x8;
+use bit64::{Wasmf64x8, Wasmi64x8, Wasmu64x8};
 
 use crate::bit_64::qsort_64bit_;
 
 pub fn wasm128_sort_i64(data: &mut [i64]) {
-    qsort_64bit_::<i64, Wasmi64x8>(data, f64::log2(data.len() as f64) as i64)
+    qsort_64bit_::<i64, 8, Wasmi64x8>(data, f64::log2(data.len() as f64) as i64)
+}
+
+pub fn wasm128_sort_u64(data: &mut [u64]) {
+    qsort_64bit_::<u64, 8, Wasmu64x8>(data, f64::log2(data.len() as f64) as i64)
+}
+
+pub fn wasm128_sort_f64(data: &mut [f64]) {
+    qsort_64bit_::<f64, 8, Wasmf64x8>(data, f64::log2(data.len() as f64) as i64)
 }
 
 #[cfg(test)]
@@ -14,11 +22,48 @@ mod test {
 
     use super::*;
 
-    test_sort_n!(i64, Wasmi64x8, 8);
-    test_sort_n!(i64, Wasmi64x8, 16);
-    test_sort_n!(i64, Wasmi64x8, 32);
-    test_sort_n!(i64, Wasmi64x8, 64);
-    test_sort_n!(i64, Wasmi64x8, 128);
-    test_sort_n!(i64, Wasmi64x8, 256);
+    test_sort_n!(i64, Wasmi64x8, 8, 8);
+    test_sort_n!(i64, Wasmi64x8, 8, 16);
+    test_sort_n!(i64, Wasmi64x8, 8, 32);
+    test_sort_n!(i64, Wasmi64x8, 8, 64);
+    test_sort_n!(i64, Wasmi64x8, 8, 128);
+    test_sort_n!(i64, Wasmi64x8, 8, 256);
     test_sort_e2e!(i64, Wasmi64x8, wasm128_sort_i64);
+    test_dedup_sorted!(i64, Wasmi64x8, 8);
+    test_count_runs!(i64, Wasmi64x8, 8);
+    test_set_operations!(i64, Wasmi64x8, 8);
+    test_lower_bound_batch!(i64, Wasmi64x8, 8);
+    test_minmax_slice!(i64, Wasmi64x8, 8);
+    test_select_ranks!(i64, Wasmi64x8, 8);
+    test_partition!(i64, Wasmi64x8, 8);
+
+    test_sort_n!(u64, Wasmu64x8, 8, 8);
+    test_sort_n!(u64, Wasmu64x8, 8, 16);
+    test_sort_n!(u64, Wasmu64x8, 8, 32);
+    test_sort_n!(u64, Wasmu64x8, 8, 64);
+    test_sort_n!(u64, Wasmu64x8, 8, 128);
+    test_sort_n!(u64, Wasmu64x8, 8, 256);
+    test_sort_e2e!(u64, Wasmu64x8, wasm128_sort_u64);
+    test_dedup_sorted!(u64, Wasmu64x8, 8);
+    test_count_runs!(u64, Wasmu64x8, 8);
+    test_set_operations!(u64, Wasmu64x8, 8);
+    test_lower_bound_batch!(u64, Wasmu64x8, 8);
+    test_minmax_slice!(u64, Wasmu64x8, 8);
+    test_select_ranks!(u64, Wasmu64x8, 8);
+    test_partition!(u64, Wasmu64x8, 8);
+
+    test_sort_n!(f64, Wasmf64x8, 8, 8);
+    test_sort_n!(f64, Wasmf64x8, 8, 16);
+    test_sort_n!(f64, Wasmf64x8, 8, 32);
+    test_sort_n!(f64, Wasmf64x8, 8, 64);
+    test_sort_n!(f64, Wasmf64x8, 8, 128);
+    test_sort_n!(f64, Wasmf64x8, 8, 256);
+    test_sort_e2e!(f64, Wasmf64x8, wasm128_sort_f64);
+    test_dedup_sorted!(f64, Wasmf64x8, 8);
+    test_count_runs!(f64, Wasmf64x8, 8);
+    test_set_operations!(f64, Wasmf64x8, 8);
+    test_lower_bound_batch!(f64, Wasmf64x8, 8);
+    test_minmax_slice!(f64, Wasmf64x8, 8);
+    test_select_ranks!(f64, Wasmf64x8, 8);
+    test_partition!(f64, Wasmf64x8, 8);
 }
@@ -1,9 +1,10 @@
 use std::arch::x86_64::{
-    __m256d, _mm256_blendv_pd, _mm256_castpd_si256, _mm256_castsi256_pd, _mm256_cmp_pd,
-    _mm256_extractf128_pd, _mm256_i64gather_pd, _mm256_loadu_pd, _mm256_loadu_si256,
-    _mm256_mask_i32gather_pd, _mm256_maskstore_pd, _mm256_max_pd, _mm256_min_pd,
-    _mm256_movemask_pd, _mm256_permute4x64_pd, _mm256_permutevar8x32_epi32, _mm256_set1_pd,
-    _mm256_shuffle_pd, _mm256_storeu_pd, _mm256_xor_pd, _mm_max_pd, _mm_min_pd, _mm_permute_pd,
+    __m128d, __m128i, __m256d, _mm256_and_pd, _mm256_blendv_pd, _mm256_castpd_si256,
+    _mm256_castsi256_pd, _mm256_cmp_pd, _mm256_extractf128_pd, _mm256_i64gather_pd,
+    _mm256_loadu_pd, _mm256_loadu_si256, _mm256_mask_i32gather_pd, _mm256_maskstore_pd,
+    _mm256_max_pd, _mm256_min_pd, _mm256_movemask_pd, _mm256_or_pd, _mm256_permute4x64_pd,
+    _mm256_permutevar8x32_epi32, _mm256_set1_pd, _mm256_setr_epi64x, _mm256_shuffle_pd,
+    _mm256_storeu_pd, _mm256_xor_pd, _mm_max_pd, _mm_min_pd, _mm_permute_pd, _CMP_EQ_OQ,
     _CMP_GE_OQ,
 };
 use std::{mem, slice};
@@ -45,7 +46,7 @@ impl From<[f64; 8]> for Avx2F64x2 {
 
 impl Avx2F64x2 {
     fn as_slice(&self) -> &[f64] {
-        unsafe { slice::from_raw_parts(mem::transmute(self.values.as_ptr()), 8) }
+        unsafe { slice::from_raw_parts(self.values.as_ptr().cast(), 8) }
     }
 }
 
@@ -59,6 +60,25 @@ fn shuffle_256d<const MASK: i32>(a: __m256d, b: __m256d) -> __m256d {
     unsafe { _mm256_shuffle_pd(a, b, MASK) }
 }
 
+// lanes selected by the low 4 bits of mask from b, the others from a
+#[inline]
+fn mask_mov_256d(a: __m256d, b: __m256d, mask: u64) -> __m256d {
+    match mask & 0xF {
+        0b0000 => a,
+        0b1010 => shuffle_256d::<0b1010>(a, b),
+        0b1111 => b,
+        m => unsafe {
+            let mask = _mm256_castsi256_pd(_mm256_setr_epi64x(
+                -((m & 1) as i64),
+                -((m >> 1 & 1) as i64),
+                -((m >> 2 & 1) as i64),
+                -((m >> 3 & 1) as i64),
+            ));
+            blend_256d(a, b, mask)
+        },
+    }
+}
+
 impl SimdCompare<f64, 8> for Avx2F64x2 {
     type OPMask = Self;
 
@@ -66,9 +86,9 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
         unsafe {
             let value1 = _mm256_min_pd(a.values[0], b.values[0]);
             let value2 = _mm256_min_pd(a.values[1], b.values[1]);
-            return Self {
+            Self {
                 values: [value1, value2],
-            };
+            }
         }
     }
 
@@ -76,9 +96,18 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
         unsafe {
             let value1 = _mm256_max_pd(a.values[0], b.values[0]);
             let value2 = _mm256_max_pd(a.values[1], b.values[1]);
-            return Self {
+            Self {
                 values: [value1, value2],
-            };
+            }
+        }
+    }
+
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Self {
+            values: [
+                mask_mov_256d(a.values[0], b.values[0], MASK),
+                mask_mov_256d(a.values[1], b.values[1], MASK >> 4),
+            ],
         }
     }
 
@@ -102,10 +131,10 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
     fn mask_loadu(data: &[f64]) -> Self {
         unsafe {
             let mask = LOADU_MASK.get_unchecked(data.len()).as_ptr();
-            let mask1 = _mm256_loadu_pd(mem::transmute(mask));
-            let mask2 = _mm256_loadu_pd(mem::transmute(mask.offset(4)));
-            let indices1 = mem::transmute(V_INDEX_1);
-            let indices2 = mem::transmute(V_INDEX_2);
+            let mask1 = _mm256_loadu_pd(mask.cast());
+            let mask2 = _mm256_loadu_pd(mask.offset(4).cast());
+            let indices1 = mem::transmute::<[i32; 4], __m128i>(V_INDEX_1);
+            let indices2 = mem::transmute::<[i32; 4], __m128i>(V_INDEX_2);
             let max_values = _mm256_set1_pd(f64::MAX);
             let v1 = _mm256_mask_i32gather_pd(max_values, data.as_ptr(), indices1, mask1, 8);
             let v2 = _mm256_mask_i32gather_pd(max_values, data.as_ptr(), indices2, mask2, 8);
@@ -116,8 +145,8 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
     fn mask_storeu(input: Self, data: &mut [f64]) {
         unsafe {
             let mask = LOADU_MASK.get_unchecked(data.len());
-            let mask1 = _mm256_loadu_si256(mem::transmute(mask.as_ptr()));
-            let mask2 = _mm256_loadu_si256(mem::transmute(mask[4..].as_ptr()));
+            let mask1 = _mm256_loadu_si256(mask.as_ptr().cast());
+            let mask2 = _mm256_loadu_si256(mask[4..].as_ptr().cast());
             let base_ptr = data.as_mut_ptr();
             _mm256_maskstore_pd(base_ptr, mask1, input.values[0]);
             //let split_index = cmp::min(data.len(), 4);
@@ -128,9 +157,9 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
 
     fn gather_from_idx(idx: [usize; 8], data: &[f64]) -> Self {
         unsafe {
-            let idx_1 = _mm256_loadu_si256(mem::transmute(idx.as_ptr()));
+            let idx_1 = _mm256_loadu_si256(idx.as_ptr().cast());
             let v1 = _mm256_i64gather_pd(data.as_ptr(), idx_1, 8);
-            let idx_2 = _mm256_loadu_si256(mem::transmute(idx[4..].as_ptr()));
+            let idx_2 = _mm256_loadu_si256(idx[4..].as_ptr().cast());
             let v2 = _mm256_i64gather_pd(data.as_ptr(), idx_2, 8);
             Self { values: [v1, v2] }
         }
@@ -158,6 +187,17 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
         }
     }
 
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm256_cmp_pd(a.values[0], b.values[0], _CMP_EQ_OQ),
+                    _mm256_cmp_pd(a.values[1], b.values[1], _CMP_EQ_OQ),
+                ],
+            }
+        }
+    }
+
     fn ones_count(mask: Self::OPMask) -> usize {
         unsafe {
             let count1 = _mm256_movemask_pd(mask.values[0]);
@@ -166,9 +206,17 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
         }
     }
 
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        unsafe {
+            let low = _mm256_movemask_pd(mask.values[0]);
+            let high = _mm256_movemask_pd(mask.values[1]);
+            (low | high << 4) as u64
+        }
+    }
+
     fn not_mask(mask: Self::OPMask) -> Self::OPMask {
         unsafe {
-            let all_bit_set = mem::transmute([-1i64, -1, -1, -1]);
+            let all_bit_set = mem::transmute::<[i64; 4], __m256d>([-1i64, -1, -1, -1]);
 
             Self {
                 values: [
@@ -179,6 +227,39 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
         }
     }
 
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm256_or_pd(a.values[0], b.values[0]),
+                    _mm256_or_pd(a.values[1], b.values[1]),
+                ],
+            }
+        }
+    }
+
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm256_and_pd(a.values[0], b.values[0]),
+                    _mm256_and_pd(a.values[1], b.values[1]),
+                ],
+            }
+        }
+    }
+
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        unsafe {
+            Self {
+                values: [
+                    _mm256_blendv_pd(a.values[0], b.values[0], mask.values[0]),
+                    _mm256_blendv_pd(a.values[1], b.values[1], mask.values[1]),
+                ],
+            }
+        }
+    }
+
     fn reducemin(x: Self) -> f64 {
         unsafe {
             let min_4 = _mm256_min_pd(x.values[0], x.values[1]);
@@ -187,7 +268,7 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
             let min_2 = _mm_min_pd(v1, v2);
             let min_2_rev = _mm_permute_pd(min_2, 0b01);
             let min_result = _mm_min_pd(min_2, min_2_rev);
-            mem::transmute::<_, [f64; 2]>(min_result)[0]
+            mem::transmute::<__m128d, [f64; 2]>(min_result)[0]
         }
     }
 
@@ -199,7 +280,7 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
             let max_2 = _mm_max_pd(v1, v2);
             let max_2_rev = _mm_permute_pd(max_2, 0b01);
             let max_result = _mm_max_pd(max_2, max_2_rev);
-            mem::transmute::<_, [f64; 2]>(max_result)[0]
+            mem::transmute::<__m128d, [f64; 2]>(max_result)[0]
         }
     }
 
@@ -208,57 +289,49 @@ impl SimdCompare<f64, 8> for Avx2F64x2 {
         unsafe {
             let base_ptr = array.as_mut_ptr();
             let bitmask1 = _mm256_movemask_pd(mask.values[0]) as usize;
-            let mask1 = _mm256_loadu_si256(mem::transmute(
-                COMPRESS_MASK
+            let mask1 = _mm256_loadu_si256(
+                (COMPRESS_MASK
                     .get_unchecked(bitmask1.count_ones() as usize)
-                    .as_ptr(),
-            ));
+                    .as_ptr())
+                .cast(),
+            );
             let v1 = _mm256_castsi256_pd(_mm256_permutevar8x32_epi32(
                 _mm256_castpd_si256(data.values[0]),
-                _mm256_loadu_si256(mem::transmute(
-                    COMPRESS_PERMUTATIONS.get_unchecked(bitmask1).as_ptr(),
-                )),
+                _mm256_loadu_si256(
+                    COMPRESS_PERMUTATIONS
+                        .get_unchecked(bitmask1)
+                        .as_ptr()
+                        .cast(),
+                ),
             ));
             _mm256_maskstore_pd(base_ptr, mask1, v1);
             let bitmask2 = _mm256_movemask_pd(mask.values[1]) as usize;
-            let mask2 = _mm256_loadu_si256(mem::transmute(
-                COMPRESS_MASK
+            let mask2 = _mm256_loadu_si256(
+                (COMPRESS_MASK
                     .get_unchecked(bitmask2.count_ones() as usize)
-                    .as_ptr(),
-            ));
+                    .as_ptr())
+                .cast(),
+            );
             let v2 = _mm256_castsi256_pd(_mm256_permutevar8x32_epi32(
                 _mm256_castpd_si256(data.values[1]),
-                _mm256_loadu_si256(mem::transmute(
-                    COMPRESS_PERMUTATIONS.get_unchecked(bitmask2).as_ptr(),
-                )),
+                _mm256_loadu_si256(
+                    COMPRESS_PERMUTATIONS
+                        .get_unchecked(bitmask2)
+                        .as_ptr()
+                        .cast(),
+                ),
             ));
             _mm256_maskstore_pd(base_ptr.offset(bitmask1.count_ones() as isize), mask2, v2);
         }
     }
 }
 
-impl Bit64Simd<f64> for Avx2F64x2 {
-    fn swizzle2_0xaa(a: Self, b: Self) -> Self {
-        let v1 = shuffle_256d::<0b1010>(a.values[0], b.values[0]);
-        let v2 = shuffle_256d::<0b1010>(a.values[1], b.values[1]);
-        Self { values: [v1, v2] }
-    }
-
-    fn swizzle2_0xcc(a: Self, b: Self) -> Self {
-        unsafe {
-            let mask = _mm256_loadu_pd(mem::transmute([0i64, 0, -1, -1].as_ptr()));
-            let v1 = blend_256d(a.values[0], b.values[0], mask);
-            let v2 = blend_256d(a.values[1], b.values[1], mask);
-            Self { values: [v1, v2] }
-        }
-    }
-
-    fn swizzle2_0xf0(a: Self, b: Self) -> Self {
-        Self {
-            values: [a.values[0], b.values[1]],
-        }
-    }
+// This is safe since the avx2 feature is enabled and the compress
+// store writes one value per set lane
+#[cfg(target_feature = "avx2")]
+unsafe impl crate::SortBackend<f64, 8> for Avx2F64x2 {}
 
+impl Bit64Simd<f64> for Avx2F64x2 {
     fn shuffle1_1_1_1(a: Self) -> Self {
         unsafe {
             let v1 = _mm256_permute4x64_pd(a.values[0], SHUFFLE1_1_1_1);
@@ -337,7 +410,7 @@ mod test {
                 count += 1;
             }
         }
-        (M::try_from(result).unwrap(), new_values)
+        (M::from(result), new_values)
     }
 
     fn mask_fn(x: u8) -> Avx2F64x2 {
@@ -348,23 +421,24 @@ mod test {
     test_loadu_storeu!(f64, Avx2F64x2, into_array_f64);
     test_mask_loadu_mask_storeu!(f64, Avx2F64x2, into_array_f64);
     test_get_at_index!(f64, Avx2F64x2);
+    test_eq_or_mask!(f64, Avx2F64x2);
+    test_to_bitmask!(f64, Avx2F64x2);
+    test_and_mask_blend!(f64, Avx2F64x2);
     test_ge!(f64, Avx2F64x2, unsafe {
-        mem::transmute([0i64, -1, 0, -1, 0, -1, 0, -1])
+        mem::transmute::<[i64; 8], Avx2F64x2>([0i64, -1, 0, -1, 0, -1, 0, -1])
     });
     test_gather!(f64, Avx2F64x2, into_array_f64);
     test_not!(
         f64,
         Avx2F64x2,
-        unsafe { mem::transmute([0i64, -1, 0, -1, 0, -1, 0, 0]) },
-        unsafe { mem::transmute([-1i64, 0, -1, 0, -1, 0, -1, -1]) }
+        unsafe { mem::transmute::<[i64; 8], Avx2F64x2>([0i64, -1, 0, -1, 0, -1, 0, 0]) },
+        unsafe { mem::transmute::<[i64; 8], Avx2F64x2>([-1i64, 0, -1, 0, -1, 0, -1, -1]) }
     );
     test_count_ones!(f64, Avx2F64x2, mask_fn);
     test_reduce_min_max!(f64, Avx2F64x2);
     test_compress_store_u!(f64, Avx2F64x2, Avx2F64x2, generate_mask_answer);
     test_shuffle1_1_1_1!(f64, Avx2F64x2, into_array_f64);
-    test_swizzle2_0xaa!(f64, Avx2F64x2, into_array_f64);
-    test_swizzle2_0xcc!(f64, Avx2F64x2, into_array_f64);
-    test_swizzle2_0xf0!(f64, Avx2F64x2, into_array_f64);
+    test_mask_mov!(f64, Avx2F64x2, into_array_f64);
     network64bit1!(f64, Avx2F64x2, into_array_f64);
     network64bit2!(f64, Avx2F64x2, into_array_f64);
     network64bit3!(f64, Avx2F64x2, into_array_f64);
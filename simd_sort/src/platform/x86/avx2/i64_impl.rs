@@ -1,12 +1,13 @@
 use std::{
     arch::x86_64::{
-        __m256i, _mm256_blendv_pd, _mm256_broadcastq_epi64, _mm256_castpd_si256,
-        _mm256_castsi256_pd, _mm256_cmpeq_epi64, _mm256_cmpgt_epi64, _mm256_extracti128_si256,
-        _mm256_i64gather_epi64, _mm256_loadu_si256, _mm256_mask_i32gather_epi64,
-        _mm256_maskstore_epi64, _mm256_movemask_pd, _mm256_permute4x64_epi64,
-        _mm256_permutevar8x32_epi32, _mm256_setr_epi64x, _mm256_shuffle_pd, _mm256_storeu_si256,
-        _mm256_xor_si256, _mm_blendv_pd, _mm_castpd_si128, _mm_castsi128_pd, _mm_cmpgt_epi64,
-        _mm_extract_epi64, _mm_set1_epi64x, _mm_unpackhi_epi64, _mm_unpacklo_epi64,
+        __m128i, __m256i, _mm256_and_si256, _mm256_blendv_pd, _mm256_broadcastq_epi64,
+        _mm256_castpd_si256, _mm256_castsi256_pd, _mm256_cmpeq_epi64, _mm256_cmpgt_epi64,
+        _mm256_extracti128_si256, _mm256_i64gather_epi64, _mm256_loadu_si256,
+        _mm256_mask_i32gather_epi64, _mm256_maskstore_epi64, _mm256_movemask_pd, _mm256_or_si256,
+        _mm256_permute4x64_epi64, _mm256_permutevar8x32_epi32, _mm256_setr_epi64x,
+        _mm256_shuffle_pd, _mm256_storeu_si256, _mm256_xor_si256, _mm_blendv_pd, _mm_castpd_si128,
+        _mm_castsi128_pd, _mm_cmpgt_epi64, _mm_extract_epi64, _mm_set1_epi64x, _mm_unpackhi_epi64,
+        _mm_unpacklo_epi64,
     },
     mem, slice,
 };
@@ -40,6 +41,25 @@ fn shuffle_256i<const MASK: i32>(a: __m256i, b: __m256i) -> __m256i {
     }
 }
 
+// lanes selected by the low 4 bits of mask from b, the others from a
+#[inline]
+fn mask_mov_256i(a: __m256i, b: __m256i, mask: u64) -> __m256i {
+    match mask & 0xF {
+        0b0000 => a,
+        0b1010 => shuffle_256i::<0b1010>(a, b),
+        0b1111 => b,
+        m => unsafe {
+            let mask = _mm256_setr_epi64x(
+                -((m & 1) as i64),
+                -((m >> 1 & 1) as i64),
+                -((m >> 2 & 1) as i64),
+                -((m >> 3 & 1) as i64),
+            );
+            blend_256i(a, b, mask)
+        },
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Avx2I64x2 {
     values: [__m256i; 2],
@@ -81,9 +101,9 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
             let value1 = blend_256i(a.values[0], b.values[0], mask1);
             let mask2 = _mm256_cmpgt_epi64(a.values[1], b.values[1]);
             let value2 = blend_256i(a.values[1], b.values[1], mask2);
-            return Self {
+            Self {
                 values: [value1, value2],
-            };
+            }
         }
     }
 
@@ -94,9 +114,18 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
             let value1 = blend_256i(b.values[0], a.values[0], mask1);
             let mask2 = _mm256_cmpgt_epi64(a.values[1], b.values[1]);
             let value2 = blend_256i(b.values[1], a.values[1], mask2);
-            return Self {
+            Self {
                 values: [value1, value2],
-            };
+            }
+        }
+    }
+
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Self {
+            values: [
+                mask_mov_256i(a.values[0], b.values[0], MASK),
+                mask_mov_256i(a.values[1], b.values[1], MASK >> 4),
+            ],
         }
     }
 
@@ -104,8 +133,8 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
     fn loadu(data: &[i64]) -> Self {
         unsafe {
             let base_ptr = data.as_ptr();
-            let v1 = _mm256_loadu_si256(mem::transmute(base_ptr));
-            let v2 = _mm256_loadu_si256(mem::transmute(base_ptr.add(4)));
+            let v1 = _mm256_loadu_si256(base_ptr.cast());
+            let v2 = _mm256_loadu_si256(base_ptr.add(4).cast());
             Self { values: [v1, v2] }
         }
     }
@@ -114,8 +143,8 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
     fn storeu(input: Self, data: &mut [i64]) {
         unsafe {
             let base_ptr = data.as_mut_ptr();
-            _mm256_storeu_si256(mem::transmute(base_ptr), input.values[0]);
-            _mm256_storeu_si256(mem::transmute(base_ptr.add(4)), input.values[1]);
+            _mm256_storeu_si256(base_ptr.cast(), input.values[0]);
+            _mm256_storeu_si256(base_ptr.add(4).cast(), input.values[1]);
         }
     }
 
@@ -123,10 +152,10 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
     fn mask_loadu(data: &[i64]) -> Self {
         unsafe {
             let mask = LOADU_MASK.get_unchecked(data.len()).as_ptr();
-            let mask1 = _mm256_loadu_si256(mem::transmute(mask));
-            let mask2 = _mm256_loadu_si256(mem::transmute(mask.add(4)));
-            let indices1 = mem::transmute(V_INDEX_1);
-            let indices2 = mem::transmute(V_INDEX_2);
+            let mask1 = _mm256_loadu_si256(mask.cast());
+            let mask2 = _mm256_loadu_si256(mask.add(4).cast());
+            let indices1 = mem::transmute::<[i32; 4], __m128i>(V_INDEX_1);
+            let indices2 = mem::transmute::<[i32; 4], __m128i>(V_INDEX_2);
             let max_values = _mm256_broadcastq_epi64(_mm_set1_epi64x(i64::MAX));
             let base_ptr = data.as_ptr();
             let v1 = _mm256_mask_i32gather_epi64(max_values, base_ptr, indices1, mask1, 8);
@@ -139,8 +168,8 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
     fn mask_storeu(input: Self, data: &mut [i64]) {
         unsafe {
             let mask = LOADU_MASK.get_unchecked(data.len()).as_ptr();
-            let mask1 = _mm256_loadu_si256(mem::transmute(mask));
-            let mask2 = _mm256_loadu_si256(mem::transmute(mask.add(4)));
+            let mask1 = _mm256_loadu_si256(mask.cast());
+            let mask2 = _mm256_loadu_si256(mask.add(4).cast());
             let base_ptr = data.as_mut_ptr();
             _mm256_maskstore_epi64(base_ptr, mask1, input.values[0]);
             //let split_index = cmp::min(data.len(), 4);
@@ -152,9 +181,9 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
     #[inline]
     fn gather_from_idx(idx: [usize; 8], data: &[i64]) -> Self {
         unsafe {
-            let idx_1 = _mm256_loadu_si256(mem::transmute(idx.as_ptr()));
+            let idx_1 = _mm256_loadu_si256(idx.as_ptr().cast());
             let v1 = _mm256_i64gather_epi64(data.as_ptr(), idx_1, 8);
-            let idx_2 = _mm256_loadu_si256(mem::transmute(idx[4..].as_ptr()));
+            let idx_2 = _mm256_loadu_si256(idx[4..].as_ptr().cast());
             let v2 = _mm256_i64gather_epi64(data.as_ptr(), idx_2, 8);
             Self { values: [v1, v2] }
         }
@@ -187,9 +216,21 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
             let mask1 = _mm256_xor_si256(gt_mask1, eq_mask1);
             let mask2 = _mm256_xor_si256(gt_mask2, eq_mask2);
 
-            return Self {
+            Self {
                 values: [mask1, mask2],
-            };
+            }
+        }
+    }
+
+    #[inline]
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm256_cmpeq_epi64(a.values[0], b.values[0]),
+                    _mm256_cmpeq_epi64(a.values[1], b.values[1]),
+                ],
+            }
         }
     }
 
@@ -202,6 +243,15 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
         }
     }
 
+    #[inline]
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        unsafe {
+            let low = _mm256_movemask_pd(_mm256_castsi256_pd(mask.values[0]));
+            let high = _mm256_movemask_pd(_mm256_castsi256_pd(mask.values[1]));
+            (low | high << 4) as u64
+        }
+    }
+
     #[inline]
     fn not_mask(mask: Self::OPMask) -> Self::OPMask {
         unsafe {
@@ -216,6 +266,40 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
         }
     }
 
+    #[inline]
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm256_or_si256(a.values[0], b.values[0]),
+                    _mm256_or_si256(a.values[1], b.values[1]),
+                ],
+            }
+        }
+    }
+
+    #[inline]
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm256_and_si256(a.values[0], b.values[0]),
+                    _mm256_and_si256(a.values[1], b.values[1]),
+                ],
+            }
+        }
+    }
+
+    #[inline]
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        Self {
+            values: [
+                blend_256i(a.values[0], b.values[0], mask.values[0]),
+                blend_256i(a.values[1], b.values[1], mask.values[1]),
+            ],
+        }
+    }
+
     #[inline]
     fn reducemin(x: Self) -> i64 {
         unsafe {
@@ -272,29 +356,37 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
         unsafe {
             let base_ptr = array.as_mut_ptr();
             let bitmask1 = _mm256_movemask_pd(_mm256_castsi256_pd(mask.values[0])) as usize;
-            let mask1 = _mm256_loadu_si256(mem::transmute(
-                COMPRESS_MASK
+            let mask1 = _mm256_loadu_si256(
+                (COMPRESS_MASK
                     .get_unchecked(bitmask1.count_ones() as usize)
-                    .as_ptr(),
-            ));
+                    .as_ptr())
+                .cast(),
+            );
             let v1 = _mm256_permutevar8x32_epi32(
                 data.values[0],
-                _mm256_loadu_si256(mem::transmute(
-                    COMPRESS_PERMUTATIONS.get_unchecked(bitmask1).as_ptr(),
-                )),
+                _mm256_loadu_si256(
+                    COMPRESS_PERMUTATIONS
+                        .get_unchecked(bitmask1)
+                        .as_ptr()
+                        .cast(),
+                ),
             );
             _mm256_maskstore_epi64(base_ptr, mask1, v1);
             let bitmask2 = _mm256_movemask_pd(_mm256_castsi256_pd(mask.values[1])) as usize;
-            let mask2 = _mm256_loadu_si256(mem::transmute(
-                COMPRESS_MASK
+            let mask2 = _mm256_loadu_si256(
+                (COMPRESS_MASK
                     .get_unchecked(bitmask2.count_ones() as usize)
-                    .as_ptr(),
-            ));
+                    .as_ptr())
+                .cast(),
+            );
             let v2 = _mm256_permutevar8x32_epi32(
                 data.values[1],
-                _mm256_loadu_si256(mem::transmute(
-                    COMPRESS_PERMUTATIONS.get_unchecked(bitmask2).as_ptr(),
-                )),
+                _mm256_loadu_si256(
+                    COMPRESS_PERMUTATIONS
+                        .get_unchecked(bitmask2)
+                        .as_ptr()
+                        .cast(),
+                ),
             );
             _mm256_maskstore_epi64(base_ptr.offset(bitmask1.count_ones() as isize), mask2, v2);
         }
@@ -303,32 +395,16 @@ impl SimdCompare<i64, 8> for Avx2I64x2 {
 
 impl Avx2I64x2 {
     fn as_slice(&self) -> &[i64] {
-        unsafe { slice::from_raw_parts(mem::transmute(self.values.as_ptr()), 8) }
+        unsafe { slice::from_raw_parts(self.values.as_ptr().cast(), 8) }
     }
 }
 
-impl Bit64Simd<i64> for Avx2I64x2 {
-    fn swizzle2_0xaa(a: Self, b: Self) -> Self {
-        let v1 = shuffle_256i::<0b1010>(a.values[0], b.values[0]);
-        let v2 = shuffle_256i::<0b1010>(a.values[1], b.values[1]);
-        Self { values: [v1, v2] }
-    }
-
-    fn swizzle2_0xcc(a: Self, b: Self) -> Self {
-        unsafe {
-            let mask = _mm256_loadu_si256(mem::transmute([0i64, 0, -1, -1].as_ptr()));
-            let v1 = blend_256i(a.values[0], b.values[0], mask);
-            let v2 = blend_256i(a.values[1], b.values[1], mask);
-            Self { values: [v1, v2] }
-        }
-    }
-
-    fn swizzle2_0xf0(a: Self, b: Self) -> Self {
-        Self {
-            values: [a.values[0], b.values[1]],
-        }
-    }
+// This is safe since the avx2 feature is enabled and the compress
+// store writes one value per set lane
+#[cfg(target_feature = "avx2")]
+unsafe impl crate::SortBackend<i64, 8> for Avx2I64x2 {}
 
+impl Bit64Simd<i64> for Avx2I64x2 {
     fn shuffle1_1_1_1(a: Self) -> Self {
         unsafe {
             let v1 = _mm256_permute4x64_epi64(a.values[0], SHUFFLE1_1_1_1);
@@ -399,7 +475,7 @@ mod test {
                 count += 1;
             }
         }
-        (M::try_from(result).unwrap(), new_values)
+        (M::from(result), new_values)
     }
 
     fn mask_fn(x: u8) -> Avx2I64x2 {
@@ -410,6 +486,9 @@ mod test {
     test_loadu_storeu!(i64, Avx2I64x2, into_array_i64);
     test_mask_loadu_mask_storeu!(i64, Avx2I64x2, into_array_i64);
     test_get_at_index!(i64, Avx2I64x2);
+    test_eq_or_mask!(i64, Avx2I64x2);
+    test_to_bitmask!(i64, Avx2I64x2);
+    test_and_mask_blend!(i64, Avx2I64x2);
     test_ge!(
         i64,
         Avx2I64x2,
@@ -426,9 +505,7 @@ mod test {
     test_reduce_min_max!(i64, Avx2I64x2);
     test_compress_store_u!(i64, Avx2I64x2, Avx2I64x2, generate_mask_answer);
     test_shuffle1_1_1_1!(i64, Avx2I64x2, into_array_i64);
-    test_swizzle2_0xaa!(i64, Avx2I64x2, into_array_i64);
-    test_swizzle2_0xcc!(i64, Avx2I64x2, into_array_i64);
-    test_swizzle2_0xf0!(i64, Avx2I64x2, into_array_i64);
+    test_mask_mov!(i64, Avx2I64x2, into_array_i64);
     network64bit1!(i64, Avx2I64x2, into_array_i64);
     network64bit2!(i64, Avx2I64x2, into_array_i64);
     network64bit3!(i64, Avx2I64x2, into_array_i64);
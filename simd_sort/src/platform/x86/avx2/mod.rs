@@ -8,11 +8,11 @@ use self::f64_impl::Avx2F64x2;
 use self::i64_impl::Avx2I64x2;
 
 pub fn avx2_sort_i64(data: &mut [i64]) {
-    qsort_64bit_::<i64, Avx2I64x2>(data, f64::log2(data.len() as f64) as i64)
+    qsort_64bit_::<i64, 8, Avx2I64x2>(data, f64::log2(data.len() as f64) as i64)
 }
 
 pub fn avx2_sort_f64(data: &mut [f64]) {
-    qsort_64bit_::<f64, Avx2F64x2>(data, f64::log2(data.len() as f64) as i64)
+    qsort_64bit_::<f64, 8, Avx2F64x2>(data, f64::log2(data.len() as f64) as i64)
 }
 
 #[cfg(test)]
@@ -21,19 +21,33 @@ mod test {
     use super::*;
     use crate::bit_64::{test::*, *};
 
-    test_sort_n!(i64, Avx2I64x2, 8);
-    test_sort_n!(i64, Avx2I64x2, 16);
-    test_sort_n!(i64, Avx2I64x2, 32);
-    test_sort_n!(i64, Avx2I64x2, 64);
-    test_sort_n!(i64, Avx2I64x2, 128);
-    test_sort_n!(i64, Avx2I64x2, 256);
+    test_sort_n!(i64, Avx2I64x2, 8, 8);
+    test_sort_n!(i64, Avx2I64x2, 8, 16);
+    test_sort_n!(i64, Avx2I64x2, 8, 32);
+    test_sort_n!(i64, Avx2I64x2, 8, 64);
+    test_sort_n!(i64, Avx2I64x2, 8, 128);
+    test_sort_n!(i64, Avx2I64x2, 8, 256);
     test_sort_e2e!(i64, Avx2I64x2, avx2_sort_i64);
+    test_dedup_sorted!(i64, Avx2I64x2, 8);
+    test_count_runs!(i64, Avx2I64x2, 8);
+    test_set_operations!(i64, Avx2I64x2, 8);
+    test_lower_bound_batch!(i64, Avx2I64x2, 8);
+    test_minmax_slice!(i64, Avx2I64x2, 8);
+    test_select_ranks!(i64, Avx2I64x2, 8);
+    test_partition!(i64, Avx2I64x2, 8);
 
-    test_sort_n!(f64, Avx2F64x2, 8);
-    test_sort_n!(f64, Avx2F64x2, 16);
-    test_sort_n!(f64, Avx2F64x2, 32);
-    test_sort_n!(f64, Avx2F64x2, 64);
-    test_sort_n!(f64, Avx2F64x2, 128);
-    test_sort_n!(f64, Avx2F64x2, 256);
+    test_sort_n!(f64, Avx2F64x2, 8, 8);
+    test_sort_n!(f64, Avx2F64x2, 8, 16);
+    test_sort_n!(f64, Avx2F64x2, 8, 32);
+    test_sort_n!(f64, Avx2F64x2, 8, 64);
+    test_sort_n!(f64, Avx2F64x2, 8, 128);
+    test_sort_n!(f64, Avx2F64x2, 8, 256);
     test_sort_e2e!(f64, Avx2F64x2, avx2_sort_f64);
+    test_dedup_sorted!(f64, Avx2F64x2, 8);
+    test_count_runs!(f64, Avx2F64x2, 8);
+    test_set_operations!(f64, Avx2F64x2, 8);
+    test_lower_bound_batch!(f64, Avx2F64x2, 8);
+    test_minmax_slice!(f64, Avx2F64x2, 8);
+    test_select_ranks!(f64, Avx2F64x2, 8);
+    test_partition!(f64, Avx2F64x2, 8);
 }
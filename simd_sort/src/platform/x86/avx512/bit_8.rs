@@ -0,0 +1,143 @@
+use std::arch::x86_64::{
+    __m128i, __m512i, __mmask16, _mm512_add_epi32, _mm512_cvtepu8_epi32, _mm512_loadu_si512,
+    _mm512_mask_i32gather_epi32, _mm512_mask_i32scatter_epi32, _mm512_or_si512,
+    _mm512_reduce_add_epi32, _mm512_set1_epi32, _mm512_set1_epi8, _mm512_set_epi32,
+    _mm512_setzero_si512, _mm512_slli_epi32, _mm512_storeu_si512, _mm_loadu_si128,
+};
+use std::ptr;
+
+/// Bytes counted by one gather and scatter, one per 32-bit lane
+const LANES: usize = 16;
+
+/// Counters of a table, one per byte and lane
+const TABLE_LEN: usize = 256 * LANES;
+
+/// Tables counted into in turn, so that a register does not wait for the
+/// scatter of the one before when they share bytes
+const TABLES: usize = 2;
+
+/// Bytes stored by one register when filling the runs
+const REGISTER_BYTES: usize = 64;
+
+/// Bytes counted before the tables are added up, so that their sum fits the
+/// 32-bit lanes of the reduction
+const BLOCK: usize = 1 << 31;
+
+/// Counts how often every byte occurs in `data`.
+///
+/// Every lane counts into its own table entries, at `byte * LANES + lane`,
+/// so the bytes of a register never share a counter and are counted with a
+/// gather, an add and a scatter, without detecting conflicts. The counters
+/// of a byte are adjacent and added up with one reduction.
+#[inline(always)]
+pub fn histogram(data: &[u8]) -> [usize; 256] {
+    let mut counts = [0usize; 256];
+    let mut tables = [[0u32; TABLE_LEN]; TABLES];
+    for block in data.chunks(BLOCK) {
+        let mut steps = block.chunks_exact(TABLES * LANES);
+        for step in &mut steps {
+            for (table, bytes) in tables.iter_mut().zip(step.chunks_exact(LANES)) {
+                count_register(table, bytes.try_into().unwrap(), u16::MAX);
+            }
+        }
+        for remainder in steps.remainder().chunks(LANES) {
+            let mut bytes = [0; LANES];
+            bytes[..remainder.len()].copy_from_slice(remainder);
+            count_register(
+                &mut tables[0],
+                &bytes,
+                u16::MAX >> (LANES - remainder.len()),
+            );
+        }
+
+        let [first, second] = &mut tables;
+        for (byte, count) in counts.iter_mut().enumerate() {
+            let range = byte * LANES..(byte + 1) * LANES;
+            unsafe {
+                let sum = _mm512_add_epi32(
+                    _mm512_loadu_si512(first[range.clone()].as_ptr() as *const __m512i),
+                    _mm512_loadu_si512(second[range].as_ptr() as *const __m512i),
+                );
+                *count += _mm512_reduce_add_epi32(sum) as u32 as usize;
+            }
+        }
+        *first = [0; TABLE_LEN];
+        *second = [0; TABLE_LEN];
+    }
+    counts
+}
+
+/// Adds the bytes selected by `mask` to the counters of their lane in `table`
+#[inline(always)]
+fn count_register(table: &mut [u32; TABLE_LEN], bytes: &[u8; LANES], mask: __mmask16) {
+    unsafe {
+        let lanes = _mm512_set_epi32(15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0);
+        let bytes = _mm512_cvtepu8_epi32(_mm_loadu_si128(bytes.as_ptr() as *const __m128i));
+        let indices = _mm512_or_si512(_mm512_slli_epi32::<4>(bytes), lanes);
+        let counters = _mm512_mask_i32gather_epi32::<4>(
+            _mm512_setzero_si512(),
+            mask,
+            indices,
+            table.as_ptr() as *const i32,
+        );
+        _mm512_mask_i32scatter_epi32::<4>(
+            table.as_mut_ptr() as *mut i32,
+            mask,
+            indices,
+            _mm512_add_epi32(counters, _mm512_set1_epi32(1)),
+        );
+    }
+}
+
+/// Writes `counts[byte]` copies of every byte to `data`, ordered by
+/// `byte ^ bias`.
+///
+/// Every run is written with whole registers from its start, and a register
+/// reaching past the run is overwritten by the runs after it. The registers
+/// that would reach past the end of `data` are stored to a copy of its last
+/// [`REGISTER_BYTES`] bytes instead, which is copied back at the end.
+///
+/// # Safety
+///
+/// The counts must add up to `data.len()`.
+#[inline(always)]
+pub unsafe fn fill_runs(data: &mut [u8], counts: &[usize; 256], bias: u8) {
+    let len = data.len();
+    let dst = data.as_mut_ptr();
+    let tail_start = len.saturating_sub(REGISTER_BYTES);
+    let mut tail = [0u8; 2 * REGISTER_BYTES];
+    let mut in_tail = false;
+
+    let mut start = 0;
+    for key in 0..=u8::MAX {
+        let byte = key ^ bias;
+        let end = start + counts[byte as usize];
+        let value = _mm512_set1_epi8(byte as i8);
+        // the stores start in ascending order, so once one is stored to the
+        // tail all the following ones are
+        while start < end {
+            if start + REGISTER_BYTES <= len {
+                _mm512_storeu_si512(dst.add(start) as *mut __m512i, value);
+            } else {
+                if !in_tail {
+                    ptr::copy_nonoverlapping(
+                        dst.add(tail_start),
+                        tail.as_mut_ptr(),
+                        len - tail_start,
+                    );
+                    in_tail = true;
+                }
+                _mm512_storeu_si512(
+                    tail.as_mut_ptr().add(start - tail_start) as *mut __m512i,
+                    value,
+                );
+            }
+            start += REGISTER_BYTES;
+        }
+        start = end;
+    }
+
+    if in_tail {
+        ptr::copy_nonoverlapping(tail.as_ptr(), dst.add(tail_start), len - tail_start);
+    }
+}
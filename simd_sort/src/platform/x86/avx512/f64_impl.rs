@@ -4,7 +4,7 @@ use std::{
         _mm512_mask_compressstoreu_pd, _mm512_mask_loadu_pd, _mm512_mask_mov_pd,
         _mm512_mask_storeu_pd, _mm512_max_pd, _mm512_min_pd, _mm512_permutexvar_pd,
         _mm512_reduce_max_pd, _mm512_reduce_min_pd, _mm512_set1_pd, _mm512_shuffle_pd,
-        _mm512_storeu_pd, _CMP_GE_OQ, _MM_PERM_ENUM,
+        _mm512_storeu_pd, _CMP_EQ_OQ, _CMP_GE_OQ, _MM_PERM_ENUM,
     },
     mem::transmute,
     slice::from_raw_parts,
@@ -14,150 +14,207 @@ use crate::{bit_64::Bit64Simd, SimdCompare};
 
 use super::bit_64::{
     network64bit1_idx, network64bit2_idx, network64bit3_idx, network64bit4_idx, SHUFFLE1_1_1_1,
-    SHUFFLE2_0XAA_MASK, SHUFFLE2_0XCC_MASK, SHUFFLE2_0XF0_MASK,
 };
 
+#[inline(always)]
 fn permutexvar_m512d(idx: __m512i, a: __m512d) -> __m512d {
     unsafe { _mm512_permutexvar_pd(idx, a) }
 }
 
+#[inline(always)]
 fn shuffle_m512d<const MASK: _MM_PERM_ENUM>(zmm: __m512d) -> __m512d {
     unsafe { _mm512_shuffle_pd::<MASK>(zmm, zmm) }
 }
 
-impl SimdCompare<f64, 8> for __m512d {
+#[derive(Debug, Copy, Clone)]
+pub struct Avx512F64x1(__m512d);
+
+impl SimdCompare<f64, 8> for Avx512F64x1 {
     type OPMask = __mmask8;
 
+    #[inline(always)]
     fn min(a: Self, b: Self) -> Self {
-        unsafe { _mm512_min_pd(a, b) }
+        unsafe { Self(_mm512_min_pd(a.0, b.0)) }
     }
 
+    #[inline(always)]
     fn max(a: Self, b: Self) -> Self {
-        unsafe { _mm512_max_pd(a, b) }
+        unsafe { Self(_mm512_max_pd(a.0, b.0)) }
+    }
+
+    #[inline(always)]
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        unsafe { Self(_mm512_mask_mov_pd(a.0, MASK as __mmask8, b.0)) }
     }
 
+    #[inline(always)]
     fn loadu(data: &[f64]) -> Self {
-        unsafe { _mm512_loadu_pd(transmute(data.as_ptr())) }
+        unsafe { Self(_mm512_loadu_pd(data.as_ptr() as *const _)) }
     }
 
+    #[inline(always)]
     fn storeu(input: Self, data: &mut [f64]) {
-        unsafe { _mm512_storeu_pd(transmute(data.as_ptr()), input) }
+        unsafe { _mm512_storeu_pd(data.as_mut_ptr() as *mut _, input.0) }
     }
 
+    #[inline(always)]
     fn mask_loadu(data: &[f64]) -> Self {
         unsafe {
             let k = (1i32.overflowing_shl(data.len() as u32).0) - 1;
             let max_zmm = Self::set(f64::MAX);
-            _mm512_mask_loadu_pd(max_zmm, k as u8, transmute(data.as_ptr()))
+            Self(_mm512_mask_loadu_pd(
+                max_zmm.0,
+                k as u8,
+                data.as_ptr() as *const _,
+            ))
         }
     }
 
+    #[inline(always)]
     fn mask_storeu(input: Self, data: &mut [f64]) {
         unsafe {
             let k = (1i32.overflowing_shl(data.len() as u32).0) - 1;
-            _mm512_mask_storeu_pd(transmute(data.as_ptr()), k as u8, input);
+            _mm512_mask_storeu_pd(data.as_mut_ptr() as *mut _, k as u8, input.0);
         }
     }
 
+    #[inline(always)]
     fn gather_from_idx(idx: [usize; 8], data: &[f64]) -> Self {
-        unsafe { _mm512_i64gather_pd(transmute(idx), transmute(data.as_ptr()), 8) }
+        unsafe {
+            Self(_mm512_i64gather_pd(
+                transmute::<[usize; 8], __m512i>(idx),
+                data.as_ptr() as *const _,
+                8,
+            ))
+        }
     }
 
+    #[inline(always)]
     fn get_value_at_idx(input: Self, idx: usize) -> f64 {
-        unsafe { *from_raw_parts(transmute(&input), 8).get_unchecked(idx) }
+        unsafe { *from_raw_parts(&input.0 as *const _ as *const f64, 8).get_unchecked(idx) }
     }
 
+    #[inline(always)]
     fn set(value: f64) -> Self {
-        unsafe { _mm512_set1_pd(value) }
+        unsafe { Self(_mm512_set1_pd(value)) }
     }
 
+    #[inline(always)]
     fn ge(a: Self, b: Self) -> Self::OPMask {
-        unsafe { _mm512_cmp_pd_mask(a, b, _CMP_GE_OQ) }
+        unsafe { _mm512_cmp_pd_mask(a.0, b.0, _CMP_GE_OQ) }
     }
 
+    #[inline(always)]
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        unsafe { _mm512_cmp_pd_mask(a.0, b.0, _CMP_EQ_OQ) }
+    }
+
+    #[inline(always)]
     fn ones_count(mask: Self::OPMask) -> usize {
         mask.count_ones() as usize
     }
 
+    #[inline(always)]
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        mask as u64
+    }
+
+    #[inline(always)]
     fn not_mask(mask: Self::OPMask) -> Self::OPMask {
         !mask
     }
 
-    fn reducemin(x: Self) -> f64 {
-        unsafe { _mm512_reduce_min_pd(x) }
+    #[inline(always)]
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a | b
     }
 
-    fn reducemax(x: Self) -> f64 {
-        unsafe { _mm512_reduce_max_pd(x) }
+    #[inline(always)]
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        a & b
     }
 
-    fn mask_compressstoreu(array: &mut [f64], mask: Self::OPMask, data: Self) {
-        unsafe { _mm512_mask_compressstoreu_pd(transmute(array.as_ptr()), mask, data) }
+    #[inline(always)]
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        unsafe { Self(_mm512_mask_mov_pd(a.0, mask, b.0)) }
     }
-}
 
-impl Bit64Simd<f64> for __m512d {
-    fn swizzle2_0xaa(a: Self, b: Self) -> Self {
-        unsafe { _mm512_mask_mov_pd(a, SHUFFLE2_0XAA_MASK, b) }
+    #[inline(always)]
+    fn reducemin(x: Self) -> f64 {
+        unsafe { _mm512_reduce_min_pd(x.0) }
     }
 
-    fn swizzle2_0xcc(a: Self, b: Self) -> Self {
-        unsafe { _mm512_mask_mov_pd(a, SHUFFLE2_0XCC_MASK, b) }
+    #[inline(always)]
+    fn reducemax(x: Self) -> f64 {
+        unsafe { _mm512_reduce_max_pd(x.0) }
     }
 
-    fn swizzle2_0xf0(a: Self, b: Self) -> Self {
-        unsafe { _mm512_mask_mov_pd(a, SHUFFLE2_0XF0_MASK, b) }
+    #[inline(always)]
+    fn mask_compressstoreu(array: &mut [f64], mask: Self::OPMask, data: Self) {
+        unsafe { _mm512_mask_compressstoreu_pd(array.as_mut_ptr() as *mut _, mask, data.0) }
     }
+}
 
+impl Bit64Simd<f64> for Avx512F64x1 {
+    #[inline(always)]
     fn shuffle1_1_1_1(a: Self) -> Self {
-        shuffle_m512d::<SHUFFLE1_1_1_1>(a)
+        Self(shuffle_m512d::<SHUFFLE1_1_1_1>(a.0))
     }
 
+    #[inline(always)]
     fn network64bit1(a: Self) -> Self {
-        permutexvar_m512d(network64bit1_idx(), a)
+        Self(permutexvar_m512d(network64bit1_idx(), a.0))
     }
 
+    #[inline(always)]
     fn network64bit2(a: Self) -> Self {
-        permutexvar_m512d(network64bit2_idx(), a)
+        Self(permutexvar_m512d(network64bit2_idx(), a.0))
     }
 
+    #[inline(always)]
     fn network64bit3(a: Self) -> Self {
-        permutexvar_m512d(network64bit3_idx(), a)
+        Self(permutexvar_m512d(network64bit3_idx(), a.0))
     }
 
+    #[inline(always)]
     fn network64bit4(a: Self) -> Self {
-        permutexvar_m512d(network64bit4_idx(), a)
+        Self(permutexvar_m512d(network64bit4_idx(), a.0))
     }
 }
 
 #[cfg(test)]
-#[cfg(target_feature = "avx512f")]
 pub mod test {
+    use crate::platform::x86::avx512::bit_64::test::cpu_supports_backend;
     use crate::{bit_64::test::*, platform::x86::avx512::bit_64::test::*};
     use std::slice::from_raw_parts;
 
     use super::*;
 
-    fn into_array_f64(x: __m512d) -> [f64; 8] {
-        unsafe { from_raw_parts(transmute(&x), 8).try_into().unwrap() }
-    }
-
-    test_min_max!(f64, __m512d, into_array_f64);
-    test_loadu_storeu!(f64, __m512d, into_array_f64);
-    test_mask_loadu_mask_storeu!(f64, __m512d, into_array_f64);
-    test_get_at_index!(f64, __m512d);
-    test_ge!(f64, __m512d, 0b10101010);
-    test_gather!(f64, __m512d, into_array_f64);
-    test_not!(f64, __m512d, 0b10101010, !0b10101010);
-    test_count_ones!(f64, __m512d, mask_fn);
-    test_reduce_min_max!(f64, __m512d);
-    test_compress_store_u!(f64, __m512d, u8, generate_mask_answer);
-    test_shuffle1_1_1_1!(f64, __m512d, into_array_f64);
-    test_swizzle2_0xaa!(f64, __m512d, into_array_f64);
-    test_swizzle2_0xcc!(f64, __m512d, into_array_f64);
-    test_swizzle2_0xf0!(f64, __m512d, into_array_f64);
-    network64bit1!(f64, __m512d, into_array_f64);
-    network64bit2!(f64, __m512d, into_array_f64);
-    network64bit3!(f64, __m512d, into_array_f64);
-    network64bit4!(f64, __m512d, into_array_f64);
+    fn into_array_f64(x: Avx512F64x1) -> [f64; 8] {
+        unsafe {
+            from_raw_parts(&x.0 as *const _ as *const f64, 8)
+                .try_into()
+                .unwrap()
+        }
+    }
+
+    test_min_max!(f64, Avx512F64x1, into_array_f64);
+    test_loadu_storeu!(f64, Avx512F64x1, into_array_f64);
+    test_mask_loadu_mask_storeu!(f64, Avx512F64x1, into_array_f64);
+    test_get_at_index!(f64, Avx512F64x1);
+    test_eq_or_mask!(f64, Avx512F64x1);
+    test_to_bitmask!(f64, Avx512F64x1);
+    test_and_mask_blend!(f64, Avx512F64x1);
+    test_ge!(f64, Avx512F64x1, 0b10101010);
+    test_gather!(f64, Avx512F64x1, into_array_f64);
+    test_not!(f64, Avx512F64x1, 0b10101010, !0b10101010);
+    test_count_ones!(f64, Avx512F64x1, mask_fn);
+    test_reduce_min_max!(f64, Avx512F64x1);
+    test_compress_store_u!(f64, Avx512F64x1, u8, generate_mask_answer);
+    test_shuffle1_1_1_1!(f64, Avx512F64x1, into_array_f64);
+    test_mask_mov!(f64, Avx512F64x1, into_array_f64);
+    network64bit1!(f64, Avx512F64x1, into_array_f64);
+    network64bit2!(f64, Avx512F64x1, into_array_f64);
+    network64bit3!(f64, Avx512F64x1, into_array_f64);
+    network64bit4!(f64, Avx512F64x1, into_array_f64);
 }
@@ -1,52 +1,277 @@
 pub(crate) mod bit_64;
+pub(crate) mod bit_8;
 pub(crate) mod f64_impl;
 pub(crate) mod i64_impl;
 pub(crate) mod u64_impl;
 
-use std::arch::x86_64::{__m512d, __m512i};
+// The register types run avx512f instructions from safe methods, so they are
+// kept private to the crate and only used by the `target_feature` functions below.
+use f64_impl::Avx512F64x1;
+use i64_impl::Avx512I64x1;
+use u64_impl::Avx512U64x1;
 
-use crate::bit_64::qsort_64bit_;
+use crate::bit_128::qsort_128bit_;
+use crate::bit_64::{
+    bitonic_sort, count_runs, dedup_sorted, lower_bound_batch, minmax, partition_64bit_,
+    qsort_64bit_, select_ranks_64bit_, set_operation,
+};
 
+use crate::platform::SetOperation;
+
+/// Sorts `data` with the avx512 backend.
+///
+/// # Panics
+///
+/// Panics if the cpu does not support `avx512f`.
 pub fn avx512_sort_i64(data: &mut [i64]) {
-    qsort_64bit_::<i64, __m512i>(data, f64::log2(data.len() as f64) as i64)
+    assert!(is_x86_feature_detected!("avx512f"));
+    // This is safe since we have verified the cpu supports avx512f
+    unsafe { qsort_i64(data) }
 }
 
+/// Sorts `data` with the avx512 backend.
+///
+/// # Panics
+///
+/// Panics if the cpu does not support `avx512f`.
 pub fn avx512_sort_u64(data: &mut [u64]) {
-    qsort_64bit_::<u64, __m512i>(data, f64::log2(data.len() as f64) as i64)
+    assert!(is_x86_feature_detected!("avx512f"));
+    // This is safe since we have verified the cpu supports avx512f
+    unsafe { qsort_u64(data) }
 }
 
+/// Sorts `data` with the avx512 backend.
+///
+/// # Panics
+///
+/// Panics if the cpu does not support `avx512f`.
 pub fn avx512_sort_f64(data: &mut [f64]) {
-    qsort_64bit_::<f64, __m512d>(data, f64::log2(data.len() as f64) as i64)
+    assert!(is_x86_feature_detected!("avx512f"));
+    // This is safe since we have verified the cpu supports avx512f
+    unsafe { qsort_f64(data) }
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn qsort_i64(data: &mut [i64]) {
+    qsort_64bit_::<i64, 8, Avx512I64x1>(data, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn qsort_u64(data: &mut [u64]) {
+    qsort_64bit_::<u64, 8, Avx512U64x1>(data, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn qsort_f64(data: &mut [f64]) {
+    qsort_64bit_::<f64, 8, Avx512F64x1>(data, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn small_sort_i64(data: &mut [i64]) {
+    bitonic_sort::<i64, 8, Avx512I64x1>(data)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn small_sort_u64(data: &mut [u64]) {
+    bitonic_sort::<u64, 8, Avx512U64x1>(data)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn small_sort_f64(data: &mut [f64]) {
+    bitonic_sort::<f64, 8, Avx512F64x1>(data)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn dedup_sorted_i64(data: &mut [i64]) -> usize {
+    dedup_sorted::<i64, 8, Avx512I64x1>(data)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn dedup_sorted_u64(data: &mut [u64]) -> usize {
+    dedup_sorted::<u64, 8, Avx512U64x1>(data)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn dedup_sorted_f64(data: &mut [f64]) -> usize {
+    dedup_sorted::<f64, 8, Avx512F64x1>(data)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn count_runs_i64(data: &[i64], counts: &mut Vec<(i64, usize)>) {
+    count_runs::<i64, 8, Avx512I64x1>(data, counts)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn count_runs_u64(data: &[u64], counts: &mut Vec<(u64, usize)>) {
+    count_runs::<u64, 8, Avx512U64x1>(data, counts)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn count_runs_f64(data: &[f64], counts: &mut Vec<(f64, usize)>) {
+    count_runs::<f64, 8, Avx512F64x1>(data, counts)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn set_operation_i64(op: SetOperation, a: &[i64], b: &[i64]) -> Vec<i64> {
+    set_operation::<i64, 8, Avx512I64x1>(op, a, b)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn set_operation_u64(op: SetOperation, a: &[u64], b: &[u64]) -> Vec<u64> {
+    set_operation::<u64, 8, Avx512U64x1>(op, a, b)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn set_operation_f64(op: SetOperation, a: &[f64], b: &[f64]) -> Vec<f64> {
+    set_operation::<f64, 8, Avx512F64x1>(op, a, b)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn lower_bound_batch_i64(sorted: &[i64], probes: &[i64], out: &mut [usize]) {
+    lower_bound_batch::<i64, 8, Avx512I64x1>(sorted, probes, out)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn lower_bound_batch_u64(sorted: &[u64], probes: &[u64], out: &mut [usize]) {
+    lower_bound_batch::<u64, 8, Avx512U64x1>(sorted, probes, out)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn lower_bound_batch_f64(sorted: &[f64], probes: &[f64], out: &mut [usize]) {
+    lower_bound_batch::<f64, 8, Avx512F64x1>(sorted, probes, out)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn minmax_i64(data: &[i64], propagate_nan: bool) -> Option<(i64, i64)> {
+    minmax::<i64, 8, Avx512I64x1>(data, propagate_nan)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn minmax_u64(data: &[u64], propagate_nan: bool) -> Option<(u64, u64)> {
+    minmax::<u64, 8, Avx512U64x1>(data, propagate_nan)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn minmax_f64(data: &[f64], propagate_nan: bool) -> Option<(f64, f64)> {
+    minmax::<f64, 8, Avx512F64x1>(data, propagate_nan)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn select_ranks_i64(data: &mut [i64], ranks: &[usize]) {
+    select_ranks_64bit_::<i64, 8, Avx512I64x1>(data, ranks, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn select_ranks_u64(data: &mut [u64], ranks: &[usize]) {
+    select_ranks_64bit_::<u64, 8, Avx512U64x1>(data, ranks, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn select_ranks_f64(data: &mut [f64], ranks: &[usize]) {
+    select_ranks_64bit_::<f64, 8, Avx512F64x1>(data, ranks, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn partition_i64(data: &mut [i64], pivot: i64) -> usize {
+    partition_64bit_::<i64, 8, Avx512I64x1>(data, pivot)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn partition_u64(data: &mut [u64], pivot: u64) -> usize {
+    partition_64bit_::<u64, 8, Avx512U64x1>(data, pivot)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn partition_f64(data: &mut [f64], pivot: f64) -> usize {
+    partition_64bit_::<f64, 8, Avx512F64x1>(data, pivot)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn qsort_128bit(hi: &mut [i64], lo: &mut [i64]) {
+    qsort_128bit_::<Avx512I64x1>(hi, lo, f64::log2(hi.len() as f64) as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+pub(crate) fn counting_sort_8bit(data: &mut [u8], bias: u8) {
+    let counts = bit_8::histogram(data);
+    // This is safe since the counts add up to the length of data
+    unsafe { bit_8::fill_runs(data, &counts, bias) }
 }
 
 #[cfg(test)]
-#[cfg(target_feature = "avx512f")]
 mod test {
     use crate::bit_64::{test::*, *};
+    use crate::platform::x86::avx512::bit_64::test::cpu_supports_backend;
 
     use super::*;
 
-    test_sort_n!(i64, __m512i, 8);
-    test_sort_n!(i64, __m512i, 16);
-    test_sort_n!(i64, __m512i, 32);
-    test_sort_n!(i64, __m512i, 64);
-    test_sort_n!(i64, __m512i, 128);
-    test_sort_n!(i64, __m512i, 256);
-    test_sort_e2e!(i64, __m512i, avx512_sort_i64);
-
-    test_sort_n!(u64, __m512i, 8);
-    test_sort_n!(u64, __m512i, 16);
-    test_sort_n!(u64, __m512i, 32);
-    test_sort_n!(u64, __m512i, 64);
-    test_sort_n!(u64, __m512i, 128);
-    test_sort_n!(u64, __m512i, 256);
-    test_sort_e2e!(u64, __m512i, avx512_sort_u64);
-
-    test_sort_n!(f64, __m512d, 8);
-    test_sort_n!(f64, __m512d, 16);
-    test_sort_n!(f64, __m512d, 32);
-    test_sort_n!(f64, __m512d, 64);
-    test_sort_n!(f64, __m512d, 128);
-    test_sort_n!(f64, __m512d, 256);
-    test_sort_e2e!(f64, __m512i, avx512_sort_f64);
+    test_sort_n!(i64, Avx512I64x1, 8, 8);
+    test_sort_n!(i64, Avx512I64x1, 8, 16);
+    test_sort_n!(i64, Avx512I64x1, 8, 32);
+    test_sort_n!(i64, Avx512I64x1, 8, 64);
+    test_sort_n!(i64, Avx512I64x1, 8, 128);
+    test_sort_n!(i64, Avx512I64x1, 8, 256);
+    test_sort_e2e!(i64, Avx512I64x1, avx512_sort_i64);
+    test_dedup_sorted!(i64, Avx512I64x1, 8);
+    test_count_runs!(i64, Avx512I64x1, 8);
+    test_set_operations!(i64, Avx512I64x1, 8);
+    test_lower_bound_batch!(i64, Avx512I64x1, 8);
+    test_minmax_slice!(i64, Avx512I64x1, 8);
+    test_select_ranks!(i64, Avx512I64x1, 8);
+    test_partition!(i64, Avx512I64x1, 8);
+
+    test_sort_n!(u64, Avx512U64x1, 8, 8);
+    test_sort_n!(u64, Avx512U64x1, 8, 16);
+    test_sort_n!(u64, Avx512U64x1, 8, 32);
+    test_sort_n!(u64, Avx512U64x1, 8, 64);
+    test_sort_n!(u64, Avx512U64x1, 8, 128);
+    test_sort_n!(u64, Avx512U64x1, 8, 256);
+    test_sort_e2e!(u64, Avx512U64x1, avx512_sort_u64);
+    test_dedup_sorted!(u64, Avx512U64x1, 8);
+    test_count_runs!(u64, Avx512U64x1, 8);
+    test_set_operations!(u64, Avx512U64x1, 8);
+    test_lower_bound_batch!(u64, Avx512U64x1, 8);
+    test_minmax_slice!(u64, Avx512U64x1, 8);
+    test_select_ranks!(u64, Avx512U64x1, 8);
+    test_partition!(u64, Avx512U64x1, 8);
+
+    test_sort_n!(f64, Avx512F64x1, 8, 8);
+    test_sort_n!(f64, Avx512F64x1, 8, 16);
+    test_sort_n!(f64, Avx512F64x1, 8, 32);
+    test_sort_n!(f64, Avx512F64x1, 8, 64);
+    test_sort_n!(f64, Avx512F64x1, 8, 128);
+    test_sort_n!(f64, Avx512F64x1, 8, 256);
+    test_sort_e2e!(f64, Avx512F64x1, avx512_sort_f64);
+    test_dedup_sorted!(f64, Avx512F64x1, 8);
+    test_count_runs!(f64, Avx512F64x1, 8);
+    test_set_operations!(f64, Avx512F64x1, 8);
+    test_lower_bound_batch!(f64, Avx512F64x1, 8);
+    test_minmax_slice!(f64, Avx512F64x1, 8);
+    test_select_ranks!(f64, Avx512F64x1, 8);
+    test_partition!(f64, Avx512F64x1, 8);
+
+    #[test]
+    fn test_counting_sort_8bit() {
+        if !cpu_supports_backend() {
+            return;
+        }
+        for len in [0, 1, 15, 16, 17, 63, 64, 65, 129, 1000, 100_000] {
+            let data: Vec<u8> = random_u64(len).into_iter().map(|x| x as u8).collect();
+
+            let mut unsigned = data.clone();
+            // This is safe since we have verified the cpu supports avx512f
+            unsafe { counting_sort_8bit(&mut unsigned, 0) };
+            let mut expected = data.clone();
+            expected.sort_unstable();
+            assert_eq!(unsigned, expected, "len {}", len);
+
+            let mut signed = data.clone();
+            // This is safe since we have verified the cpu supports avx512f
+            unsafe { counting_sort_8bit(&mut signed, 0x80) };
+            let mut expected: Vec<i8> = data.iter().map(|&x| x as i8).collect();
+            expected.sort_unstable();
+            let signed: Vec<i8> = signed.into_iter().map(|x| x as i8).collect();
+            assert_eq!(signed, expected, "len {}", len);
+        }
+    }
 }
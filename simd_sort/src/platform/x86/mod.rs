@@ -1,4 +1,3 @@
 pub mod avx2;
-
-#[cfg(feature = "nightly")]
 pub mod avx512;
+pub mod sse;
@@ -0,0 +1,76 @@
+use std::arch::x86_64::{
+    __m128d, _mm_castpd_si128, _mm_castsi128_pd, _mm_loadu_pd, _mm_loadu_si128, _mm_movemask_pd,
+    _mm_shuffle_epi8, _mm_storel_pd, _mm_storeu_pd,
+};
+
+/// [1, 0]
+pub const SHUFFLE1_1: i32 = 0b01001110;
+
+/// Byte shuffles used to left pack the selected lanes of a register,
+/// indexed by the 2 bit movemask of the register.
+pub const COMPRESS_SHUFFLE: [[i8; 16]; 4] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15], //00
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15], //01
+    [8, 9, 10, 11, 12, 13, 14, 15, 8, 9, 10, 11, 12, 13, 14, 15], //10
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15], //11
+];
+
+/// Stores the lanes of `values` selected by `masks` left packed at `dst`.
+///
+/// Every register is stored whole where its first selected lane goes, so the
+/// store does not branch on the masks, which are random while partitioning.
+/// The next register overwrites the lanes that were not selected, and the at
+/// most 2 values stored past the packed lanes are put back at the end. Only
+/// when `len` leaves no room for them the lanes are stored one by one.
+///
+/// # Safety
+///
+/// `dst` must be valid for reads and writes of `len` values, and `len` must
+/// be at least the number of selected lanes.
+#[inline(always)]
+pub unsafe fn compress_store_x4(
+    dst: *mut f64,
+    len: usize,
+    masks: [__m128d; 4],
+    values: [__m128d; 4],
+) {
+    let mut packed = values;
+    let mut counts = [0; 4];
+    for i in 0..4 {
+        let bitmask = _mm_movemask_pd(masks[i]) as usize;
+        packed[i] = _mm_castsi128_pd(_mm_shuffle_epi8(
+            _mm_castpd_si128(values[i]),
+            _mm_loadu_si128(COMPRESS_SHUFFLE.get_unchecked(bitmask).as_ptr() as *const _),
+        ));
+        counts[i] = (bitmask & 1) + (bitmask >> 1);
+    }
+    let count = counts[0] + counts[1] + counts[2] + counts[3];
+    let mut ptr = dst;
+    if count + 2 <= len {
+        let past = _mm_loadu_pd(dst.add(count));
+        for i in 0..4 {
+            _mm_storeu_pd(ptr, packed[i]);
+            ptr = ptr.add(counts[i]);
+        }
+        _mm_storeu_pd(dst.add(count), past);
+    } else {
+        for i in 0..4 {
+            match counts[i] {
+                2 => _mm_storeu_pd(ptr, packed[i]),
+                1 => _mm_storel_pd(ptr, packed[i]),
+                _ => {}
+            }
+            ptr = ptr.add(counts[i]);
+        }
+    }
+}
+
+/// Sign bit of a 64 bit lane, used to map unsigned compares onto signed ones
+pub const SIGN_BIT: i64 = i64::MIN;
+
+#[cfg(test)]
+pub mod test {
+    pub fn cpu_supports_backend() -> bool {
+        is_x86_feature_detected!("sse4.2")
+    }
+}
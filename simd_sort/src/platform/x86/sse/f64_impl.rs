@@ -0,0 +1,419 @@
+use std::arch::x86_64::{
+    __m128d, _mm_and_pd, _mm_blendv_pd, _mm_cmpeq_pd, _mm_cmpge_pd, _mm_cvtsd_f64, _mm_loadu_pd,
+    _mm_max_pd, _mm_min_pd, _mm_move_sd, _mm_movemask_pd, _mm_or_pd, _mm_set1_pd, _mm_set_pd,
+    _mm_shuffle_pd, _mm_storeu_pd, _mm_unpackhi_pd, _mm_xor_pd,
+};
+use std::{mem, slice};
+
+use crate::bit_64::Bit64Simd;
+use crate::SimdCompare;
+
+use super::bit_64::compress_store_x4;
+
+#[inline(always)]
+fn swap_x2(a: __m128d) -> __m128d {
+    unsafe { _mm_shuffle_pd(a, a, 0b01) }
+}
+
+// lanes selected by the low 2 bits of mask from b, the others from a
+#[inline(always)]
+fn mask_mov_x2(a: __m128d, b: __m128d, mask: u64) -> __m128d {
+    unsafe {
+        match mask & 0b11 {
+            0b00 => a,
+            0b01 => _mm_move_sd(a, b),
+            0b10 => _mm_move_sd(b, a),
+            _ => b,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SseF64x4 {
+    values: [__m128d; 4],
+}
+
+impl PartialEq for SseF64x4 {
+    fn eq(&self, other: &Self) -> bool {
+        unsafe {
+            let a_ptr: *const i64 = &self.values as *const _ as *const i64;
+            let b_ptr: *const i64 = &other.values as *const _ as *const i64;
+            let a_slice = std::slice::from_raw_parts(a_ptr, 8);
+            let b_slice = std::slice::from_raw_parts(b_ptr, 8);
+
+            a_slice == b_slice
+        }
+    }
+}
+
+impl From<[f64; 8]> for SseF64x4 {
+    fn from(v: [f64; 8]) -> Self {
+        unsafe { mem::transmute(v) }
+    }
+}
+
+impl SseF64x4 {
+    fn as_slice(&self) -> &[f64] {
+        unsafe { slice::from_raw_parts(self.values.as_ptr() as *const f64, 8) }
+    }
+}
+
+impl SimdCompare<f64, 8> for SseF64x4 {
+    type OPMask = Self;
+
+    #[inline(always)]
+    fn min(a: Self, b: Self) -> Self {
+        unsafe {
+            Self {
+                values: [
+                    _mm_min_pd(a.values[0], b.values[0]),
+                    _mm_min_pd(a.values[1], b.values[1]),
+                    _mm_min_pd(a.values[2], b.values[2]),
+                    _mm_min_pd(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn max(a: Self, b: Self) -> Self {
+        unsafe {
+            Self {
+                values: [
+                    _mm_max_pd(a.values[0], b.values[0]),
+                    _mm_max_pd(a.values[1], b.values[1]),
+                    _mm_max_pd(a.values[2], b.values[2]),
+                    _mm_max_pd(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Self {
+            values: [
+                mask_mov_x2(a.values[0], b.values[0], MASK),
+                mask_mov_x2(a.values[1], b.values[1], MASK >> 2),
+                mask_mov_x2(a.values[2], b.values[2], MASK >> 4),
+                mask_mov_x2(a.values[3], b.values[3], MASK >> 6),
+            ],
+        }
+    }
+
+    #[inline(always)]
+    fn loadu(data: &[f64]) -> Self {
+        unsafe {
+            let base_ptr = data.as_ptr();
+            Self {
+                values: [
+                    _mm_loadu_pd(base_ptr),
+                    _mm_loadu_pd(base_ptr.add(2)),
+                    _mm_loadu_pd(base_ptr.add(4)),
+                    _mm_loadu_pd(base_ptr.add(6)),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn storeu(input: Self, data: &mut [f64]) {
+        unsafe {
+            let base_ptr = data.as_mut_ptr();
+            _mm_storeu_pd(base_ptr, input.values[0]);
+            _mm_storeu_pd(base_ptr.add(2), input.values[1]);
+            _mm_storeu_pd(base_ptr.add(4), input.values[2]);
+            _mm_storeu_pd(base_ptr.add(6), input.values[3]);
+        }
+    }
+
+    #[inline(always)]
+    fn mask_loadu(data: &[f64]) -> Self {
+        let mut max = [f64::MAX; 8];
+        max[..data.len()].copy_from_slice(data);
+        Self::from(max)
+    }
+
+    #[inline(always)]
+    fn mask_storeu(input: Self, data: &mut [f64]) {
+        let len = data.len();
+        data.copy_from_slice(&input.as_slice()[..len]);
+    }
+
+    #[inline(always)]
+    fn gather_from_idx(idx: [usize; 8], data: &[f64]) -> Self {
+        unsafe {
+            Self {
+                values: [
+                    _mm_set_pd(*data.get_unchecked(idx[1]), *data.get_unchecked(idx[0])),
+                    _mm_set_pd(*data.get_unchecked(idx[3]), *data.get_unchecked(idx[2])),
+                    _mm_set_pd(*data.get_unchecked(idx[5]), *data.get_unchecked(idx[4])),
+                    _mm_set_pd(*data.get_unchecked(idx[7]), *data.get_unchecked(idx[6])),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn get_value_at_idx(input: Self, idx: usize) -> f64 {
+        unsafe { *input.as_slice().get_unchecked(idx) }
+    }
+
+    #[inline(always)]
+    fn set(value: f64) -> Self {
+        unsafe {
+            let v = _mm_set1_pd(value);
+            Self { values: [v; 4] }
+        }
+    }
+
+    #[inline(always)]
+    fn ge(a: Self, b: Self) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm_cmpge_pd(a.values[0], b.values[0]),
+                    _mm_cmpge_pd(a.values[1], b.values[1]),
+                    _mm_cmpge_pd(a.values[2], b.values[2]),
+                    _mm_cmpge_pd(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm_cmpeq_pd(a.values[0], b.values[0]),
+                    _mm_cmpeq_pd(a.values[1], b.values[1]),
+                    _mm_cmpeq_pd(a.values[2], b.values[2]),
+                    _mm_cmpeq_pd(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn ones_count(mask: Self::OPMask) -> usize {
+        unsafe {
+            let mut count = 0;
+            for i in 0..4 {
+                count += _mm_movemask_pd(mask.values[i]).count_ones();
+            }
+            count as usize
+        }
+    }
+
+    #[inline(always)]
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        unsafe {
+            let mut bits = 0;
+            for i in 0..4 {
+                bits |= (_mm_movemask_pd(mask.values[i]) as u64) << (2 * i);
+            }
+            bits
+        }
+    }
+
+    #[inline(always)]
+    fn not_mask(mask: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            let all_bit_set = mem::transmute::<[i64; 2], __m128d>([-1i64, -1]);
+            Self {
+                values: [
+                    _mm_xor_pd(all_bit_set, mask.values[0]),
+                    _mm_xor_pd(all_bit_set, mask.values[1]),
+                    _mm_xor_pd(all_bit_set, mask.values[2]),
+                    _mm_xor_pd(all_bit_set, mask.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm_or_pd(a.values[0], b.values[0]),
+                    _mm_or_pd(a.values[1], b.values[1]),
+                    _mm_or_pd(a.values[2], b.values[2]),
+                    _mm_or_pd(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm_and_pd(a.values[0], b.values[0]),
+                    _mm_and_pd(a.values[1], b.values[1]),
+                    _mm_and_pd(a.values[2], b.values[2]),
+                    _mm_and_pd(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        unsafe {
+            Self {
+                values: [
+                    _mm_blendv_pd(a.values[0], b.values[0], mask.values[0]),
+                    _mm_blendv_pd(a.values[1], b.values[1], mask.values[1]),
+                    _mm_blendv_pd(a.values[2], b.values[2], mask.values[2]),
+                    _mm_blendv_pd(a.values[3], b.values[3], mask.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn reducemin(x: Self) -> f64 {
+        unsafe {
+            let m1 = _mm_min_pd(x.values[0], x.values[1]);
+            let m2 = _mm_min_pd(x.values[2], x.values[3]);
+            let m2 = _mm_min_pd(m1, m2);
+            _mm_cvtsd_f64(_mm_min_pd(m2, _mm_unpackhi_pd(m2, m2)))
+        }
+    }
+
+    #[inline(always)]
+    fn reducemax(x: Self) -> f64 {
+        unsafe {
+            let m1 = _mm_max_pd(x.values[0], x.values[1]);
+            let m2 = _mm_max_pd(x.values[2], x.values[3]);
+            let m2 = _mm_max_pd(m1, m2);
+            _mm_cvtsd_f64(_mm_max_pd(m2, _mm_unpackhi_pd(m2, m2)))
+        }
+    }
+
+    #[inline(always)]
+    fn mask_compressstoreu(array: &mut [f64], mask: Self::OPMask, data: Self) {
+        unsafe {
+            compress_store_x4(array.as_mut_ptr(), array.len(), mask.values, data.values);
+        }
+    }
+}
+
+// This is safe since the sse4.2 feature is enabled and the compress
+// store writes one value per set lane
+#[cfg(target_feature = "sse4.2")]
+unsafe impl crate::SortBackend<f64, 8> for SseF64x4 {}
+
+impl Bit64Simd<f64> for SseF64x4 {
+    fn shuffle1_1_1_1(a: Self) -> Self {
+        Self {
+            values: [
+                swap_x2(a.values[0]),
+                swap_x2(a.values[1]),
+                swap_x2(a.values[2]),
+                swap_x2(a.values[3]),
+            ],
+        }
+    }
+
+    // 3, 2, 1, 0, 7, 6, 5, 4
+    fn network64bit1(a: Self) -> Self {
+        Self {
+            values: [
+                swap_x2(a.values[1]),
+                swap_x2(a.values[0]),
+                swap_x2(a.values[3]),
+                swap_x2(a.values[2]),
+            ],
+        }
+    }
+
+    // 7, 6, 5, 4, 3, 2, 1, 0
+    fn network64bit2(a: Self) -> Self {
+        Self {
+            values: [
+                swap_x2(a.values[3]),
+                swap_x2(a.values[2]),
+                swap_x2(a.values[1]),
+                swap_x2(a.values[0]),
+            ],
+        }
+    }
+
+    // 2, 3, 0, 1, 6, 7, 4, 5
+    fn network64bit3(a: Self) -> Self {
+        Self {
+            values: [a.values[1], a.values[0], a.values[3], a.values[2]],
+        }
+    }
+
+    // 4, 5, 6, 7, 0, 1, 2, 3
+    fn network64bit4(a: Self) -> Self {
+        Self {
+            values: [a.values[2], a.values[3], a.values[0], a.values[1]],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bit_64::test::*;
+    use crate::platform::x86::sse::bit_64::test::cpu_supports_backend;
+
+    fn into_array_f64(x: SseF64x4) -> [f64; 8] {
+        unsafe { mem::transmute(x) }
+    }
+
+    fn generate_mask_answer<T, M>(bitmask: usize, values: &[T]) -> (M, [T; 8])
+    where
+        T: Default + Copy,
+        M: From<[f64; 8]>,
+    {
+        let mut result = [0f64; 8];
+        let mut new_values = [<T as Default>::default(); 8];
+        let mut count = 0;
+        for i in 0..8 {
+            if bitmask & (1 << i) != 0 {
+                result[i] = f64::from_bits(!0u64);
+                new_values[count] = values[i];
+                count += 1;
+            }
+        }
+        (M::from(result), new_values)
+    }
+
+    fn mask_fn(x: u8) -> SseF64x4 {
+        generate_mask_answer::<f64, SseF64x4>(x as usize, &[0f64; 8]).0
+    }
+
+    test_min_max!(f64, SseF64x4, into_array_f64);
+    test_loadu_storeu!(f64, SseF64x4, into_array_f64);
+    test_mask_loadu_mask_storeu!(f64, SseF64x4, into_array_f64);
+    test_get_at_index!(f64, SseF64x4);
+    test_eq_or_mask!(f64, SseF64x4);
+    test_to_bitmask!(f64, SseF64x4);
+    test_and_mask_blend!(f64, SseF64x4);
+    test_ge!(f64, SseF64x4, unsafe {
+        mem::transmute::<[i64; 8], SseF64x4>([0i64, -1, 0, -1, 0, -1, 0, -1])
+    });
+    test_gather!(f64, SseF64x4, into_array_f64);
+    test_not!(
+        f64,
+        SseF64x4,
+        unsafe { mem::transmute::<[i64; 8], SseF64x4>([0i64, -1, 0, -1, 0, -1, 0, 0]) },
+        unsafe { mem::transmute::<[i64; 8], SseF64x4>([-1i64, 0, -1, 0, -1, 0, -1, -1]) }
+    );
+    test_count_ones!(f64, SseF64x4, mask_fn);
+    test_reduce_min_max!(f64, SseF64x4);
+    test_compress_store_u!(f64, SseF64x4, SseF64x4, generate_mask_answer);
+    test_shuffle1_1_1_1!(f64, SseF64x4, into_array_f64);
+    test_mask_mov!(f64, SseF64x4, into_array_f64);
+    network64bit1!(f64, SseF64x4, into_array_f64);
+    network64bit2!(f64, SseF64x4, into_array_f64);
+    network64bit3!(f64, SseF64x4, into_array_f64);
+    network64bit4!(f64, SseF64x4, into_array_f64);
+}
@@ -0,0 +1,421 @@
+use std::{
+    arch::x86_64::{
+        __m128i, _mm_and_si128, _mm_blendv_epi8, _mm_castpd_si128, _mm_castsi128_pd,
+        _mm_cmpeq_epi64, _mm_cmpgt_epi64, _mm_cvtsi128_si64, _mm_loadu_si128, _mm_move_sd,
+        _mm_movemask_pd, _mm_or_si128, _mm_set1_epi64x, _mm_set_epi64x, _mm_shuffle_epi32,
+        _mm_storeu_si128, _mm_unpackhi_epi64, _mm_xor_si128,
+    },
+    array, mem, slice,
+};
+
+use crate::{bit_64::Bit64Simd, SimdCompare};
+
+use super::bit_64::{compress_store_x4, SHUFFLE1_1};
+
+#[inline(always)]
+fn min_x2(a: __m128i, b: __m128i) -> __m128i {
+    unsafe { _mm_blendv_epi8(a, b, _mm_cmpgt_epi64(a, b)) }
+}
+
+#[inline(always)]
+fn max_x2(a: __m128i, b: __m128i) -> __m128i {
+    unsafe { _mm_blendv_epi8(b, a, _mm_cmpgt_epi64(a, b)) }
+}
+
+#[inline(always)]
+fn swap_x2(a: __m128i) -> __m128i {
+    unsafe { _mm_shuffle_epi32(a, SHUFFLE1_1) }
+}
+
+// lanes selected by the low 2 bits of mask from b, the others from a
+#[inline(always)]
+fn mask_mov_x2(a: __m128i, b: __m128i, mask: u64) -> __m128i {
+    unsafe {
+        match mask & 0b11 {
+            0b00 => a,
+            0b01 => _mm_castpd_si128(_mm_move_sd(_mm_castsi128_pd(a), _mm_castsi128_pd(b))),
+            0b10 => _mm_castpd_si128(_mm_move_sd(_mm_castsi128_pd(b), _mm_castsi128_pd(a))),
+            _ => b,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SseI64x4 {
+    values: [__m128i; 4],
+}
+
+impl PartialEq for SseI64x4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl From<[i64; 8]> for SseI64x4 {
+    fn from(v: [i64; 8]) -> Self {
+        unsafe { mem::transmute(v) }
+    }
+}
+
+impl SseI64x4 {
+    fn as_slice(&self) -> &[i64] {
+        unsafe { slice::from_raw_parts(self.values.as_ptr() as *const i64, 8) }
+    }
+}
+
+impl SimdCompare<i64, 8> for SseI64x4 {
+    type OPMask = Self;
+
+    #[inline(always)]
+    fn min(a: Self, b: Self) -> Self {
+        Self {
+            values: [
+                min_x2(a.values[0], b.values[0]),
+                min_x2(a.values[1], b.values[1]),
+                min_x2(a.values[2], b.values[2]),
+                min_x2(a.values[3], b.values[3]),
+            ],
+        }
+    }
+
+    #[inline(always)]
+    fn max(a: Self, b: Self) -> Self {
+        Self {
+            values: [
+                max_x2(a.values[0], b.values[0]),
+                max_x2(a.values[1], b.values[1]),
+                max_x2(a.values[2], b.values[2]),
+                max_x2(a.values[3], b.values[3]),
+            ],
+        }
+    }
+
+    #[inline(always)]
+    fn mask_mov<const MASK: u64>(a: Self, b: Self) -> Self {
+        Self {
+            values: [
+                mask_mov_x2(a.values[0], b.values[0], MASK),
+                mask_mov_x2(a.values[1], b.values[1], MASK >> 2),
+                mask_mov_x2(a.values[2], b.values[2], MASK >> 4),
+                mask_mov_x2(a.values[3], b.values[3], MASK >> 6),
+            ],
+        }
+    }
+
+    #[inline(always)]
+    fn loadu(data: &[i64]) -> Self {
+        unsafe {
+            let base_ptr = data.as_ptr() as *const __m128i;
+            Self {
+                values: [
+                    _mm_loadu_si128(base_ptr),
+                    _mm_loadu_si128(base_ptr.add(1)),
+                    _mm_loadu_si128(base_ptr.add(2)),
+                    _mm_loadu_si128(base_ptr.add(3)),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn storeu(input: Self, data: &mut [i64]) {
+        unsafe {
+            let base_ptr = data.as_mut_ptr() as *mut __m128i;
+            _mm_storeu_si128(base_ptr, input.values[0]);
+            _mm_storeu_si128(base_ptr.add(1), input.values[1]);
+            _mm_storeu_si128(base_ptr.add(2), input.values[2]);
+            _mm_storeu_si128(base_ptr.add(3), input.values[3]);
+        }
+    }
+
+    #[inline(always)]
+    fn mask_loadu(data: &[i64]) -> Self {
+        let mut max = [i64::MAX; 8];
+        max[..data.len()].copy_from_slice(data);
+        Self::from(max)
+    }
+
+    #[inline(always)]
+    fn mask_storeu(input: Self, data: &mut [i64]) {
+        let len = data.len();
+        data.copy_from_slice(&input.as_slice()[..len]);
+    }
+
+    #[inline(always)]
+    fn gather_from_idx(idx: [usize; 8], data: &[i64]) -> Self {
+        unsafe {
+            Self {
+                values: [
+                    _mm_set_epi64x(*data.get_unchecked(idx[1]), *data.get_unchecked(idx[0])),
+                    _mm_set_epi64x(*data.get_unchecked(idx[3]), *data.get_unchecked(idx[2])),
+                    _mm_set_epi64x(*data.get_unchecked(idx[5]), *data.get_unchecked(idx[4])),
+                    _mm_set_epi64x(*data.get_unchecked(idx[7]), *data.get_unchecked(idx[6])),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn get_value_at_idx(input: Self, idx: usize) -> i64 {
+        unsafe { *input.as_slice().get_unchecked(idx) }
+    }
+
+    #[inline(always)]
+    fn set(value: i64) -> Self {
+        unsafe {
+            let v = _mm_set1_epi64x(value);
+            Self { values: [v; 4] }
+        }
+    }
+
+    #[inline(always)]
+    fn ge(a: Self, b: Self) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: array::from_fn(|i| {
+                    _mm_or_si128(
+                        _mm_cmpgt_epi64(a.values[i], b.values[i]),
+                        _mm_cmpeq_epi64(a.values[i], b.values[i]),
+                    )
+                }),
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn eq(a: Self, b: Self) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm_cmpeq_epi64(a.values[0], b.values[0]),
+                    _mm_cmpeq_epi64(a.values[1], b.values[1]),
+                    _mm_cmpeq_epi64(a.values[2], b.values[2]),
+                    _mm_cmpeq_epi64(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn ones_count(mask: Self::OPMask) -> usize {
+        unsafe {
+            let mut count = 0;
+            for i in 0..4 {
+                count += _mm_movemask_pd(_mm_castsi128_pd(mask.values[i])).count_ones();
+            }
+            count as usize
+        }
+    }
+
+    #[inline(always)]
+    fn to_bitmask(mask: Self::OPMask) -> u64 {
+        unsafe {
+            let mut bits = 0;
+            for i in 0..4 {
+                bits |= (_mm_movemask_pd(_mm_castsi128_pd(mask.values[i])) as u64) << (2 * i);
+            }
+            bits
+        }
+    }
+
+    #[inline(always)]
+    fn not_mask(mask: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            let all_bit_set = _mm_set1_epi64x(-1);
+            Self {
+                values: [
+                    _mm_xor_si128(all_bit_set, mask.values[0]),
+                    _mm_xor_si128(all_bit_set, mask.values[1]),
+                    _mm_xor_si128(all_bit_set, mask.values[2]),
+                    _mm_xor_si128(all_bit_set, mask.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn or_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm_or_si128(a.values[0], b.values[0]),
+                    _mm_or_si128(a.values[1], b.values[1]),
+                    _mm_or_si128(a.values[2], b.values[2]),
+                    _mm_or_si128(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn and_mask(a: Self::OPMask, b: Self::OPMask) -> Self::OPMask {
+        unsafe {
+            Self {
+                values: [
+                    _mm_and_si128(a.values[0], b.values[0]),
+                    _mm_and_si128(a.values[1], b.values[1]),
+                    _mm_and_si128(a.values[2], b.values[2]),
+                    _mm_and_si128(a.values[3], b.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn blend(mask: Self::OPMask, a: Self, b: Self) -> Self {
+        unsafe {
+            Self {
+                values: [
+                    _mm_blendv_epi8(a.values[0], b.values[0], mask.values[0]),
+                    _mm_blendv_epi8(a.values[1], b.values[1], mask.values[1]),
+                    _mm_blendv_epi8(a.values[2], b.values[2], mask.values[2]),
+                    _mm_blendv_epi8(a.values[3], b.values[3], mask.values[3]),
+                ],
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn reducemin(x: Self) -> i64 {
+        let m1 = min_x2(x.values[0], x.values[1]);
+        let m2 = min_x2(x.values[2], x.values[3]);
+        let m2 = min_x2(m1, m2);
+        unsafe { _mm_cvtsi128_si64(min_x2(m2, _mm_unpackhi_epi64(m2, m2))) }
+    }
+
+    #[inline(always)]
+    fn reducemax(x: Self) -> i64 {
+        let m1 = max_x2(x.values[0], x.values[1]);
+        let m2 = max_x2(x.values[2], x.values[3]);
+        let m2 = max_x2(m1, m2);
+        unsafe { _mm_cvtsi128_si64(max_x2(m2, _mm_unpackhi_epi64(m2, m2))) }
+    }
+
+    #[inline(always)]
+    fn mask_compressstoreu(array: &mut [i64], mask: Self::OPMask, data: Self) {
+        unsafe {
+            compress_store_x4(
+                array.as_mut_ptr() as *mut f64,
+                array.len(),
+                mask.values.map(|v| _mm_castsi128_pd(v)),
+                data.values.map(|v| _mm_castsi128_pd(v)),
+            );
+        }
+    }
+}
+
+// This is safe since the sse4.2 feature is enabled and the compress
+// store writes one value per set lane
+#[cfg(target_feature = "sse4.2")]
+unsafe impl crate::SortBackend<i64, 8> for SseI64x4 {}
+
+impl Bit64Simd<i64> for SseI64x4 {
+    fn shuffle1_1_1_1(a: Self) -> Self {
+        Self {
+            values: [
+                swap_x2(a.values[0]),
+                swap_x2(a.values[1]),
+                swap_x2(a.values[2]),
+                swap_x2(a.values[3]),
+            ],
+        }
+    }
+
+    // 3, 2, 1, 0, 7, 6, 5, 4
+    fn network64bit1(a: Self) -> Self {
+        Self {
+            values: [
+                swap_x2(a.values[1]),
+                swap_x2(a.values[0]),
+                swap_x2(a.values[3]),
+                swap_x2(a.values[2]),
+            ],
+        }
+    }
+
+    // 7, 6, 5, 4, 3, 2, 1, 0
+    fn network64bit2(a: Self) -> Self {
+        Self {
+            values: [
+                swap_x2(a.values[3]),
+                swap_x2(a.values[2]),
+                swap_x2(a.values[1]),
+                swap_x2(a.values[0]),
+            ],
+        }
+    }
+
+    // 2, 3, 0, 1, 6, 7, 4, 5
+    fn network64bit3(a: Self) -> Self {
+        Self {
+            values: [a.values[1], a.values[0], a.values[3], a.values[2]],
+        }
+    }
+
+    // 4, 5, 6, 7, 0, 1, 2, 3
+    fn network64bit4(a: Self) -> Self {
+        Self {
+            values: [a.values[2], a.values[3], a.values[0], a.values[1]],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bit_64::test::*;
+    use crate::platform::x86::sse::bit_64::test::cpu_supports_backend;
+    use std::ops::Not;
+
+    use super::*;
+
+    fn into_array_i64(x: SseI64x4) -> [i64; 8] {
+        unsafe { mem::transmute(x) }
+    }
+
+    fn generate_mask_answer<T, M>(bitmask: usize, values: &[T]) -> (M, [T; 8])
+    where
+        T: Default + Copy + Not<Output = T>,
+        M: From<[T; 8]>,
+    {
+        let mut result = [<T as Default>::default(); 8];
+        let mut new_values = [<T as Default>::default(); 8];
+        let mut count = 0;
+        for i in 0..8 {
+            if bitmask & (1 << i) != 0 {
+                result[i] = !<T as Default>::default();
+                new_values[count] = values[i];
+                count += 1;
+            }
+        }
+        (M::from(result), new_values)
+    }
+
+    fn mask_fn(x: u8) -> SseI64x4 {
+        SseI64x4::from(generate_mask_answer::<i64, [i64; 8]>(x as usize, &[0; 8]).0)
+    }
+
+    test_min_max!(i64, SseI64x4, into_array_i64);
+    test_loadu_storeu!(i64, SseI64x4, into_array_i64);
+    test_mask_loadu_mask_storeu!(i64, SseI64x4, into_array_i64);
+    test_get_at_index!(i64, SseI64x4);
+    test_eq_or_mask!(i64, SseI64x4);
+    test_to_bitmask!(i64, SseI64x4);
+    test_and_mask_blend!(i64, SseI64x4);
+    test_ge!(i64, SseI64x4, SseI64x4::from([0, -1, 0, -1, 0, -1, 0, -1]));
+    test_gather!(i64, SseI64x4, into_array_i64);
+    test_not!(
+        i64,
+        SseI64x4,
+        SseI64x4::from([0, -1, 0, -1, 0, -1, 0, 0]),
+        SseI64x4::from([-1, 0, -1, 0, -1, 0, -1, -1])
+    );
+    test_count_ones!(i64, SseI64x4, mask_fn);
+    test_reduce_min_max!(i64, SseI64x4);
+    test_compress_store_u!(i64, SseI64x4, SseI64x4, generate_mask_answer);
+    test_shuffle1_1_1_1!(i64, SseI64x4, into_array_i64);
+    test_mask_mov!(i64, SseI64x4, into_array_i64);
+    network64bit1!(i64, SseI64x4, into_array_i64);
+    network64bit2!(i64, SseI64x4, into_array_i64);
+    network64bit3!(i64, SseI64x4, into_array_i64);
+    network64bit4!(i64, SseI64x4, into_array_i64);
+}
@@ -0,0 +1,241 @@
+pub(crate) mod bit_64;
+pub(crate) mod f64_impl;
+pub(crate) mod i64_impl;
+pub(crate) mod u64_impl;
+
+use crate::bit_128::qsort_128bit_;
+use crate::bit_64::{
+    bitonic_sort, count_runs, dedup_sorted, lower_bound_batch, minmax, partition_64bit_,
+    qsort_64bit_, select_ranks_64bit_, set_operation,
+};
+
+use crate::platform::SetOperation;
+
+use self::f64_impl::SseF64x4;
+use self::i64_impl::SseI64x4;
+use self::u64_impl::SseU64x4;
+
+/// Sorts `data` with the sse backend.
+///
+/// # Panics
+///
+/// Panics if the cpu does not support `sse4.2`.
+pub fn sse_sort_i64(data: &mut [i64]) {
+    assert!(is_x86_feature_detected!("sse4.2"));
+    // This is safe since we have verified the cpu supports sse4.2
+    unsafe { qsort_i64(data) }
+}
+
+/// Sorts `data` with the sse backend.
+///
+/// # Panics
+///
+/// Panics if the cpu does not support `sse4.2`.
+pub fn sse_sort_u64(data: &mut [u64]) {
+    assert!(is_x86_feature_detected!("sse4.2"));
+    // This is safe since we have verified the cpu supports sse4.2
+    unsafe { qsort_u64(data) }
+}
+
+/// Sorts `data` with the sse backend.
+///
+/// # Panics
+///
+/// Panics if the cpu does not support `sse4.2`.
+pub fn sse_sort_f64(data: &mut [f64]) {
+    assert!(is_x86_feature_detected!("sse4.2"));
+    // This is safe since we have verified the cpu supports sse4.2
+    unsafe { qsort_f64(data) }
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn qsort_i64(data: &mut [i64]) {
+    qsort_64bit_::<i64, 8, SseI64x4>(data, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn qsort_u64(data: &mut [u64]) {
+    qsort_64bit_::<u64, 8, SseU64x4>(data, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn qsort_f64(data: &mut [f64]) {
+    qsort_64bit_::<f64, 8, SseF64x4>(data, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn small_sort_i64(data: &mut [i64]) {
+    bitonic_sort::<i64, 8, SseI64x4>(data)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn small_sort_u64(data: &mut [u64]) {
+    bitonic_sort::<u64, 8, SseU64x4>(data)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn small_sort_f64(data: &mut [f64]) {
+    bitonic_sort::<f64, 8, SseF64x4>(data)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn dedup_sorted_i64(data: &mut [i64]) -> usize {
+    dedup_sorted::<i64, 8, SseI64x4>(data)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn dedup_sorted_u64(data: &mut [u64]) -> usize {
+    dedup_sorted::<u64, 8, SseU64x4>(data)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn dedup_sorted_f64(data: &mut [f64]) -> usize {
+    dedup_sorted::<f64, 8, SseF64x4>(data)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn count_runs_i64(data: &[i64], counts: &mut Vec<(i64, usize)>) {
+    count_runs::<i64, 8, SseI64x4>(data, counts)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn count_runs_u64(data: &[u64], counts: &mut Vec<(u64, usize)>) {
+    count_runs::<u64, 8, SseU64x4>(data, counts)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn count_runs_f64(data: &[f64], counts: &mut Vec<(f64, usize)>) {
+    count_runs::<f64, 8, SseF64x4>(data, counts)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn set_operation_i64(op: SetOperation, a: &[i64], b: &[i64]) -> Vec<i64> {
+    set_operation::<i64, 8, SseI64x4>(op, a, b)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn set_operation_u64(op: SetOperation, a: &[u64], b: &[u64]) -> Vec<u64> {
+    set_operation::<u64, 8, SseU64x4>(op, a, b)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn set_operation_f64(op: SetOperation, a: &[f64], b: &[f64]) -> Vec<f64> {
+    set_operation::<f64, 8, SseF64x4>(op, a, b)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn lower_bound_batch_i64(sorted: &[i64], probes: &[i64], out: &mut [usize]) {
+    lower_bound_batch::<i64, 8, SseI64x4>(sorted, probes, out)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn lower_bound_batch_u64(sorted: &[u64], probes: &[u64], out: &mut [usize]) {
+    lower_bound_batch::<u64, 8, SseU64x4>(sorted, probes, out)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn lower_bound_batch_f64(sorted: &[f64], probes: &[f64], out: &mut [usize]) {
+    lower_bound_batch::<f64, 8, SseF64x4>(sorted, probes, out)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn minmax_i64(data: &[i64], propagate_nan: bool) -> Option<(i64, i64)> {
+    minmax::<i64, 8, SseI64x4>(data, propagate_nan)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn minmax_u64(data: &[u64], propagate_nan: bool) -> Option<(u64, u64)> {
+    minmax::<u64, 8, SseU64x4>(data, propagate_nan)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn minmax_f64(data: &[f64], propagate_nan: bool) -> Option<(f64, f64)> {
+    minmax::<f64, 8, SseF64x4>(data, propagate_nan)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn select_ranks_i64(data: &mut [i64], ranks: &[usize]) {
+    select_ranks_64bit_::<i64, 8, SseI64x4>(data, ranks, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn select_ranks_u64(data: &mut [u64], ranks: &[usize]) {
+    select_ranks_64bit_::<u64, 8, SseU64x4>(data, ranks, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn select_ranks_f64(data: &mut [f64], ranks: &[usize]) {
+    select_ranks_64bit_::<f64, 8, SseF64x4>(data, ranks, f64::log2(data.len() as f64) as i64)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn partition_i64(data: &mut [i64], pivot: i64) -> usize {
+    partition_64bit_::<i64, 8, SseI64x4>(data, pivot)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn partition_u64(data: &mut [u64], pivot: u64) -> usize {
+    partition_64bit_::<u64, 8, SseU64x4>(data, pivot)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn partition_f64(data: &mut [f64], pivot: f64) -> usize {
+    partition_64bit_::<f64, 8, SseF64x4>(data, pivot)
+}
+
+#[target_feature(enable = "sse4.2")]
+pub(crate) fn qsort_128bit(hi: &mut [i64], lo: &mut [i64]) {
+    qsort_128bit_::<SseI64x4>(hi, lo, f64::log2(hi.len() as f64) as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bit_64::{test::*, *};
+    use crate::platform::x86::sse::bit_64::test::cpu_supports_backend;
+
+    test_sort_n!(i64, SseI64x4, 8, 8);
+    test_sort_n!(i64, SseI64x4, 8, 16);
+    test_sort_n!(i64, SseI64x4, 8, 32);
+    test_sort_n!(i64, SseI64x4, 8, 64);
+    test_sort_n!(i64, SseI64x4, 8, 128);
+    test_sort_n!(i64, SseI64x4, 8, 256);
+    test_sort_e2e!(i64, SseI64x4, sse_sort_i64);
+    test_dedup_sorted!(i64, SseI64x4, 8);
+    test_count_runs!(i64, SseI64x4, 8);
+    test_set_operations!(i64, SseI64x4, 8);
+    test_lower_bound_batch!(i64, SseI64x4, 8);
+    test_minmax_slice!(i64, SseI64x4, 8);
+    test_select_ranks!(i64, SseI64x4, 8);
+    test_partition!(i64, SseI64x4, 8);
+
+    test_sort_n!(u64, SseU64x4, 8, 8);
+    test_sort_n!(u64, SseU64x4, 8, 16);
+    test_sort_n!(u64, SseU64x4, 8, 32);
+    test_sort_n!(u64, SseU64x4, 8, 64);
+    test_sort_n!(u64, SseU64x4, 8, 128);
+    test_sort_n!(u64, SseU64x4, 8, 256);
+    test_sort_e2e!(u64, SseU64x4, sse_sort_u64);
+    test_dedup_sorted!(u64, SseU64x4, 8);
+    test_count_runs!(u64, SseU64x4, 8);
+    test_set_operations!(u64, SseU64x4, 8);
+    test_lower_bound_batch!(u64, SseU64x4, 8);
+    test_minmax_slice!(u64, SseU64x4, 8);
+    test_select_ranks!(u64, SseU64x4, 8);
+    test_partition!(u64, SseU64x4, 8);
+
+    test_sort_n!(f64, SseF64x4, 8, 8);
+    test_sort_n!(f64, SseF64x4, 8, 16);
+    test_sort_n!(f64, SseF64x4, 8, 32);
+    test_sort_n!(f64, SseF64x4, 8, 64);
+    test_sort_n!(f64, SseF64x4, 8, 128);
+    test_sort_n!(f64, SseF64x4, 8, 256);
+    test_sort_e2e!(f64, SseF64x4, sse_sort_f64);
+    test_dedup_sorted!(f64, SseF64x4, 8);
+    test_count_runs!(f64, SseF64x4, 8);
+    test_set_operations!(f64, SseF64x4, 8);
+    test_lower_bound_batch!(f64, SseF64x4, 8);
+    test_minmax_slice!(f64, SseF64x4, 8);
+    test_select_ranks!(f64, SseF64x4, 8);
+    test_partition!(f64, SseF64x4, 8);
+}
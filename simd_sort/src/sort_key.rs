@@ -0,0 +1,237 @@
+use std::{mem, slice};
+
+use crate::platform::BackendSortable;
+
+/// Types that sort like the `i64` or `u64` they map to.
+///
+/// `to_key` must preserve the order, `a < b` exactly if
+/// `a.to_key() < b.to_key()`, and `from_key(to_key(x))` must give back `x`.
+/// [`sort`](crate::sort) sorts the keys with the 64-bit backends and maps
+/// them back. Newtypes can use [`impl_simd_sort_key`](crate::impl_simd_sort_key)
+/// to sort like their field.
+pub trait SimdSortKey: Copy {
+    /// `i64` or `u64`
+    type Key: BackendSortable + Ord;
+
+    fn to_key(self) -> Self::Key;
+    fn from_key(key: Self::Key) -> Self;
+
+    /// Returns `data` as its keys if `to_key` only reinterprets the bits,
+    /// so [`sort`](crate::sort) can sort it in place without a key buffer.
+    #[inline]
+    fn as_keys(_data: &mut [Self]) -> Option<&mut [Self::Key]> {
+        None
+    }
+}
+
+/// Implements [`SimdSortKey`] for a tuple struct with a single field by
+/// sorting on that field, e.g. `impl_simd_sort_key!(Timestamp, i64)` for
+/// `struct Timestamp(i64)`.
+#[macro_export]
+macro_rules! impl_simd_sort_key {
+    ($name: ty, $field: ty) => {
+        impl $crate::SimdSortKey for $name {
+            type Key = <$field as $crate::SimdSortKey>::Key;
+
+            #[inline]
+            fn to_key(self) -> Self::Key {
+                <$field as $crate::SimdSortKey>::to_key(self.0)
+            }
+
+            #[inline]
+            fn from_key(key: Self::Key) -> Self {
+                Self(<$field as $crate::SimdSortKey>::from_key(key))
+            }
+        }
+    };
+}
+
+macro_rules! impl_integer_key {
+    ($key: ty, $($ty: ty),*) => {
+        $(
+            impl SimdSortKey for $ty {
+                type Key = $key;
+
+                #[inline]
+                fn to_key(self) -> Self::Key {
+                    self as $key
+                }
+
+                #[inline]
+                fn from_key(key: Self::Key) -> Self {
+                    key as $ty
+                }
+
+                #[inline]
+                fn as_keys(data: &mut [Self]) -> Option<&mut [Self::Key]> {
+                    if mem::size_of::<$ty>() != mem::size_of::<$key>()
+                        || mem::align_of::<$ty>() != mem::align_of::<$key>()
+                    {
+                        return None;
+                    }
+                    // This is safe since an integer as wide as the key has
+                    // the same bits as its key
+                    Some(unsafe {
+                        slice::from_raw_parts_mut(data.as_mut_ptr() as *mut $key, data.len())
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_key!(i64, i8, i16, i32, i64, isize);
+impl_integer_key!(u64, u8, u16, u32, u64, usize);
+
+macro_rules! impl_float_key {
+    ($ty: ty, $bits: ty) => {
+        /// Sorts like `total_cmp`, so negative NaN comes first and positive
+        /// NaN last
+        impl SimdSortKey for $ty {
+            type Key = u64;
+
+            #[inline]
+            fn to_key(self) -> Self::Key {
+                const SIGN_BIT: $bits = 1 << (<$bits>::BITS - 1);
+                let bits = self.to_bits();
+                // negative values sort in reverse order of their bits
+                let key = if bits & SIGN_BIT != 0 {
+                    !bits
+                } else {
+                    bits | SIGN_BIT
+                };
+                key as u64
+            }
+
+            #[inline]
+            fn from_key(key: Self::Key) -> Self {
+                const SIGN_BIT: $bits = 1 << (<$bits>::BITS - 1);
+                let key = key as $bits;
+                if key & SIGN_BIT != 0 {
+                    <$ty>::from_bits(key & !SIGN_BIT)
+                } else {
+                    <$ty>::from_bits(!key)
+                }
+            }
+        }
+    };
+}
+
+impl_float_key!(f64, u64);
+impl_float_key!(f32, u32);
+
+impl SimdSortKey for char {
+    type Key = u64;
+
+    #[inline]
+    fn to_key(self) -> Self::Key {
+        self as u64
+    }
+
+    #[inline]
+    fn from_key(key: Self::Key) -> Self {
+        char::from_u32(key as u32).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SimdSortKey;
+    use crate::bit_64::test::random_u64;
+    use crate::sort;
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    #[repr(transparent)]
+    struct Price(f64);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    #[repr(transparent)]
+    struct Timestamp(i64);
+
+    impl_simd_sort_key!(Price, f64);
+    impl_simd_sort_key!(Timestamp, i64);
+
+    #[test]
+    fn test_float_keys_round_trip() {
+        let values = [
+            f64::NEG_INFINITY,
+            -1.5,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            2.5,
+            f64::MAX,
+            f64::INFINITY,
+        ];
+        for pair in values.windows(2) {
+            assert!(pair[0].to_key() < pair[1].to_key(), "{:?}", pair);
+        }
+        for value in values {
+            assert_eq!(f64::from_key(value.to_key()).to_bits(), value.to_bits());
+        }
+        assert!(f64::NAN.to_key() > f64::INFINITY.to_key());
+        assert!((-f64::NAN).to_key() < f64::NEG_INFINITY.to_key());
+        assert_eq!(f32::from_key((-1.25f32).to_key()), -1.25);
+        assert!((-1.25f32).to_key() < 0.5f32.to_key());
+        let signalling_nan = f32::from_bits(0x7f80_0001);
+        assert_eq!(
+            f32::from_key(signalling_nan.to_key()).to_bits(),
+            0x7f80_0001
+        );
+    }
+
+    #[test]
+    fn test_as_keys() {
+        let mut random = random_u64(100);
+        assert!(u64::as_keys(&mut random).is_some());
+        let mut signed: Vec<i64> = random.iter().map(|&x| x as i64).collect();
+        assert!(i64::as_keys(&mut signed).is_some());
+        let mut small: Vec<i32> = random.iter().map(|&x| x as i32).collect();
+        assert!(i32::as_keys(&mut small).is_none());
+        let mut floats: Vec<f64> = random.iter().map(|&x| x as f64).collect();
+        assert!(f64::as_keys(&mut floats).is_none());
+
+        let mut expected = signed.clone();
+        expected.sort_unstable();
+        sort(&mut signed);
+        assert_eq!(signed, expected);
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        for len in [0, 1, 7, 8, 9, 255, 256, 257, 1000, 10_000] {
+            let random = random_u64(len);
+
+            let mut timestamps: Vec<Timestamp> =
+                random.iter().map(|&x| Timestamp(x as i64)).collect();
+            let mut expected = timestamps.clone();
+            expected.sort_unstable();
+            sort(&mut timestamps);
+            assert_eq!(timestamps, expected, "len {}", len);
+
+            let mut prices: Vec<Price> = random
+                .iter()
+                .map(|&x| Price((x as i64) as f64 / 1e3))
+                .collect();
+            let mut expected = prices.clone();
+            expected.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+            sort(&mut prices);
+            assert_eq!(prices, expected, "len {}", len);
+
+            let mut small: Vec<i16> = random.iter().map(|&x| x as i16).collect();
+            let mut expected = small.clone();
+            expected.sort_unstable();
+            sort(&mut small);
+            assert_eq!(small, expected, "len {}", len);
+
+            let mut chars: Vec<char> = random
+                .iter()
+                .filter_map(|&x| char::from_u32(x as u32 % 0x11000))
+                .collect();
+            let mut expected = chars.clone();
+            expected.sort_unstable();
+            sort(&mut chars);
+            assert_eq!(chars, expected, "len {}", len);
+        }
+    }
+}
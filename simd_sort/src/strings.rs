@@ -0,0 +1,187 @@
+use crate::platform::{selected_backend, sort_128bit_with, SIGN_BIT};
+
+/// Groups shorter than this are sorted by comparing the remaining bytes
+const MIN_KEY_SORT_LEN: usize = 32;
+
+/// Bytes of a string packed into each key
+const PREFIX_LEN: usize = 8;
+
+/// Sorts `data` in ascending byte-wise lexicographic order, which for `str`
+/// is also the order of `Ord`. Equal strings keep their order.
+///
+/// The first 8 bytes of every string are packed big-endian into a `u64` and
+/// sorted together with the index of the string as a 128-bit key. Runs of
+/// strings with the same prefix are then sorted the same way on their next
+/// 8 bytes, until fewer than 32 of them are left and they are compared
+/// directly.
+pub fn sort_strings<T: AsRef<[u8]>>(data: &mut [T]) {
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    sort_indices(data, &mut order);
+    apply_permutation(data, &mut order);
+}
+
+/// Returns the 8 bytes of `bytes` from `depth` on as a big-endian `u64`,
+/// padded with zeros
+fn prefix_key(bytes: &[u8], depth: usize) -> u64 {
+    let rest = &bytes[depth.min(bytes.len())..];
+    let len = rest.len().min(PREFIX_LEN);
+    let mut prefix = [0; PREFIX_LEN];
+    prefix[..len].copy_from_slice(&rest[..len]);
+    u64::from_be_bytes(prefix)
+}
+
+/// Sorts the indices in `order` by the strings of `data` they point to
+fn sort_indices<T: AsRef<[u8]>>(data: &[T], order: &mut [usize]) {
+    let backend = selected_backend::<i64>();
+    let (mut hi, mut lo) = (Vec::new(), Vec::new());
+    // ranges of `order` whose strings share their first `depth` bytes, kept
+    // on a stack so long common prefixes don't recurse deeply
+    let mut pending = vec![(0, order.len(), 0)];
+    while let Some((start, end, depth)) = pending.pop() {
+        let group = &mut order[start..end];
+        if group.len() < MIN_KEY_SORT_LEN {
+            group.sort_by(|&a, &b| data[a].as_ref()[depth..].cmp(&data[b].as_ref()[depth..]));
+            continue;
+        }
+
+        hi.clear();
+        lo.clear();
+        for &i in group.iter() {
+            hi.push((prefix_key(data[i].as_ref(), depth) ^ SIGN_BIT) as i64);
+            lo.push(i as i64);
+        }
+        // ties are broken by the index, which keeps equal strings in order
+        sort_128bit_with(backend, &mut hi, &mut lo);
+        for (slot, &i) in group.iter_mut().zip(&lo) {
+            *slot = i as usize;
+        }
+
+        let next = depth + PREFIX_LEN;
+        let mut run_start = 0;
+        while run_start < group.len() {
+            let key = hi[run_start];
+            let run_len = hi[run_start..].iter().take_while(|&&k| k == key).count();
+            let run = &mut group[run_start..run_start + run_len];
+            if run_len > 1 {
+                // strings ending before `next` are prefixes of the longer
+                // ones of the run and only differ from each other by length
+                run.sort_by_key(|&i| data[i].as_ref().len().min(next));
+                let ended = run.partition_point(|&i| data[i].as_ref().len() < next);
+                if run_len - ended > 1 {
+                    pending.push((start + run_start + ended, start + run_start + run_len, next));
+                }
+            }
+            run_start += run_len;
+        }
+    }
+}
+
+/// Moves the element at `order[i]` to position `i` for every `i`, leaving
+/// `order` as the identity
+fn apply_permutation<T>(data: &mut [T], order: &mut [usize]) {
+    for start in 0..order.len() {
+        let mut current = start;
+        while order[current] != current {
+            let source = order[current];
+            order[current] = current;
+            if source == start {
+                break;
+            }
+            data.swap(current, source);
+            current = source;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bit_64::test::random_u64;
+
+    /// Strings over a small alphabet including zero bytes, with many shared
+    /// prefixes and strings that are prefixes of others
+    fn random_strings(len: usize) -> Vec<Vec<u8>> {
+        let random = random_u64(len);
+        let common = b"https://example.com/path/";
+        random
+            .iter()
+            .map(|&x| {
+                let mut bytes = common[..(x % 26) as usize].to_vec();
+                let mut state = x;
+                for _ in 0..(x >> 8) % 20 {
+                    bytes.push(b"\0ab"[(state % 3) as usize]);
+                    state /= 3;
+                }
+                bytes
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_prefix_key() {
+        assert_eq!(prefix_key(b"", 0), 0);
+        assert_eq!(prefix_key(b"ab", 0), 0x6162 << 48);
+        assert_eq!(prefix_key(b"0123456789", 8), 0x3839 << 48);
+        assert_eq!(prefix_key(b"0123456789", 16), 0);
+        assert!(prefix_key(b"\xff", 0) > prefix_key(b"\x7f\xff", 0));
+    }
+
+    #[test]
+    fn test_sort_strings() {
+        for len in [0, 1, 7, 31, 32, 33, 100, 1000, 10_000] {
+            let data = random_strings(len);
+            let mut expected = data.clone();
+            expected.sort();
+
+            let mut owned = data.clone();
+            sort_strings(&mut owned);
+            assert_eq!(owned, expected, "len {}", len);
+
+            let mut slices: Vec<&[u8]> = data.iter().map(|s| s.as_slice()).collect();
+            sort_strings(&mut slices);
+            assert_eq!(slices, expected, "len {}", len);
+        }
+    }
+
+    #[test]
+    fn test_sort_strings_str() {
+        let words: Vec<String> = random_u64(5000)
+            .into_iter()
+            .map(|x| format!("{:x}", x % 100_000).repeat((x >> 40) as usize % 4))
+            .collect();
+        let mut expected = words.clone();
+        expected.sort();
+        let mut strs: Vec<&str> = words.iter().map(|s| s.as_str()).collect();
+        sort_strings(&mut strs);
+        assert_eq!(strs, expected);
+    }
+
+    #[test]
+    fn test_sort_strings_stable() {
+        let data: Vec<(usize, Vec<u8>)> = random_strings(3000).into_iter().enumerate().collect();
+        let mut keyed: Vec<KeyedBytes> = data.iter().map(|(i, s)| KeyedBytes(*i, s)).collect();
+        sort_strings(&mut keyed);
+        let mut expected = data.clone();
+        expected.sort_by(|a, b| a.1.cmp(&b.1));
+        let sorted: Vec<usize> = keyed.iter().map(|k| k.0).collect();
+        let expected: Vec<usize> = expected.iter().map(|k| k.0).collect();
+        assert_eq!(sorted, expected);
+    }
+
+    struct KeyedBytes<'a>(usize, &'a [u8]);
+
+    impl AsRef<[u8]> for KeyedBytes<'_> {
+        fn as_ref(&self) -> &[u8] {
+            self.1
+        }
+    }
+
+    #[test]
+    fn test_apply_permutation() {
+        let mut data = vec!['a', 'b', 'c', 'd', 'e'];
+        let mut order = vec![3, 0, 4, 1, 2];
+        apply_permutation(&mut data, &mut order);
+        assert_eq!(data, ['d', 'a', 'e', 'b', 'c']);
+        assert_eq!(order, [0, 1, 2, 3, 4]);
+    }
+}
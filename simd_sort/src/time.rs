@@ -0,0 +1,259 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::platform::{selected_backend, sort_128bit_with, BackendSortable, SIGN_BIT};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// Returns the nanoseconds of `duration` if they fit in a `u64`, which holds
+/// durations of up to about 584 years. The key preserves the order.
+pub fn duration_key_u64(duration: Duration) -> Option<u64> {
+    u64::try_from(duration.as_nanos()).ok()
+}
+
+/// Inverse of [`duration_key_u64`]
+pub fn duration_from_key_u64(key: u64) -> Duration {
+    Duration::from_nanos(key)
+}
+
+/// Returns the nanoseconds of `duration`, which preserve the order of every
+/// duration
+pub fn duration_key(duration: Duration) -> u128 {
+    duration.as_nanos()
+}
+
+/// Inverse of [`duration_key`].
+///
+/// # Panics
+///
+/// Panics if the seconds of `key` do not fit in a `u64`.
+pub fn duration_from_key(key: u128) -> Duration {
+    let secs = u64::try_from(key / NANOS_PER_SEC as u128).expect("duration key out of range");
+    Duration::new(secs, (key % NANOS_PER_SEC as u128) as u32)
+}
+
+/// Returns the signed nanoseconds of `time` since the unix epoch, which
+/// preserve the order
+pub fn system_time_key(time: SystemTime) -> i128 {
+    let (secs, nanos) = system_time_parts(time);
+    secs as i128 * NANOS_PER_SEC as i128 + nanos as i128
+}
+
+/// Inverse of [`system_time_key`].
+///
+/// # Panics
+///
+/// Panics if the key is out of the range of `SystemTime` on this platform.
+pub fn system_time_from_key(key: i128) -> SystemTime {
+    let secs =
+        i64::try_from(key.div_euclid(NANOS_PER_SEC as i128)).expect("system time key out of range");
+    system_time_from_parts(secs, key.rem_euclid(NANOS_PER_SEC as i128) as u32)
+}
+
+/// Sorts `data` in ascending order.
+///
+/// If all durations fit in a `u64` of nanoseconds they are sorted as `u64`,
+/// otherwise as pairs of seconds and nanoseconds on the 128-bit sort.
+pub fn sort_durations(data: &mut [Duration]) {
+    let keys: Option<Vec<u64>> = data.iter().map(|&d| duration_key_u64(d)).collect();
+    if let Some(mut keys) = keys {
+        u64::sort_with(selected_backend::<u64>(), &mut keys);
+        for (duration, key) in data.iter_mut().zip(keys) {
+            *duration = duration_from_key_u64(key);
+        }
+        return;
+    }
+    let (mut hi, mut lo): (Vec<i64>, Vec<i64>) = data
+        .iter()
+        .map(|d| ((d.as_secs() ^ SIGN_BIT) as i64, d.subsec_nanos() as i64))
+        .unzip();
+    sort_128bit_with(selected_backend::<i64>(), &mut hi, &mut lo);
+    for (duration, (hi, lo)) in data.iter_mut().zip(hi.into_iter().zip(lo)) {
+        *duration = Duration::new(hi as u64 ^ SIGN_BIT, lo as u32);
+    }
+}
+
+/// Returns the indices that sort `data` in ascending order. Equal durations
+/// keep their order.
+pub fn argsort_durations(data: &[Duration]) -> Vec<usize> {
+    argsort_parts(data.len(), |i| {
+        let duration = data[i];
+        (
+            (duration.as_secs() ^ SIGN_BIT) as i64,
+            duration.subsec_nanos(),
+        )
+    })
+}
+
+/// Sorts `data` in ascending order as pairs of seconds and nanoseconds since
+/// the unix epoch on the 128-bit sort.
+pub fn sort_system_times(data: &mut [SystemTime]) {
+    let (mut hi, mut lo): (Vec<i64>, Vec<i64>) = data
+        .iter()
+        .map(|&time| {
+            let (secs, nanos) = system_time_parts(time);
+            (secs, nanos as i64)
+        })
+        .unzip();
+    sort_128bit_with(selected_backend::<i64>(), &mut hi, &mut lo);
+    for (time, (hi, lo)) in data.iter_mut().zip(hi.into_iter().zip(lo)) {
+        *time = system_time_from_parts(hi, lo as u32);
+    }
+}
+
+/// Returns the indices that sort `data` in ascending order. Equal times keep
+/// their order.
+pub fn argsort_system_times(data: &[SystemTime]) -> Vec<usize> {
+    argsort_parts(data.len(), |i| system_time_parts(data[i]))
+}
+
+/// Sorts the indices `0..len` by the seconds and nanoseconds `parts` returns
+/// for them.
+///
+/// The seconds are the high half of a 128-bit key and the low half holds the
+/// nanoseconds above the index, so ties are broken by the index and the
+/// result is stable. Slices too long for a 32-bit index fall back to the
+/// stable sort of the standard library.
+fn argsort_parts(len: usize, parts: impl Fn(usize) -> (i64, u32)) -> Vec<usize> {
+    if u32::try_from(len).is_err() {
+        let mut indices: Vec<usize> = (0..len).collect();
+        indices.sort_by_key(|&i| parts(i));
+        return indices;
+    }
+    let (mut hi, mut lo): (Vec<i64>, Vec<i64>) = (0..len)
+        .map(|i| {
+            let (secs, nanos) = parts(i);
+            (secs, ((nanos as u64) << 32 | i as u64) as i64)
+        })
+        .unzip();
+    sort_128bit_with(selected_backend::<i64>(), &mut hi, &mut lo);
+    lo.into_iter().map(|lo| lo as u32 as usize).collect()
+}
+
+/// Splits `time` into whole seconds since the unix epoch, rounded down, and
+/// the nanoseconds past them
+fn system_time_parts(time: SystemTime) -> (i64, u32) {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => (after.as_secs() as i64, after.subsec_nanos()),
+        Err(before) => {
+            let before = before.duration();
+            let secs = -(before.as_secs() as i64);
+            match before.subsec_nanos() {
+                0 => (secs, 0),
+                nanos => (secs - 1, NANOS_PER_SEC - nanos),
+            }
+        }
+    }
+}
+
+/// Inverse of [`system_time_parts`]
+fn system_time_from_parts(secs: i64, nanos: u32) -> SystemTime {
+    let time = if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    };
+    time + Duration::from_nanos(nanos as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bit_64::test::random_u64;
+
+    fn random_durations(len: usize, max_secs: u64) -> Vec<Duration> {
+        random_u64(len)
+            .into_iter()
+            .map(|x| Duration::new(x % max_secs, (x >> 32) as u32 % NANOS_PER_SEC))
+            .collect()
+    }
+
+    fn expected_argsort<T: Ord>(data: &[T]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..data.len()).collect();
+        indices.sort_by_key(|&i| &data[i]);
+        indices
+    }
+
+    #[test]
+    fn test_keys_round_trip() {
+        let durations = [
+            Duration::ZERO,
+            Duration::new(0, 1),
+            Duration::new(1, 0),
+            Duration::new(1, NANOS_PER_SEC - 1),
+            Duration::from_nanos(u64::MAX),
+            Duration::MAX,
+        ];
+        for pair in durations.windows(2) {
+            assert!(duration_key(pair[0]) < duration_key(pair[1]), "{:?}", pair);
+        }
+        for duration in durations {
+            assert_eq!(duration_from_key(duration_key(duration)), duration);
+        }
+        assert_eq!(duration_key_u64(Duration::MAX), None);
+        assert_eq!(
+            duration_from_key_u64(duration_key_u64(Duration::new(5, 7)).unwrap()),
+            Duration::new(5, 7)
+        );
+
+        let times = [
+            UNIX_EPOCH - Duration::new(10, 1),
+            UNIX_EPOCH - Duration::new(10, 0),
+            UNIX_EPOCH - Duration::new(0, 1),
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::new(0, 1),
+            UNIX_EPOCH + Duration::new(1_700_000_000, 5),
+        ];
+        for pair in times.windows(2) {
+            assert!(
+                system_time_key(pair[0]) < system_time_key(pair[1]),
+                "{:?}",
+                pair
+            );
+        }
+        for time in times {
+            assert_eq!(system_time_from_key(system_time_key(time)), time);
+        }
+    }
+
+    #[test]
+    fn test_sort_durations() {
+        for len in [0, 1, 7, 8, 9, 255, 256, 257, 1000, 10_000] {
+            for max_secs in [3, 1_000_000, u64::MAX] {
+                let data = random_durations(len, max_secs);
+                let mut expected = data.clone();
+                expected.sort_unstable();
+                let mut sorted = data.clone();
+                sort_durations(&mut sorted);
+                assert_eq!(sorted, expected, "len {}, max {}", len, max_secs);
+                assert_eq!(
+                    argsort_durations(&data),
+                    expected_argsort(&data),
+                    "len {}, max {}",
+                    len,
+                    max_secs
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_system_times() {
+        for len in [0, 1, 7, 8, 9, 255, 256, 257, 1000, 10_000] {
+            let data: Vec<SystemTime> = random_durations(len, 4_000_000_000)
+                .into_iter()
+                .map(|d| UNIX_EPOCH - Duration::from_secs(2_000_000_000) + d)
+                .collect();
+            let mut expected = data.clone();
+            expected.sort_unstable();
+            let mut sorted = data.clone();
+            sort_system_times(&mut sorted);
+            assert_eq!(sorted, expected, "len {}", len);
+            assert_eq!(
+                argsort_system_times(&data),
+                expected_argsort(&data),
+                "len {}",
+                len
+            );
+        }
+    }
+}
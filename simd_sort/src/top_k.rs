@@ -0,0 +1,238 @@
+use crate::platform::{selected_backend, Backend, BackendSortable};
+
+/// Values buffered at least before the buffer is cut down to `k` values
+const MIN_BUFFER_LEN: usize = 1024;
+
+/// Collects the `k` largest or smallest values of a stream of chunks.
+///
+/// Incoming values are partitioned around the `k`-th best value seen so far
+/// and only the ones that can still be in the result are buffered. When the
+/// buffer grows past `max(2 * k, 1024)` values it is cut down to the best `k`
+/// with a quickselect, which also tightens the threshold. `f64` values must
+/// not be NaN.
+#[derive(Debug, Clone)]
+pub struct TopK<T: BackendSortable> {
+    k: usize,
+    largest: bool,
+    backend: Backend,
+    values: Vec<T>,
+    /// The `k`-th best value so far, known after the first compaction
+    threshold: Option<T>,
+}
+
+impl<T: BackendSortable> TopK<T> {
+    /// Collects the `k` largest values
+    pub fn largest(k: usize) -> Self {
+        Self::largest_with(selected_backend::<T>(), k)
+    }
+
+    /// Collects the `k` smallest values
+    pub fn smallest(k: usize) -> Self {
+        Self::smallest_with(selected_backend::<T>(), k)
+    }
+
+    /// Collects the `k` largest values with the given backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    pub fn largest_with(backend: Backend, k: usize) -> Self {
+        Self::new(backend, k, true)
+    }
+
+    /// Collects the `k` smallest values with the given backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not available on this target or cpu, or
+    /// does not support this type.
+    pub fn smallest_with(backend: Backend, k: usize) -> Self {
+        Self::new(backend, k, false)
+    }
+
+    fn new(backend: Backend, k: usize, largest: bool) -> Self {
+        assert!(
+            backend.is_available(),
+            "{:?} backend is not available",
+            backend
+        );
+        TopK {
+            k,
+            largest,
+            backend,
+            values: Vec::new(),
+            threshold: None,
+        }
+    }
+
+    /// Number of values the result holds at most
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Adds a single value
+    pub fn push(&mut self, value: T) {
+        self.extend_from_slice(&[value]);
+    }
+
+    /// Adds all values of `chunk`
+    pub fn extend_from_slice(&mut self, chunk: &[T]) {
+        if self.k == 0 {
+            return;
+        }
+        let limit = self.buffer_limit();
+        for piece in chunk.chunks(limit) {
+            let start = self.values.len();
+            self.values.extend_from_slice(piece);
+            if let Some(threshold) = self.threshold {
+                let new = &mut self.values[start..];
+                let below = T::partition_with(self.backend, new, threshold);
+                let kept = if self.largest {
+                    let len = new.len();
+                    new.copy_within(below.., 0);
+                    len - below
+                } else {
+                    below
+                };
+                self.values.truncate(start + kept);
+            }
+            if self.values.len() > limit {
+                self.compact();
+            }
+        }
+    }
+
+    /// Returns the collected values sorted from best to worst, that is
+    /// descending for [`TopK::largest`] and ascending for [`TopK::smallest`]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        if self.values.len() > self.k {
+            self.compact();
+        }
+        T::sort_with(self.backend, &mut self.values);
+        if self.largest {
+            self.values.reverse();
+        }
+        self.values
+    }
+
+    fn buffer_limit(&self) -> usize {
+        (2 * self.k).max(MIN_BUFFER_LEN)
+    }
+
+    /// Cuts the buffer down to the best `k` values and updates the threshold
+    fn compact(&mut self) {
+        let len = self.values.len();
+        let rank = if self.largest {
+            len - self.k
+        } else {
+            self.k - 1
+        };
+        T::select_ranks_with(self.backend, &mut self.values, &[rank]);
+        self.threshold = Some(self.values[rank]);
+        if self.largest {
+            self.values.drain(..rank);
+        } else {
+            self.values.truncate(self.k);
+        }
+    }
+}
+
+impl<T: BackendSortable> Extend<T> for TopK<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut chunk = Vec::with_capacity(MIN_BUFFER_LEN);
+        for value in iter {
+            chunk.push(value);
+            if chunk.len() == MIN_BUFFER_LEN {
+                self.extend_from_slice(&chunk);
+                chunk.clear();
+            }
+        }
+        self.extend_from_slice(&chunk);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TopK;
+    use crate::bit_64::test::random_u64;
+    use crate::platform::{available_backends, BackendSortable};
+
+    fn expected<T: BackendSortable>(values: &[T], k: usize, largest: bool) -> Vec<T> {
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        if largest {
+            sorted.reverse();
+        }
+        sorted.truncate(k);
+        sorted
+    }
+
+    fn check_top_k<T: BackendSortable>(values: &[T]) {
+        for backend in available_backends() {
+            if !T::BACKENDS.contains(&backend) {
+                continue;
+            }
+            for k in [0, 1, 7, 64, 300, values.len(), values.len() + 5] {
+                for largest in [true, false] {
+                    for chunk_len in [1, 33, 1000, 4096] {
+                        let mut top = if largest {
+                            TopK::largest_with(backend, k)
+                        } else {
+                            TopK::smallest_with(backend, k)
+                        };
+                        for chunk in values.chunks(chunk_len) {
+                            top.extend_from_slice(chunk);
+                        }
+                        assert_eq!(
+                            top.into_sorted_vec(),
+                            expected(values, k, largest),
+                            "{:?} failed for k {}, largest {}, chunk length {}",
+                            backend,
+                            k,
+                            largest,
+                            chunk_len
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_top_k_i64() {
+        for len in [0, 1, 10, 500, 5000] {
+            let values: Vec<i64> = random_u64(len).into_iter().map(|x| x as i64).collect();
+            check_top_k(&values);
+        }
+    }
+
+    #[test]
+    fn test_top_k_u64_duplicates() {
+        let values: Vec<u64> = random_u64(5000).into_iter().map(|x| x % 20).collect();
+        check_top_k(&values);
+        check_top_k(&vec![3u64; 3000]);
+    }
+
+    #[test]
+    fn test_top_k_f64() {
+        let values: Vec<f64> = random_u64(5000)
+            .into_iter()
+            .map(|x| x as i64 as f64)
+            .collect();
+        check_top_k(&values);
+    }
+
+    #[test]
+    fn test_top_k_push_and_extend() {
+        let values: Vec<i64> = (0..3000).map(|i| (i * 7919) % 3001).collect();
+        let mut pushed = TopK::smallest(10);
+        for &value in &values {
+            pushed.push(value);
+        }
+        let mut extended = TopK::largest(10);
+        extended.extend(values.iter().copied());
+        assert_eq!(pushed.into_sorted_vec(), expected(&values, 10, false));
+        assert_eq!(extended.into_sorted_vec(), expected(&values, 10, true));
+    }
+}